@@ -381,6 +381,16 @@ pub struct FeaturesConfig {
     pub seeds: bool,
     #[serde(default, rename = "skip-lint")]
     pub skip_lint: bool,
+    /// Add the discriminator of each event to the constants of the IDL.
+    #[serde(default, rename = "event-discriminators")]
+    pub event_discriminators: bool,
+    /// Write the SQL schema of the events next to the IDL when building.
+    #[serde(default, rename = "event-sql")]
+    pub event_sql: bool,
+    /// Write the fingerprints of the events next to the IDL when building, and fail if an event
+    /// changed its layout without a version bump since the committed fingerprints.
+    #[serde(default, rename = "event-fingerprints")]
+    pub event_fingerprints: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -1426,4 +1436,14 @@ mod tests {
         let config = Config::from_str(&string).unwrap();
         assert!(!config.features.skip_lint);
     }
+
+    #[test]
+    fn parse_event_fingerprints() {
+        let config = Config::from_str(BASE_CONFIG).unwrap();
+        assert!(!config.features.event_fingerprints);
+
+        let string = BASE_CONFIG.to_owned() + "[features]\nevent-fingerprints = true";
+        let config = Config::from_str(&string).unwrap();
+        assert!(config.features.event_fingerprints);
+    }
 }
@@ -6,6 +6,8 @@ use crate::config::{
 use anchor_client::Cluster;
 use anchor_lang::idl::{IdlAccount, IdlInstruction, ERASED_AUTHORITY};
 use anchor_lang::{AccountDeserialize, AnchorDeserialize, AnchorSerialize};
+use anchor_syn::idl::fingerprint::{breaking_changes, events_fingerprints, EventFingerprints};
+use anchor_syn::idl::sql::events_sql;
 use anchor_syn::idl::types::{
     EnumFields, Idl, IdlConst, IdlErrorCode, IdlEvent, IdlType, IdlTypeDefinition,
     IdlTypeDefinitionTy,
@@ -1468,7 +1470,19 @@ fn build_cwd_verifiable(
             // Write out the JSON file.
             println!("Writing the IDL file");
             let out_file = workspace_dir.join(format!("target/idl/{}.json", idl.name));
-            write_idl(&idl, OutFile::File(out_file))?;
+            write_idl(&idl, OutFile::File(out_file.clone()))?;
+
+            // Write out the SQL schema of the events.
+            if cfg.features.event_sql {
+                println!("Writing the .sql file");
+                fs::write(out_file.with_extension("sql"), events_sql(&idl))?;
+            }
+
+            // Write out and check the fingerprints of the events.
+            if cfg.features.event_fingerprints {
+                println!("Checking the event fingerprints");
+                check_event_fingerprints(&idl, &out_file)?;
+            }
 
             // Write out the TypeScript type.
             println!("Writing the .ts file");
@@ -1771,7 +1785,15 @@ fn _build_rust_cwd(
     };
 
     // Write out the JSON file.
-    write_idl(&idl, OutFile::File(out))?;
+    write_idl(&idl, OutFile::File(out.clone()))?;
+    // Write out the SQL schema of the events.
+    if cfg.features.event_sql {
+        fs::write(out.with_extension("sql"), events_sql(&idl))?;
+    }
+    // Write out and check the fingerprints of the events.
+    if cfg.features.event_fingerprints {
+        check_event_fingerprints(&idl, &out)?;
+    }
     // Write out the TypeScript type.
     fs::write(&ts_out, rust_template::idl_ts(&idl)?)?;
     // Copy out the TypeScript type.
@@ -2488,6 +2510,7 @@ fn idl_parse(
         &*file,
         manifest.version(),
         cfg.features.seeds,
+        cfg.features.event_discriminators,
         no_docs,
         !cfg.features.skip_lint,
     )?;
@@ -2545,6 +2568,7 @@ fn generate_idl(cfg: &WithPath<Config>, skip_lint: bool, no_docs: bool) -> Resul
             "src/lib.rs",
             manifest.version(),
             cfg.features.seeds,
+            cfg.features.event_discriminators,
             no_docs,
             !(cfg.features.skip_lint || skip_lint),
         )
@@ -2556,10 +2580,18 @@ fn generate_idl_parse(
     path: impl AsRef<Path>,
     version: String,
     seeds_feature: bool,
+    event_discriminators_feature: bool,
     no_docs: bool,
     safety_checks: bool,
 ) -> Result<Idl> {
-    anchor_syn::idl::parse::file::parse(path, version, seeds_feature, no_docs, safety_checks)
+    anchor_syn::idl::parse::file::parse(
+        path,
+        version,
+        seeds_feature,
+        event_discriminators_feature,
+        no_docs,
+        safety_checks,
+    )
 }
 
 /// Generate IDL with the build method.
@@ -2568,6 +2600,11 @@ fn generate_idl_build(no_docs: bool) -> Result<Vec<Idl>> {
 
     let cfg = Config::discover(&ConfigOverride::default())?.expect("Not in workspace.");
     let seeds_feature = if cfg.features.seeds { "TRUE" } else { "FALSE" };
+    let event_discriminators_feature = if cfg.features.event_discriminators {
+        "TRUE"
+    } else {
+        "FALSE"
+    };
 
     let exit = std::process::Command::new("cargo")
         .args([
@@ -2581,6 +2618,10 @@ fn generate_idl_build(no_docs: bool) -> Result<Vec<Idl>> {
         ])
         .env("ANCHOR_IDL_BUILD_NO_DOCS", no_docs)
         .env("ANCHOR_IDL_BUILD_SEEDS_FEATURE", seeds_feature)
+        .env(
+            "ANCHOR_IDL_BUILD_EVENT_DISCRIMINATORS_FEATURE",
+            event_discriminators_feature,
+        )
         .stderr(Stdio::inherit())
         .output()
         .map_err(|e| anyhow::format_err!("{}", e.to_string()))?;
@@ -2764,6 +2805,44 @@ fn idl_fetch(cfg_override: &ConfigOverride, address: Pubkey, out: Option<String>
     write_idl(&idl, out)
 }
 
+/// File next to the `Cargo.toml` of a program that stores the committed fingerprints of its
+/// events, see the `event-fingerprints` feature.
+const EVENT_FINGERPRINTS_FILE: &str = "event-fingerprints.json";
+
+/// Writes the fingerprints of the events of `idl` next to the IDL at `idl_out`, and checks them
+/// against the committed fingerprints of the program in the current directory.
+///
+/// Fails if an event changed its layout without a version bump. Otherwise the committed
+/// fingerprints are created or updated, so that they can be committed with the change.
+fn check_event_fingerprints(idl: &Idl, idl_out: &Path) -> Result<()> {
+    let fingerprints = events_fingerprints(idl);
+    let json = serde_json::to_string_pretty(&fingerprints)? + "\n";
+    fs::write(idl_out.with_extension("fingerprints.json"), &json)?;
+
+    let committed_path = Path::new(EVENT_FINGERPRINTS_FILE);
+    if committed_path.exists() {
+        let committed: EventFingerprints =
+            serde_json::from_str(&fs::read_to_string(committed_path)?)
+                .with_context(|| format!("Failed to parse {EVENT_FINGERPRINTS_FILE}"))?;
+        let changes = breaking_changes(&committed, &fingerprints);
+        if !changes.is_empty() {
+            return Err(anyhow!(
+                "{}\nBump the versions of the events, or remove them from {} if they were never \
+                deployed.",
+                changes.join("\n"),
+                EVENT_FINGERPRINTS_FILE
+            ));
+        }
+        if committed == fingerprints {
+            return Ok(());
+        }
+    }
+
+    println!("Writing {EVENT_FINGERPRINTS_FILE}, commit it to detect breaking event changes");
+    fs::write(committed_path, json)?;
+    Ok(())
+}
+
 fn write_idl(idl: &Idl, out: OutFile) -> Result<()> {
     let idl_json = serde_json::to_string_pretty(idl)?;
     match out {
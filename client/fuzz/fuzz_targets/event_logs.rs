@@ -0,0 +1,99 @@
+//! Feeds arbitrary transaction logs to the event log parsers, which must skip the lines they
+//! can't parse instead of panicking.
+//!
+//! Run with `cargo +nightly fuzz run event_logs` from the `client/fuzz` directory.
+
+#![no_main]
+
+use anchor_client::{
+    decode_events_from_logs, handle_program_log, handle_system_log, EventParser, Execution,
+    LogEncoding, LogEventParser,
+};
+use anchor_lang::__private::base64::{engine::general_purpose::STANDARD, Engine};
+use anchor_lang::event::{decode_logged_events, decode_program_data, EventScratch};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{Discriminator, Event};
+use libfuzzer_sys::fuzz_target;
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[event]
+pub struct FixedEvent {
+    pub authority: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct DynamicEvent {
+    pub label: String,
+    pub values: Vec<u32>,
+    pub id: Option<[u8; 4]>,
+}
+
+#[event(version = 1)]
+pub struct VersionedEvent {
+    pub data: u16,
+}
+
+/// Turns the first byte of every input line into one of the log lines that the parsers handle,
+/// so that the fuzzer reaches the decoding of events and the tracking of the invoked programs
+/// without having to guess program ids and discriminators.
+fn log_line(line: &[u8]) -> String {
+    let (kind, rest) = match line.split_first() {
+        Some((kind, rest)) => (*kind, rest),
+        None => return String::new(),
+    };
+    let text = String::from_utf8_lossy(rest);
+    let event = |discriminator: &[u8]| STANDARD.encode([discriminator, rest].concat());
+    match kind % 10 {
+        0 => format!("Program {ID} invoke [{text}]"),
+        1 => format!("Program {ID} success"),
+        2 => format!("Program {ID} failed: {text}"),
+        3 => format!("Program {} invoke [2]", Pubkey::new_from_array([kind; 32])),
+        4 => format!("Program data: {text}"),
+        5 => format!("Program data: {}", STANDARD.encode(rest)),
+        6 => format!("Program data: {}", event(&FixedEvent::DISCRIMINATOR)),
+        7 => format!(
+            "Program data: {} {}",
+            event(&DynamicEvent::DISCRIMINATOR),
+            event(&[&VersionedEvent::DISCRIMINATOR[..], &[1]].concat()),
+        ),
+        8 => format!("Program log: {}", event(&DynamicEvent::DISCRIMINATOR)),
+        _ => text.into_owned(),
+    }
+}
+
+fn parse(logs: &[String], raw: &[u8]) {
+    EventParser.parse(logs, &[Instruction::new_with_bytes(ID, raw, vec![])]);
+    LogEventParser::new("Program data: ", LogEncoding::Base58).parse_logs(logs);
+    decode_events_from_logs::<FixedEvent>(logs);
+    decode_events_from_logs::<DynamicEvent>(logs);
+    decode_events_from_logs::<VersionedEvent>(logs);
+    decode_logged_events::<DynamicEvent>(logs);
+
+    let mut scratch = EventScratch::new();
+    for log in logs {
+        decode_program_data(log);
+        scratch.for_each_program_data(log, |data| {
+            let _ = DynamicEvent::try_from_event_data(data);
+        });
+        let _ = handle_program_log::<DynamicEvent>(&ID.to_string(), log);
+        handle_system_log(&ID.to_string(), log);
+    }
+    let _ = Execution::new(&mut &logs[..]);
+}
+
+fuzz_target!(|data: &[u8]| {
+    let raw_logs = String::from_utf8_lossy(data)
+        .lines()
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    parse(&raw_logs, data);
+
+    let logs = data
+        .split(|b| *b == b'\n')
+        .map(log_line)
+        .collect::<Vec<_>>();
+    parse(&logs, data);
+});
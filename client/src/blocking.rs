@@ -1,6 +1,6 @@
 use crate::{
-    ClientError, Config, EventContext, EventUnsubscriber, Program, ProgramAccountsIterator,
-    RequestBuilder,
+    ClientError, Config, EventContext, EventSubscription, EventUnsubscriber, Program,
+    ProgramAccountsIterator, RequestBuilder, SubscriptionConfig,
 };
 use anchor_lang::{prelude::Pubkey, AccountDeserialize, Discriminator};
 use solana_client::{rpc_config::RpcSendTransactionConfig, rpc_filter::RpcFilterType};
@@ -21,6 +21,16 @@ impl<'a> EventUnsubscriber<'a> {
     }
 }
 
+impl<'a> EventSubscription<'a> {
+    /// Stops the subscription and waits for the handler to process the buffered events.
+    ///
+    /// Returns the last error if the subscription stopped after running out of attempts to
+    /// reconnect.
+    pub fn shutdown(self) -> Result<(), ClientError> {
+        self.runtime_handle.block_on(self.shutdown_internal())
+    }
+}
+
 impl<C: Deref<Target = impl Signer> + Clone> Program<C> {
     pub fn new(program_id: Pubkey, cfg: Config<C>) -> Result<Self, ClientError> {
         let rt: tokio::runtime::Runtime = Builder::new_multi_thread().enable_all().build()?;
@@ -55,6 +65,11 @@ impl<C: Deref<Target = impl Signer> + Clone> Program<C> {
         self.rt.block_on(self.accounts_lazy_internal(filters))
     }
 
+    /// Subscribe to program logs.
+    ///
+    /// Events that fail to deserialize are skipped, use [`Program::subscribe`] to handle them.
+    ///
+    /// Returns an [`EventUnsubscriber`] to unsubscribe and close connection gracefully.
     pub fn on<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
         &self,
         f: impl Fn(&EventContext, T) + Send + 'static,
@@ -68,6 +83,72 @@ impl<C: Deref<Target = impl Signer> + Clone> Program<C> {
             _lifetime_marker: PhantomData,
         })
     }
+
+    /// Subscribe to program logs, only passing the events of type `T` for which `filter`
+    /// returns `true` to `f`, e.g. to match the value of a field marked with `#[index]`:
+    ///
+    /// ```ignore
+    /// program.on_filtered::<Transfer>(move |t| t.from == my_key, |ctx, t| { /* ... */ })
+    /// ```
+    ///
+    /// **NOTE:** Filtering happens on the client after the events are deserialized, since logs
+    /// can't be filtered by their content on the server. This doesn't reduce the bandwidth of the
+    /// subscription.
+    ///
+    /// Returns an [`EventUnsubscriber`] to unsubscribe and close connection gracefully.
+    pub fn on_filtered<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
+        &self,
+        filter: impl Fn(&T) -> bool + Send + 'static,
+        f: impl Fn(&EventContext, T) + Send + 'static,
+    ) -> Result<EventUnsubscriber, ClientError> {
+        self.on(move |ctx, event| {
+            if filter(&event) {
+                f(ctx, event)
+            }
+        })
+    }
+
+    /// Subscribe to program logs, passing the events of type `T` to `f` along with the slot and
+    /// signature of their transaction.
+    ///
+    /// Events that match the discriminator of `T` but fail to deserialize are passed to `f` as
+    /// errors. The subscription is re-established if the websocket connection drops.
+    ///
+    /// Returns an [`EventUnsubscriber`] to unsubscribe and close connection gracefully.
+    pub fn subscribe<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
+        &self,
+        f: impl Fn(&EventContext, Result<T, ClientError>) + Send + 'static,
+    ) -> Result<EventUnsubscriber, ClientError> {
+        let (handle, rx) = self.rt.block_on(self.subscribe_internal(f))?;
+
+        Ok(EventUnsubscriber {
+            handle,
+            rx,
+            runtime_handle: self.rt.handle(),
+            _lifetime_marker: PhantomData,
+        })
+    }
+
+    /// Subscribes to the events of the program, decoded with `decode`, and passes them to
+    /// `handler` with the slot and signature of their transaction. The subscription reconnects
+    /// with exponential backoff when the connection drops.
+    ///
+    /// Usually called through [`subscribe_events!`](crate::subscribe_events), which decodes the
+    /// events into an event enum.
+    pub fn subscribe_events<E, D, F>(
+        &self,
+        config: SubscriptionConfig,
+        decode: D,
+        handler: F,
+    ) -> EventSubscription
+    where
+        E: Send + 'static,
+        D: Fn(&[u8]) -> Option<E> + Send + 'static,
+        F: FnMut(E, u64, Signature) + Send + 'static,
+    {
+        self.rt
+            .block_on(self.subscribe_events_internal(config, decode, handler))
+    }
 }
 
 impl<'a, C: Deref<Target = impl Signer> + Clone> RequestBuilder<'a, C> {
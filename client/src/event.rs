@@ -0,0 +1,723 @@
+use crate::PROGRAM_DATA;
+use anchor_lang::__private::base64;
+use anchor_lang::event::{
+    decompress_event_data, split_event_ix_data, split_event_ix_data_with, EventIxTagOrder,
+};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::Event;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use solana_sdk::bs58;
+use solana_transaction_status::{
+    EncodedTransaction, EncodedTransactionWithStatusMeta, UiInnerInstructions, UiInstruction,
+    UiLoadedAddresses, UiMessage, UiParsedInstruction, UiTransaction,
+};
+use std::str::FromStr;
+
+/// How an event returned by [`EventParser::parse`] was emitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventSource {
+    /// Logged with [`emit!`](anchor_lang::prelude::emit) in the `Program data:` logs, which
+    /// RPCs may truncate.
+    Log,
+    /// Emitted with
+    /// [`emit_cpi!`](https://docs.rs/anchor-lang/latest/anchor_lang/macro.emit_cpi.html) as a
+    /// self-CPI instruction, which is stored in the transaction metadata and is therefore more
+    /// reliable.
+    Cpi,
+}
+
+/// Parses the raw events of a transaction, regardless of whether they were emitted with
+/// [`emit!`](anchor_lang::prelude::emit) or
+/// [`emit_cpi!`](https://docs.rs/anchor-lang/latest/anchor_lang/macro.emit_cpi.html).
+///
+/// Events are returned as the id of the program that emitted them, the serialized event, i.e.
+/// the discriminator followed by the payload, so that they can be matched against the
+/// [`Discriminator`](anchor_lang::Discriminator) of the expected event types, and the
+/// [`EventSource`] of the event. Programs that emit the same logical event both ways can be
+/// deduplicated by preferring [`EventSource::Cpi`].
+///
+/// ```ignore
+/// let events = EventParser.parse(&logs, &inner_ixs);
+/// for (program_id, data, source) in events {
+///     if program_id == my_program::ID && data.starts_with(&MyEvent::DISCRIMINATOR) {
+///         let event = MyEvent::try_from_event_data(&data)?;
+///     }
+/// }
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EventParser;
+
+impl EventParser {
+    /// Parses the events in the given transaction logs and inner instructions.
+    ///
+    /// Events logged with `Program data:` are attributed to the program being executed when
+    /// they were logged, and are returned in log order. Self-CPI event instructions, i.e.
+    /// instructions whose data is prefixed with [`EVENT_IX_TAG_LE`], follow in instruction
+    /// order. Logs and instructions that are not events are skipped.
+    ///
+    /// The sequence numbers of events emitted with `#[event_cpi(sequence)]` are not part of
+    /// the returned data, use [`split_event_ix_data`] on the instruction data to get them.
+    ///
+    /// [`EVENT_IX_TAG_LE`]: anchor_lang::event::EVENT_IX_TAG_LE
+    pub fn parse(
+        &self,
+        logs: &[String],
+        cpi_ixs: &[Instruction],
+    ) -> Vec<(Pubkey, Vec<u8>, EventSource)> {
+        let log_events = self
+            .parse_logs(logs)
+            .into_iter()
+            .map(|(program_id, data)| (program_id, data, EventSource::Log));
+        let cpi_events = self
+            .parse_cpi_ixs(cpi_ixs)
+            .into_iter()
+            .map(|(program_id, data)| (program_id, data, EventSource::Cpi));
+        log_events.chain(cpi_events).collect()
+    }
+
+    /// Parses the events logged with `Program data:` in the given transaction logs, see
+    /// [`LogEventParser`] for other log conventions.
+    pub fn parse_logs(&self, logs: &[String]) -> Vec<(Pubkey, Vec<u8>)> {
+        LogEventParser::default().parse_logs(logs)
+    }
+
+    /// Parses the self-CPI event instructions in the given instructions.
+    pub fn parse_cpi_ixs(&self, cpi_ixs: &[Instruction]) -> Vec<(Pubkey, Vec<u8>)> {
+        self.parse_cpi_ixs_with(cpi_ixs, EventIxTagOrder::LittleEndian)
+    }
+
+    /// Same as [`parse_cpi_ixs`](Self::parse_cpi_ixs), but accepts the event instruction tag in
+    /// the given byte order.
+    ///
+    /// [`emit_cpi!`](https://docs.rs/anchor-lang/latest/anchor_lang/macro.emit_cpi.html) always
+    /// writes [`EVENT_IX_TAG_LE`], other orders are only useful for instruction data produced by
+    /// tooling that assumed [`EVENT_IX_TAG_BE`].
+    ///
+    /// [`EVENT_IX_TAG_LE`]: anchor_lang::event::EVENT_IX_TAG_LE
+    /// [`EVENT_IX_TAG_BE`]: anchor_lang::event::EVENT_IX_TAG_BE
+    pub fn parse_cpi_ixs_with(
+        &self,
+        cpi_ixs: &[Instruction],
+        order: EventIxTagOrder,
+    ) -> Vec<(Pubkey, Vec<u8>)> {
+        cpi_ixs
+            .iter()
+            .filter_map(|ix| {
+                split_event_ix_data_with(&ix.data, order)
+                    .map(|(_, data)| (ix.program_id, data.to_vec()))
+            })
+            .collect()
+    }
+}
+
+/// Encoding of the events in the logs parsed by [`LogEventParser`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogEncoding {
+    /// Base64, which is what `sol_log_data` logs.
+    Base64,
+    /// Base58, e.g. for log pipelines that re-encode the data of events.
+    Base58,
+}
+
+impl Default for LogEncoding {
+    fn default() -> Self {
+        Self::Base64
+    }
+}
+
+/// Parses events logged with a configurable prefix and encoding, e.g. for custom validators or
+/// log pipelines that don't follow the `Program data: <base64>` convention of `sol_log_data`.
+///
+/// The default parser uses that convention, which is what [`EventParser::parse_logs`] does. As
+/// with `Program data:` logs, a log line can hold multiple whitespace separated events, and
+/// events are attributed to the program being executed when they were logged.
+///
+/// ```ignore
+/// let parser = LogEventParser::new("Program event: ", LogEncoding::Base58);
+/// for (program_id, data) in parser.parse_logs(&logs) {
+///     // ...
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEventParser {
+    prefix: String,
+    encoding: LogEncoding,
+}
+
+impl Default for LogEventParser {
+    fn default() -> Self {
+        Self::new(PROGRAM_DATA, LogEncoding::Base64)
+    }
+}
+
+impl LogEventParser {
+    /// Creates a parser of the events logged with the given prefix, including any separator
+    /// before the event data, and encoding.
+    pub fn new(prefix: impl Into<String>, encoding: LogEncoding) -> Self {
+        Self {
+            prefix: prefix.into(),
+            encoding,
+        }
+    }
+
+    /// Returns the prefix of the event logs.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Returns the encoding of the events.
+    pub fn encoding(&self) -> LogEncoding {
+        self.encoding
+    }
+
+    /// Parses the events in the given transaction logs, returned in log order as the id of the
+    /// program that logged them and the decoded event. Segments that can't be decoded are
+    /// skipped.
+    pub fn parse_logs(&self, logs: &[String]) -> Vec<(Pubkey, Vec<u8>)> {
+        let mut stack = vec![];
+        let mut events = vec![];
+        for log in logs {
+            if let Some(data) = log.strip_prefix(self.prefix.as_str()) {
+                let program_id = match stack.last() {
+                    Some(program_id) => *program_id,
+                    None => continue,
+                };
+                // Events emitted together with `emit_many!` share a single log line
+                events.extend(
+                    data.split_whitespace()
+                        .filter_map(|segment| self.decode(segment))
+                        .map(|data| (program_id, data)),
+                );
+            } else if let Some(program_id) = parse_invoke_log(log) {
+                stack.push(program_id);
+            } else if is_exit_log(log) {
+                stack.pop();
+            }
+        }
+
+        events
+    }
+
+    fn decode(&self, segment: &str) -> Option<Vec<u8>> {
+        match self.encoding {
+            LogEncoding::Base64 => STANDARD.decode(segment).ok(),
+            LogEncoding::Base58 => bs58::decode(segment).into_vec().ok(),
+        }
+    }
+}
+
+/// Decodes the events of type `E` logged with [`emit!`](anchor_lang::prelude::emit) in the
+/// given transaction logs, e.g. the `meta.logMessages` of a confirmed transaction.
+///
+/// Events are returned in log order. `Program data:` segments that are not events of type
+/// `E`, i.e. whose discriminator or version doesn't match, or that fail to deserialize, are
+/// skipped. Events of all programs are decoded, use [`EventParser`] to tell apart the programs
+/// that emitted them.
+///
+/// ```ignore
+/// let events = decode_events_from_logs::<MyEvent>(&logs);
+/// ```
+pub fn decode_events_from_logs<E: Event>(logs: &[String]) -> Vec<E> {
+    EventParser
+        .parse_logs(logs)
+        .into_iter()
+        .filter_map(|(_, data)| E::try_from_event_data(&data).ok())
+        .collect()
+}
+
+/// Decodes the events of type `E` logged with
+/// [`emit_compressed!`](anchor_lang::prelude::emit_compressed) in the given transaction logs,
+/// which is the counterpart of [`decode_events_from_logs`] for compressed events.
+///
+/// Events compressed with the codecs of Anchor are decompressed with
+/// [`decompress_event_data`](anchor_lang::event::decompress_event_data). Segments that are not
+/// compressed events of type `E`, or that fail to decompress or deserialize, are skipped.
+///
+/// ```ignore
+/// let events = decode_compressed_events_from_logs::<MyEvent>(&logs);
+/// ```
+pub fn decode_compressed_events_from_logs<E: Event>(logs: &[String]) -> Vec<E> {
+    EventParser
+        .parse_logs(logs)
+        .into_iter()
+        .filter_map(|(_, data)| {
+            let data = decompress_event_data::<E>(&data).ok()?;
+            E::try_from_event_data(&data).ok()
+        })
+        .collect()
+}
+
+/// Decodes the events of `program_id` emitted with
+/// [`emit_cpi!`](https://docs.rs/anchor-lang/latest/anchor_lang/macro.emit_cpi.html) from the
+/// inner instructions of a transaction, e.g. one fetched with `getTransaction`. This is the
+/// counterpart of [`decode_events_from_logs`] for events stored in the transaction metadata,
+/// which can't be truncated like logs.
+///
+/// Event instructions are the inner instructions of `program_id` whose only account is the
+/// event authority and whose data starts with [`EVENT_IX_TAG_LE`]. The metadata doesn't tell
+/// whether an account signed an inner instruction, but the program rejects event instructions
+/// that aren't signed by its event authority, so only successful transactions are decoded and
+/// no events are returned for failed ones.
+///
+/// Events are returned in instruction order, as the 8-byte discriminator and the payload that
+/// follows it. The payload of events with a longer `discriminator_len` starts with the rest of
+/// their discriminator. The sequence numbers of events emitted with `#[event_cpi(sequence)]`
+/// are skipped. Binary, `json` and `jsonParsed` encodings are supported.
+///
+/// ```ignore
+/// for (discriminator, payload) in decode_cpi_events(&tx, &my_program::ID) {
+///     if discriminator == MyEvent::DISCRIMINATOR {
+///         let event = MyEvent::try_from_event_data(&[&discriminator[..], &payload].concat())?;
+///     }
+/// }
+/// ```
+///
+/// [`EVENT_IX_TAG_LE`]: anchor_lang::event::EVENT_IX_TAG_LE
+pub fn decode_cpi_events(
+    tx: &EncodedTransactionWithStatusMeta,
+    program_id: &Pubkey,
+) -> Vec<([u8; 8], Vec<u8>)> {
+    let meta = match &tx.meta {
+        Some(meta) if meta.err.is_none() => meta,
+        _ => return vec![],
+    };
+    let inner_ixs: Option<&Vec<UiInnerInstructions>> = meta.inner_instructions.as_ref().into();
+    let loaded_addresses: Option<&UiLoadedAddresses> = meta.loaded_addresses.as_ref().into();
+    let account_keys = transaction_account_keys(&tx.transaction, loaded_addresses);
+
+    inner_ixs
+        .into_iter()
+        .flatten()
+        .flat_map(|inner_ixs| &inner_ixs.instructions)
+        .filter_map(|ix| {
+            let (ix_program_id, accounts_len, data) = match ix {
+                UiInstruction::Compiled(ix) => (
+                    account_keys.get(usize::from(ix.program_id_index)).copied(),
+                    ix.accounts.len(),
+                    &ix.data,
+                ),
+                UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(ix)) => (
+                    Pubkey::from_str(&ix.program_id).ok(),
+                    ix.accounts.len(),
+                    &ix.data,
+                ),
+                // Only instructions of known programs, e.g. the system program, are parsed
+                UiInstruction::Parsed(UiParsedInstruction::Parsed(_)) => return None,
+            };
+            if ix_program_id.as_ref() != Some(program_id) || accounts_len != 1 {
+                return None;
+            }
+
+            let data = bs58::decode(data).into_vec().ok()?;
+            let (_, event) = split_event_ix_data(&data)?;
+            let discriminator = event.get(..8)?.try_into().ok()?;
+            Some((discriminator, event[8..].to_vec()))
+        })
+        .collect()
+}
+
+/// Returns the account keys of a transaction in the order that compiled instructions refer to
+/// them, i.e. the static keys followed by the keys loaded from address lookup tables.
+fn transaction_account_keys(
+    tx: &EncodedTransaction,
+    loaded_addresses: Option<&UiLoadedAddresses>,
+) -> Vec<Pubkey> {
+    let parse_keys = |keys: &[String]| {
+        keys.iter()
+            .filter_map(|key| Pubkey::from_str(key).ok())
+            .collect::<Vec<_>>()
+    };
+    let mut account_keys = match tx {
+        EncodedTransaction::Json(UiTransaction {
+            message: UiMessage::Raw(message),
+            ..
+        }) => parse_keys(&message.account_keys),
+        // Parsed messages already include the loaded keys
+        EncodedTransaction::Json(UiTransaction {
+            message: UiMessage::Parsed(message),
+            ..
+        }) => {
+            return message
+                .account_keys
+                .iter()
+                .filter_map(|key| Pubkey::from_str(&key.pubkey).ok())
+                .collect()
+        }
+        tx => tx
+            .decode()
+            .map(|tx| tx.message.static_account_keys().to_vec())
+            .unwrap_or_default(),
+    };
+    if let Some(loaded_addresses) = loaded_addresses {
+        account_keys.extend(parse_keys(&loaded_addresses.writable));
+        account_keys.extend(parse_keys(&loaded_addresses.readonly));
+    }
+
+    account_keys
+}
+
+/// Returns the program id of a `Program <id> invoke [<depth>]` log.
+fn parse_invoke_log(log: &str) -> Option<Pubkey> {
+    let (program_id, rest) = log.strip_prefix("Program ")?.split_once(' ')?;
+    if !rest.starts_with("invoke [") {
+        return None;
+    }
+    Pubkey::from_str(program_id).ok()
+}
+
+/// Returns whether the log is a `Program <id> success` or `Program <id> failed: ..` log.
+fn is_exit_log(log: &str) -> bool {
+    log.strip_prefix("Program ")
+        .and_then(|log| log.split_once(' '))
+        .map(|(program_id, rest)| {
+            (rest == "success" || rest.starts_with("failed"))
+                && Pubkey::from_str(program_id).is_ok()
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::event::{EVENT_IX_TAG_BE, EVENT_IX_TAG_LE, EVENT_SEQUENCE_IX_TAG_LE};
+    use anchor_lang::prelude::*;
+    use anchor_lang::solana_program::hash::Hash;
+    use anchor_lang::solana_program::instruction::AccountMeta;
+    use anchor_lang::solana_program::message::MessageHeader;
+    use anchor_lang::Discriminator;
+    use solana_sdk::instruction::CompiledInstruction;
+    use solana_sdk::transaction::TransactionError;
+    use solana_transaction_status::{
+        InnerInstruction, InnerInstructions, TransactionStatusMeta, UiRawMessage,
+    };
+
+    #[event]
+    pub struct LogEvent {
+        pub data: u64,
+    }
+
+    #[event]
+    pub struct CpiEvent {
+        pub data: u64,
+    }
+
+    fn program_data(events: &[&[u8]]) -> String {
+        let segments = events
+            .iter()
+            .map(|event| STANDARD.encode(event))
+            .collect::<Vec<_>>();
+        format!("{PROGRAM_DATA}{}", segments.join(" "))
+    }
+
+    fn cpi_event_ix(program_id: Pubkey, event: &[u8]) -> Instruction {
+        Instruction::new_with_bytes(
+            program_id,
+            &[&EVENT_IX_TAG_LE[..], event].concat(),
+            vec![AccountMeta::new_readonly(Pubkey::new_unique(), true)],
+        )
+    }
+
+    #[test]
+    fn parse_cpi_ixs_with_tag_order() {
+        let program_id = Pubkey::new_unique();
+        let event = CpiEvent { data: 5 }.data();
+        let be_ix = Instruction::new_with_bytes(
+            program_id,
+            &[&EVENT_IX_TAG_BE[..], &event].concat(),
+            vec![],
+        );
+        let cpi_ixs = [cpi_event_ix(program_id, &event), be_ix];
+
+        assert_eq!(
+            EventParser.parse_cpi_ixs(&cpi_ixs),
+            vec![(program_id, event.clone())]
+        );
+        assert_eq!(
+            EventParser.parse_cpi_ixs_with(&cpi_ixs, EventIxTagOrder::BigEndian),
+            vec![(program_id, event.clone())]
+        );
+        assert_eq!(
+            EventParser.parse_cpi_ixs_with(&cpi_ixs, EventIxTagOrder::Either),
+            vec![(program_id, event.clone()), (program_id, event)]
+        );
+    }
+
+    #[test]
+    fn parse_mixed_events() {
+        let program_id = Pubkey::new_unique();
+        let other_program_id = Pubkey::new_unique();
+
+        let log_event = LogEvent { data: 1 }.data();
+        let other_event = LogEvent { data: 2 }.data();
+        let batched_events = [LogEvent { data: 3 }.data(), LogEvent { data: 4 }.data()];
+        let cpi_event = CpiEvent { data: 5 }.data();
+
+        let logs = vec![
+            format!("Program {program_id} invoke [1]"),
+            "Program log: Instruction: Emit".to_string(),
+            program_data(&[&log_event]),
+            format!("Program {other_program_id} invoke [2]"),
+            program_data(&[&other_event]),
+            format!("Program {other_program_id} consumed 1000 of 190000 compute units"),
+            format!("Program {other_program_id} success"),
+            format!("Program {program_id} invoke [2]"),
+            format!("Program {program_id} consumed 1000 of 180000 compute units"),
+            format!("Program {program_id} success"),
+            program_data(&[&batched_events[0], &batched_events[1]]),
+            format!("Program {program_id} consumed 10000 of 200000 compute units"),
+            format!("Program {program_id} success"),
+        ];
+        let cpi_ixs = vec![
+            Instruction::new_with_bytes(other_program_id, &[1, 2, 3], vec![]),
+            cpi_event_ix(program_id, &cpi_event),
+        ];
+
+        let events = EventParser.parse(&logs, &cpi_ixs);
+        assert_eq!(
+            events,
+            vec![
+                (program_id, log_event, EventSource::Log),
+                (other_program_id, other_event, EventSource::Log),
+                (program_id, batched_events[0].clone(), EventSource::Log),
+                (program_id, batched_events[1].clone(), EventSource::Log),
+                (program_id, cpi_event, EventSource::Cpi),
+            ]
+        );
+
+        let log_events = events
+            .iter()
+            .filter(|(_, data, _)| LogEvent::discriminator_matches(data))
+            .map(|(_, data, _)| LogEvent::try_from_event_data(data).unwrap().data)
+            .collect::<Vec<_>>();
+        assert_eq!(log_events, vec![1, 2, 3, 4]);
+
+        let cpi_events = events
+            .iter()
+            .filter(|(_, data, _)| CpiEvent::discriminator_matches(data))
+            .map(|(_, data, _)| CpiEvent::try_from_event_data(data).unwrap().data)
+            .collect::<Vec<_>>();
+        assert_eq!(cpi_events, vec![5]);
+    }
+
+    #[test]
+    fn parse_failed_cpi() {
+        let program_id = Pubkey::new_unique();
+        let other_program_id = Pubkey::new_unique();
+        let event = LogEvent { data: 1 }.data();
+
+        let logs = vec![
+            format!("Program {program_id} invoke [1]"),
+            format!("Program {other_program_id} invoke [2]"),
+            format!("Program {other_program_id} failed: custom program error: 0x1"),
+            program_data(&[&event]),
+        ];
+        assert_eq!(
+            EventParser.parse(&logs, &[]),
+            vec![(program_id, event, EventSource::Log)]
+        );
+    }
+
+    #[test]
+    fn decode_events() {
+        let program_id = Pubkey::new_unique();
+        let logs = vec![
+            format!("Program {program_id} invoke [1]"),
+            program_data(&[&LogEvent { data: 1 }.data()]),
+            program_data(&[&CpiEvent { data: 2 }.data(), &LogEvent { data: 3 }.data()]),
+            program_data(&[&LogEvent::DISCRIMINATOR]),
+            format!("{PROGRAM_DATA}not base64!"),
+            format!("Program {program_id} success"),
+        ];
+
+        let events = decode_events_from_logs::<LogEvent>(&logs);
+        assert_eq!(
+            events.iter().map(|event| event.data).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        let events = decode_events_from_logs::<CpiEvent>(&logs);
+        assert_eq!(
+            events.iter().map(|event| event.data).collect::<Vec<_>>(),
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn decode_compressed_events() {
+        use anchor_lang::event::{compress_event_data, EventCodec, IdentityCodec, ZeroRunCodec};
+
+        let program_id = Pubkey::new_unique();
+        let logs = vec![
+            format!("Program {program_id} invoke [1]"),
+            program_data(&[&compress_event_data(&LogEvent { data: 1 }, &ZeroRunCodec)]),
+            program_data(&[&LogEvent { data: 2 }.data()]),
+            program_data(&[&compress_event_data(&LogEvent { data: 3 }, &IdentityCodec)]),
+            program_data(&[&compress_event_data(&CpiEvent { data: 4 }, &ZeroRunCodec)]),
+            program_data(&[&[&LogEvent::DISCRIMINATOR[..], &[ZeroRunCodec::ID, 0]].concat()]),
+            format!("Program {program_id} success"),
+        ];
+
+        let events = decode_compressed_events_from_logs::<LogEvent>(&logs);
+        assert_eq!(
+            events.iter().map(|event| event.data).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+    }
+
+    /// Returns a successful transaction with the given account keys and inner instructions.
+    fn encoded_tx(
+        account_keys: &[Pubkey],
+        inner_ixs: Vec<CompiledInstruction>,
+    ) -> EncodedTransactionWithStatusMeta {
+        let message = UiRawMessage {
+            header: MessageHeader::default(),
+            account_keys: account_keys.iter().map(|key| key.to_string()).collect(),
+            recent_blockhash: Hash::default().to_string(),
+            instructions: vec![],
+            address_table_lookups: None,
+        };
+        let meta = TransactionStatusMeta {
+            inner_instructions: Some(vec![InnerInstructions {
+                index: 0,
+                instructions: inner_ixs
+                    .into_iter()
+                    .map(|instruction| InnerInstruction {
+                        instruction,
+                        stack_height: Some(2),
+                    })
+                    .collect(),
+            }]),
+            ..Default::default()
+        };
+        EncodedTransactionWithStatusMeta {
+            transaction: EncodedTransaction::Json(UiTransaction {
+                signatures: vec![],
+                message: UiMessage::Raw(message),
+            }),
+            meta: Some(meta.into()),
+            version: None,
+        }
+    }
+
+    #[test]
+    fn decode_cpi_events_from_tx() {
+        let program_id = Pubkey::new_unique();
+        let other_program_id = Pubkey::new_unique();
+        let event_authority = Pubkey::new_unique();
+        // [program, event authority, other program, other account]
+        let account_keys = [
+            program_id,
+            event_authority,
+            other_program_id,
+            Pubkey::new_unique(),
+        ];
+
+        let event_data = |event: &[u8]| [&EVENT_IX_TAG_LE[..], event].concat();
+        let cpi_event = CpiEvent { data: 1 }.data();
+        let inner_ixs = vec![
+            CompiledInstruction::new_from_raw_parts(0, event_data(&cpi_event), vec![1]),
+            // Another program
+            CompiledInstruction::new_from_raw_parts(2, event_data(&cpi_event), vec![1]),
+            // Not only the event authority
+            CompiledInstruction::new_from_raw_parts(0, event_data(&cpi_event), vec![1, 3]),
+            // Not an event instruction
+            CompiledInstruction::new_from_raw_parts(0, cpi_event.clone(), vec![1]),
+            CompiledInstruction::new_from_raw_parts(
+                0,
+                event_data(&LogEvent { data: 2 }.data()),
+                vec![1],
+            ),
+            // Emitted with `#[event_cpi(sequence)]`
+            CompiledInstruction::new_from_raw_parts(
+                0,
+                [
+                    &EVENT_SEQUENCE_IX_TAG_LE[..],
+                    &7u64.to_le_bytes(),
+                    &CpiEvent { data: 3 }.data(),
+                ]
+                .concat(),
+                vec![1],
+            ),
+        ];
+        let tx = encoded_tx(&account_keys, inner_ixs);
+
+        let events = decode_cpi_events(&tx, &program_id);
+        assert_eq!(
+            events,
+            vec![
+                (CpiEvent::DISCRIMINATOR, 1u64.to_le_bytes().to_vec()),
+                (LogEvent::DISCRIMINATOR, 2u64.to_le_bytes().to_vec()),
+                (CpiEvent::DISCRIMINATOR, 3u64.to_le_bytes().to_vec()),
+            ]
+        );
+        assert!(decode_cpi_events(&tx, &Pubkey::new_unique()).is_empty());
+
+        // Events of failed transactions can't be trusted
+        let mut failed_tx = tx;
+        failed_tx.meta.as_mut().unwrap().err = Some(TransactionError::AccountInUse);
+        assert!(decode_cpi_events(&failed_tx, &program_id).is_empty());
+    }
+
+    #[test]
+    fn parse_logs_with_encoding() {
+        let program_id = Pubkey::new_unique();
+        let events = [LogEvent { data: 1 }.data(), LogEvent { data: 2 }.data()];
+        let invoke_log = format!("Program {program_id} invoke [1]");
+        let success_log = format!("Program {program_id} success");
+
+        let default_parser = LogEventParser::default();
+        assert_eq!(default_parser.prefix(), PROGRAM_DATA);
+        assert_eq!(default_parser.encoding(), LogEncoding::Base64);
+        let logs = vec![
+            invoke_log.clone(),
+            program_data(&[&events[0], &events[1]]),
+            success_log.clone(),
+        ];
+        assert_eq!(
+            default_parser.parse_logs(&logs),
+            vec![
+                (program_id, events[0].clone()),
+                (program_id, events[1].clone())
+            ]
+        );
+        assert_eq!(
+            default_parser.parse_logs(&logs),
+            EventParser.parse_logs(&logs)
+        );
+
+        let base58 = |event: &[u8]| bs58::encode(event).into_string();
+        let parser = LogEventParser::new("Program event: ", LogEncoding::Base58);
+        let logs = vec![
+            invoke_log,
+            format!(
+                "Program event: {} {}",
+                base58(&events[0]),
+                base58(&events[1])
+            ),
+            // Base64 encoded and not base58
+            format!("Program event: {}", STANDARD.encode([0xff; 8])),
+            // Other prefix
+            program_data(&[&events[0]]),
+            success_log,
+        ];
+        assert_eq!(
+            parser.parse_logs(&logs),
+            vec![
+                (program_id, events[0].clone()),
+                (program_id, events[1].clone())
+            ]
+        );
+        assert!(default_parser.parse_logs(&logs[..2]).is_empty());
+    }
+
+    #[test]
+    fn parse_invalid_logs() {
+        let logs = vec![
+            program_data(&[b"outside of any program"]),
+            format!("Program {} invoke [1]", Pubkey::new_unique()),
+            format!("{PROGRAM_DATA}not base64!"),
+        ];
+        assert!(EventParser.parse(&logs, &[]).is_empty());
+    }
+}
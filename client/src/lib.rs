@@ -96,7 +96,7 @@ use thiserror::Error;
 use tokio::{
     runtime::Handle,
     sync::{
-        mpsc::{unbounded_channel, UnboundedReceiver},
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
         RwLock,
     },
     task::JoinHandle,
@@ -104,10 +104,20 @@ use tokio::{
 
 pub use anchor_lang;
 pub use cluster::Cluster;
+pub use event::{
+    decode_compressed_events_from_logs, decode_cpi_events, decode_events_from_logs, EventParser,
+    EventSource, LogEncoding, LogEventParser,
+};
 pub use solana_client;
 pub use solana_sdk;
+pub use solana_transaction_status;
+#[cfg(feature = "async")]
+pub use subscription::EventStream;
+pub use subscription::{EventSubscription, SubscriptionConfig};
 
 mod cluster;
+mod event;
+mod subscription;
 
 #[cfg(not(feature = "async"))]
 mod blocking;
@@ -205,7 +215,15 @@ pub struct EventUnsubscriber<'a> {
 
 impl<'a> EventUnsubscriber<'a> {
     async fn unsubscribe_internal(mut self) {
-        if let Some(unsubscribe) = self.rx.recv().await {
+        // Wait for the first subscription, then close the channel so that the subscription isn't
+        // re-established after unsubscribing.
+        let mut unsubscribe = self.rx.recv().await;
+        self.rx.close();
+        // Only the latest subscription is active if it was re-established after a drop
+        while let Ok(latest) = self.rx.try_recv() {
+            unsubscribe = Some(latest);
+        }
+        if let Some(unsubscribe) = unsubscribe {
             unsubscribe().await;
         }
 
@@ -321,6 +339,26 @@ impl<C: Deref<Target = impl Signer> + Clone> Program<C> {
             UnboundedReceiver<UnsubscribeFn>,
         ),
         ClientError,
+    > {
+        self.subscribe_internal(move |ctx, event: Result<T, ClientError>| match event {
+            Ok(event) => f(ctx, event),
+            Err(_e) => {
+                #[cfg(feature = "debug")]
+                println!("Unable to parse log: {_e}");
+            }
+        })
+        .await
+    }
+
+    async fn subscribe_internal<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
+        &self,
+        f: impl Fn(&EventContext, Result<T, ClientError>) + Send + 'static,
+    ) -> Result<
+        (
+            JoinHandle<Result<(), ClientError>>,
+            UnboundedReceiver<UnsubscribeFn>,
+        ),
+        ClientError,
     > {
         self.init_sub_client_if_needed().await?;
         let (tx, rx) = unbounded_channel::<_>();
@@ -329,39 +367,100 @@ impl<C: Deref<Target = impl Signer> + Clone> Program<C> {
         };
         let program_id_str = self.program_id.to_string();
         let filter = RpcTransactionLogsFilter::Mentions(vec![program_id_str.clone()]);
+        let ws_url = self.cfg.cluster.ws_url().to_string();
 
         let lock = Arc::clone(&self.sub_client);
 
+        let mut f = f;
         let handle = tokio::spawn(async move {
-            if let Some(ref client) = *lock.read().await {
-                let (mut notifications, unsubscribe) =
-                    client.logs_subscribe(filter, config).await?;
-
-                tx.send(unsubscribe).map_err(|e| {
-                    ClientError::SolanaClientPubsubError(PubsubClientError::RequestFailed {
-                        message: "Unsubscribe failed".to_string(),
-                        reason: e.to_string(),
-                    })
-                })?;
-
-                while let Some(logs) = notifications.next().await {
-                    let ctx = EventContext {
-                        signature: logs.value.signature.parse().unwrap(),
-                        slot: logs.context.slot,
-                    };
-                    let events = parse_logs_response(logs, &program_id_str);
-                    for e in events {
-                        f(&ctx, e);
-                    }
+            let subscription = LogsSubscription {
+                filter,
+                config,
+                program_id_str,
+                tx,
+            };
+
+            // Connection used after the shared connection dropped
+            let mut reconnected_client: Option<PubsubClient> = None;
+            let mut reconnect_attempts = 0;
+            loop {
+                let result = match &reconnected_client {
+                    Some(client) => subscription.run(client, &mut f).await,
+                    None => match &*lock.read().await {
+                        Some(client) => subscription.run(client, &mut f).await,
+                        None => Err(ClientError::SolanaClientPubsubError(
+                            PubsubClientError::ConnectionClosed("No connection".to_string()),
+                        )),
+                    },
+                };
+
+                match result {
+                    // Unsubscribed
+                    Ok(false) => return Ok(()),
+                    Ok(true) if subscription.tx.is_closed() => return Ok(()),
+                    // The connection dropped after subscribing successfully
+                    Ok(true) => reconnect_attempts = 0,
+                    Err(e) if reconnect_attempts >= MAX_RECONNECT_ATTEMPTS => return Err(e),
+                    Err(_) => {}
                 }
+
+                reconnect_attempts += 1;
+                tokio::time::sleep(RECONNECT_DELAY * reconnect_attempts).await;
+                reconnected_client = PubsubClient::new(&ws_url).await.ok();
             }
-            Ok::<(), ClientError>(())
         });
 
         Ok((handle, rx))
     }
 }
 
+/// Maximum number of consecutive attempts to re-establish a dropped logs subscription.
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+/// Delay before reconnecting, multiplied by the number of the attempt.
+const RECONNECT_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+struct LogsSubscription {
+    filter: RpcTransactionLogsFilter,
+    config: RpcTransactionLogsConfig,
+    program_id_str: String,
+    tx: UnboundedSender<UnsubscribeFn>,
+}
+
+impl LogsSubscription {
+    /// Subscribes to the logs with the given connection and passes the events to `f` until the
+    /// subscription ends.
+    ///
+    /// Returns `Ok(false)` if the subscription was unsubscribed before it started, and `Ok(true)`
+    /// once the subscription stream ends, either because it was unsubscribed or because the
+    /// connection dropped.
+    async fn run<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
+        &self,
+        client: &PubsubClient,
+        // Exclusive reference so that the future is `Send` without requiring `Sync` callbacks
+        f: &mut impl Fn(&EventContext, Result<T, ClientError>),
+    ) -> Result<bool, ClientError> {
+        let (mut notifications, unsubscribe) = client
+            .logs_subscribe(self.filter.clone(), self.config.clone())
+            .await?;
+        if self.tx.send(unsubscribe).is_err() {
+            return Ok(false);
+        }
+
+        while let Some(logs) = notifications.next().await {
+            let ctx = EventContext {
+                signature: logs.value.signature.parse().unwrap(),
+                slot: logs.context.slot,
+            };
+            let events = parse_logs_response(logs, &self.program_id_str);
+            for e in events {
+                f(&ctx, e);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
 /// Iterator with items of type (Pubkey, T). Used to lazily deserialize account structs.
 /// Wrapper type hides the inner type from usages so the implementation can be changed.
 pub struct ProgramAccountsIterator<T> {
@@ -401,16 +500,9 @@ pub fn handle_program_log<T: anchor_lang::Event + anchor_lang::AnchorDeserialize
             }
         };
 
-        let mut slice: &[u8] = &borsh_bytes[..];
-        let disc: [u8; 8] = {
-            let mut disc = [0; 8];
-            disc.copy_from_slice(&borsh_bytes[..8]);
-            slice = &slice[8..];
-            disc
-        };
         let mut event = None;
-        if disc == T::discriminator() {
-            let e: T = anchor_lang::AnchorDeserialize::deserialize(&mut slice)
+        if borsh_bytes.starts_with(T::EVENT_DISCRIMINATOR) {
+            let e = T::try_from_event_data(&borsh_bytes)
                 .map_err(|e| ClientError::LogParseError(e.to_string()))?;
             event = Some(e);
         }
@@ -444,8 +536,10 @@ pub struct Execution {
 
 impl Execution {
     pub fn new(logs: &mut &[String]) -> Result<Self, ClientError> {
-        let l = &logs[0];
-        *logs = &logs[1..];
+        let (l, rest) = logs
+            .split_first()
+            .ok_or_else(|| ClientError::LogParseError("empty logs".to_string()))?;
+        *logs = rest;
 
         let re = Regex::new(r"^Program (.*) invoke.*$").unwrap();
         let c = re
@@ -646,19 +740,26 @@ impl<'a, C: Deref<Target = impl Signer> + Clone> RequestBuilder<'a, C> {
 fn parse_logs_response<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
     logs: RpcResponse<RpcLogsResponse>,
     program_id_str: &str,
-) -> Vec<T> {
+) -> Vec<Result<T, ClientError>> {
     let mut logs = &logs.value.logs[..];
-    let mut events: Vec<T> = Vec::new();
+    let mut events: Vec<Result<T, ClientError>> = Vec::new();
     if !logs.is_empty() {
         if let Ok(mut execution) = Execution::new(&mut logs) {
             for l in logs {
+                // The program that was invoked first returned, the rest of the logs can't be
+                // attributed to any program.
+                if execution.stack.is_empty() {
+                    break;
+                }
                 // Parse the log.
                 let (event, new_program, did_pop) = {
                     if program_id_str == execution.program() {
-                        handle_program_log(program_id_str, l).unwrap_or_else(|e| {
-                            println!("Unable to parse log: {e}");
-                            std::process::exit(1);
-                        })
+                        match handle_program_log(program_id_str, l) {
+                            Ok((event, new_program, did_pop)) => {
+                                (event.map(Ok), new_program, did_pop)
+                            }
+                            Err(e) => (Some(Err(e)), None, false),
+                        }
                     } else {
                         let (program, did_pop) = handle_system_log(program_id_str, l);
                         (None, program, did_pop)
@@ -685,6 +786,21 @@ fn parse_logs_response<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use anchor_lang::__private::base64::{engine::general_purpose::STANDARD, Engine};
+    use anchor_lang::prelude::*;
+    use anchor_lang::Event;
+    use solana_client::rpc_response::RpcResponseContext;
+
+    #[event]
+    pub struct MyEvent {
+        pub data: u64,
+    }
+
+    #[event(correlated)]
+    pub struct MyCorrelatedEvent {
+        pub data: u64,
+    }
+
     #[test]
     fn new_execution() {
         let mut logs: &[String] =
@@ -696,6 +812,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn new_execution_empty_logs() {
+        let mut logs: &[String] = &[];
+        assert!(matches!(
+            Execution::new(&mut logs),
+            Err(ClientError::LogParseError(_))
+        ));
+    }
+
     #[test]
     fn handle_system_log_pop() {
         let log = "Program 7Y8VDzehoewALqJfyxZYMgYCnMTCDhWuGfJKUvjYWATw success";
@@ -711,4 +836,64 @@ mod tests {
         assert_eq!(program, None);
         assert!(!did_pop);
     }
+
+    #[test]
+    fn parse_logs_response_errors() {
+        let program_id = Pubkey::new_unique();
+        let event = MyEvent { data: 1 }.data();
+        let logs = RpcResponse {
+            context: RpcResponseContext::new(1),
+            value: RpcLogsResponse {
+                signature: Signature::default().to_string(),
+                err: None,
+                logs: vec![
+                    format!("Program {program_id} invoke [1]"),
+                    format!("{PROGRAM_DATA}{}", STANDARD.encode(&event)),
+                    format!("{PROGRAM_DATA}{}", STANDARD.encode(&event[..10])),
+                    format!("Program {program_id} success"),
+                ],
+            },
+        };
+
+        let events = parse_logs_response::<MyEvent>(logs, &program_id.to_string());
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].as_ref().unwrap().data, 1);
+        assert!(matches!(events[1], Err(ClientError::LogParseError(_))));
+    }
+
+    #[test]
+    fn parse_logs_response_after_return() {
+        let program_id = Pubkey::new_unique();
+        let event = MyEvent { data: 1 }.data();
+        let logs = RpcResponse {
+            context: RpcResponseContext::new(1),
+            value: RpcLogsResponse {
+                signature: Signature::default().to_string(),
+                err: None,
+                logs: vec![
+                    format!("Program {program_id} invoke [1]"),
+                    format!("Program {program_id} success"),
+                    format!("Program {program_id} success"),
+                    format!("{PROGRAM_DATA}{}", STANDARD.encode(&event)),
+                ],
+            },
+        };
+
+        let events = parse_logs_response::<MyEvent>(logs, &program_id.to_string());
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn handle_program_log_correlated_event() {
+        let mut event = vec![];
+        MyCorrelatedEvent { data: 1 }.correlated_data_into(&mut event, Some([7; 16]));
+        let log = format!("{PROGRAM_DATA}{}", STANDARD.encode(&event));
+
+        let (parsed, _, _) = handle_program_log::<MyCorrelatedEvent>("asdf", &log).unwrap();
+        assert_eq!(parsed.unwrap().data, 1);
+        assert_eq!(
+            MyCorrelatedEvent::correlation_id_from_event_data(&event).unwrap(),
+            Some([7; 16])
+        );
+    }
 }
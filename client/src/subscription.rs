@@ -0,0 +1,527 @@
+//! Event subscriptions that reconnect with exponential backoff, created with
+//! [`subscribe_events!`](crate::subscribe_events), or consumed as a [`Stream`] with
+//! `Program::event_stream` of the `async` feature.
+//!
+//! [`Stream`]: futures::Stream
+
+use crate::{ClientError, EventParser, Program};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use futures::StreamExt;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_client::rpc_response::RpcLogsResponse;
+use solana_sdk::signature::{Signature, Signer};
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+
+/// Configuration of a subscription created with [`subscribe_events!`](crate::subscribe_events).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionConfig {
+    /// Maximum number of consecutive failed attempts to reconnect before the subscription stops
+    /// with the last error.
+    pub max_retries: u32,
+    /// Delay before the first attempt to reconnect, which doubles with every failed attempt.
+    pub initial_backoff: Duration,
+    /// Maximum delay between attempts to reconnect.
+    pub max_backoff: Duration,
+    /// Number of events buffered for the handler. Once the buffer is full, notifications aren't
+    /// read from the connection until the handler catches up.
+    pub channel_capacity: usize,
+}
+
+impl Default for SubscriptionConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            channel_capacity: 1024,
+        }
+    }
+}
+
+impl SubscriptionConfig {
+    /// Returns the delay before the given attempt to reconnect, starting at 1.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Subscription created with [`subscribe_events!`](crate::subscribe_events).
+///
+/// Dropping the subscription stops it without waiting for the handler, use
+/// [`shutdown`](Self::shutdown) to let the handler process the events it already received.
+pub struct EventSubscription<'a> {
+    shutdown: watch::Sender<bool>,
+    subscriber: JoinHandle<Result<(), ClientError>>,
+    handler: JoinHandle<()>,
+    #[cfg(not(feature = "async"))]
+    pub(crate) runtime_handle: &'a Handle,
+    _lifetime_marker: PhantomData<&'a Handle>,
+}
+
+impl<'a> EventSubscription<'a> {
+    /// Returns whether the subscription stopped, i.e. it was shut down or it ran out of
+    /// attempts to reconnect.
+    pub fn is_finished(&self) -> bool {
+        self.subscriber.is_finished()
+    }
+
+    pub(crate) async fn shutdown_internal(self) -> Result<(), ClientError> {
+        // The subscriber already stopped if it ran out of attempts to reconnect
+        let _ = self.shutdown.send(true);
+        let result = match self.subscriber.await {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(_) => Ok(()),
+        };
+        // The handler stops once it processed the buffered events
+        let _ = self.handler.await;
+        result
+    }
+}
+
+impl<C: Deref<Target = impl Signer> + Clone> Program<C> {
+    pub(crate) async fn subscribe_events_internal<E, D, F>(
+        &self,
+        config: SubscriptionConfig,
+        decode: D,
+        mut handler: F,
+    ) -> EventSubscription<'_>
+    where
+        E: Send + 'static,
+        D: Fn(&[u8]) -> Option<E> + Send + 'static,
+        F: FnMut(E, u64, Signature) + Send + 'static,
+    {
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let (tx, mut rx) = mpsc::channel(config.channel_capacity.max(1));
+        let subscriber = Subscriber {
+            ws_url: self.cfg.cluster.ws_url().to_string(),
+            program_id: self.program_id,
+            logs_config: RpcTransactionLogsConfig {
+                commitment: self.cfg.options,
+            },
+            config,
+            decode,
+            tx,
+            shutdown: shutdown_rx,
+        };
+
+        EventSubscription {
+            shutdown,
+            subscriber: tokio::spawn(subscriber.run()),
+            handler: tokio::spawn(async move {
+                while let Some((event, slot, signature)) = rx.recv().await {
+                    handler(event, slot, signature);
+                }
+            }),
+            #[cfg(not(feature = "async"))]
+            runtime_handle: self.rt.handle(),
+            _lifetime_marker: PhantomData,
+        }
+    }
+}
+
+struct Subscriber<E, D> {
+    ws_url: String,
+    program_id: Pubkey,
+    logs_config: RpcTransactionLogsConfig,
+    config: SubscriptionConfig,
+    decode: D,
+    tx: mpsc::Sender<(E, u64, Signature)>,
+    shutdown: watch::Receiver<bool>,
+}
+
+impl<E, D: Fn(&[u8]) -> Option<E>> Subscriber<E, D> {
+    /// (Re)connects until the subscription is shut down or runs out of attempts to reconnect.
+    async fn run(mut self) -> Result<(), ClientError> {
+        let mut failed_attempts = 0;
+        loop {
+            match self.connect().await {
+                Ok(true) => return Ok(()),
+                // The connection dropped after subscribing successfully
+                Ok(false) => failed_attempts = 0,
+                Err(e) if failed_attempts >= self.config.max_retries => return Err(e),
+                Err(_) => {}
+            }
+
+            failed_attempts += 1;
+            tokio::select! {
+                _ = self.shutdown.changed() => return Ok(()),
+                _ = tokio::time::sleep(self.config.backoff(failed_attempts)) => {}
+            }
+        }
+    }
+
+    /// Subscribes to the logs of the program with a new connection and sends the decoded events
+    /// to the handler until the connection drops. Returns whether the subscription was shut
+    /// down.
+    async fn connect(&mut self) -> Result<bool, ClientError> {
+        let client = PubsubClient::new(&self.ws_url).await?;
+        let filter = RpcTransactionLogsFilter::Mentions(vec![self.program_id.to_string()]);
+        let (mut notifications, unsubscribe) = client
+            .logs_subscribe(filter, self.logs_config.clone())
+            .await?;
+
+        loop {
+            let logs = tokio::select! {
+                _ = self.shutdown.changed() => {
+                    unsubscribe().await;
+                    return Ok(true);
+                }
+                logs = notifications.next() => match logs {
+                    Some(logs) => logs,
+                    None => return Ok(false),
+                },
+            };
+            let signature = match logs.value.signature.parse() {
+                Ok(signature) => signature,
+                Err(_) => continue,
+            };
+            for event in decode_logs(&logs.value, &self.program_id, &self.decode) {
+                // The handler only stops after the subscriber
+                if self
+                    .tx
+                    .send((event, logs.context.slot, signature))
+                    .await
+                    .is_err()
+                {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+}
+
+/// Stream of the events of type `T` of a program, created with `Program::event_stream` of the
+/// `async` feature, which yields the events with the slot and signature of their transaction.
+///
+/// Events that match the discriminator of `T` but fail to deserialize are yielded as errors,
+/// and the stream continues after them. If the subscription runs out of attempts to
+/// reconnect, the last connection error is yielded and the stream ends.
+///
+/// Events are buffered in a bounded channel of [`SubscriptionConfig::channel_capacity`]
+/// events. Events are never dropped while the stream is alive: once the buffer is full, the
+/// subscription stops reading notifications until the stream is polled again, so a slow
+/// consumer lets notifications queue up in the websocket connection, where the RPC node may
+/// eventually drop the connection, which is then re-established without the notifications it
+/// dropped. Dropping the stream unsubscribes and drops the buffered events.
+#[cfg(feature = "async")]
+pub struct EventStream<T> {
+    rx: mpsc::Receiver<(Result<T, ClientError>, u64, Signature)>,
+    subscriber: Option<JoinHandle<Result<(), ClientError>>>,
+    // Stops the subscriber when the stream is dropped
+    _shutdown: watch::Sender<bool>,
+}
+
+#[cfg(feature = "async")]
+impl<T> futures::Stream for EventStream<T> {
+    type Item = Result<(T, crate::EventContext), ClientError>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        use std::task::Poll;
+
+        if let Some((event, slot, signature)) = futures::ready!(self.rx.poll_recv(cx)) {
+            return Poll::Ready(Some(
+                event.map(|event| (event, crate::EventContext { signature, slot })),
+            ));
+        }
+
+        // The channel closes once the subscriber stopped, which yields its error, if any
+        let subscriber = match &mut self.subscriber {
+            Some(subscriber) => subscriber,
+            None => return Poll::Ready(None),
+        };
+        let result = futures::ready!(futures::FutureExt::poll_unpin(subscriber, cx));
+        self.subscriber = None;
+        match result {
+            Ok(Err(e)) => Poll::Ready(Some(Err(e))),
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            _ => Poll::Ready(None),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<C: Deref<Target = impl Signer> + Clone> Program<C> {
+    pub(crate) async fn event_stream_internal<T>(
+        &self,
+        config: SubscriptionConfig,
+    ) -> EventStream<T>
+    where
+        T: anchor_lang::Event + Send + 'static,
+    {
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let (tx, rx) = mpsc::channel(config.channel_capacity.max(1));
+        let subscriber = Subscriber {
+            ws_url: self.cfg.cluster.ws_url().to_string(),
+            program_id: self.program_id,
+            logs_config: RpcTransactionLogsConfig {
+                commitment: self.cfg.options,
+            },
+            config,
+            decode: decode_event::<T>,
+            tx,
+            shutdown: shutdown_rx,
+        };
+
+        EventStream {
+            rx,
+            subscriber: Some(tokio::spawn(subscriber.run())),
+            _shutdown: shutdown,
+        }
+    }
+}
+
+/// Decodes `data` if it's an event of type `T`, which fails if it matches the discriminator of
+/// `T` but isn't a valid event.
+#[cfg(feature = "async")]
+fn decode_event<T: anchor_lang::Event>(data: &[u8]) -> Option<Result<T, ClientError>> {
+    data.starts_with(T::EVENT_DISCRIMINATOR)
+        .then(|| T::try_from_event_data(data).map_err(Into::into))
+}
+
+/// Returns the events of `program_id` in the logs of a transaction, decoded with `decode`.
+/// Events that `decode` doesn't recognize, and the events of failed transactions, which were
+/// never emitted, are skipped.
+fn decode_logs<E>(
+    logs: &RpcLogsResponse,
+    program_id: &Pubkey,
+    decode: impl Fn(&[u8]) -> Option<E>,
+) -> Vec<E> {
+    if logs.err.is_some() {
+        return vec![];
+    }
+    EventParser
+        .parse_logs(&logs.logs)
+        .into_iter()
+        .filter(|(id, _)| id == program_id)
+        .filter_map(|(_, data)| decode(&data))
+        .collect()
+}
+
+/// Subscribes to the events of a program, which are decoded into an event enum generated with
+/// [`generate_event_enum!`](anchor_lang::prelude::generate_event_enum) and passed to a handler
+/// as `(event, slot, signature)`.
+///
+/// The subscription reconnects with exponential backoff when the connection drops, and stops
+/// after [`SubscriptionConfig::max_retries`] consecutive failed attempts. Events are buffered
+/// for the handler in a bounded channel, and events of other programs and of failed
+/// transactions are skipped. [`EventSubscription::shutdown`] stops the subscription once the
+/// handler processed the buffered events.
+///
+/// The configuration defaults to [`SubscriptionConfig::default`]:
+///
+/// ```ignore
+/// let subscription = subscribe_events!(program, ProgramEvent, |event, slot, signature| {
+///     match event {
+///         ProgramEvent::Deposit(deposit) => println!("{slot} {signature}: {}", deposit.amount),
+///         ProgramEvent::Withdraw(_) => {}
+///     }
+/// });
+///
+/// let config = SubscriptionConfig {
+///     max_retries: 10,
+///     ..Default::default()
+/// };
+/// let subscription = subscribe_events!(program, ProgramEvent, config, |event, slot, signature| {
+///     // ...
+/// });
+///
+/// // ...
+/// subscription.shutdown()?;
+/// ```
+///
+/// The first connection is attempted in the background like the reconnections. With the `async`
+/// feature, the macro and [`EventSubscription::shutdown`] return futures.
+#[macro_export]
+macro_rules! subscribe_events {
+    ($program:expr, $event_enum:ty, $handler:expr $(,)?) => {
+        $crate::subscribe_events!(
+            $program,
+            $event_enum,
+            $crate::SubscriptionConfig::default(),
+            $handler
+        )
+    };
+    ($program:expr, $event_enum:ty, $config:expr, $handler:expr $(,)?) => {
+        $program.subscribe_events($config, <$event_enum>::decode, $handler)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PROGRAM_DATA;
+    use anchor_lang::__private::base64::{engine::general_purpose::STANDARD, Engine};
+    use anchor_lang::prelude::*;
+    use anchor_lang::Event;
+    use solana_sdk::transaction::TransactionError;
+
+    #[event]
+    pub struct Deposit {
+        pub amount: u64,
+    }
+
+    generate_event_enum! {
+        pub enum ProgramEvent {
+            Deposit,
+        }
+    }
+
+    #[test]
+    fn backoff() {
+        let config = SubscriptionConfig {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            ..Default::default()
+        };
+        assert_eq!(config.backoff(1), Duration::from_millis(100));
+        assert_eq!(config.backoff(2), Duration::from_millis(200));
+        assert_eq!(config.backoff(4), Duration::from_millis(800));
+        assert_eq!(config.backoff(5), Duration::from_secs(1));
+        assert_eq!(config.backoff(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn decode_logs_of_program() {
+        let program_id = Pubkey::new_unique();
+        let other_program_id = Pubkey::new_unique();
+        let program_data = |amount: u64| {
+            format!(
+                "{PROGRAM_DATA}{}",
+                STANDARD.encode(Deposit { amount }.data())
+            )
+        };
+        let mut logs = RpcLogsResponse {
+            signature: Signature::default().to_string(),
+            err: None,
+            logs: vec![
+                format!("Program {program_id} invoke [1]"),
+                program_data(1),
+                format!("Program {other_program_id} invoke [2]"),
+                program_data(2),
+                format!("Program {other_program_id} success"),
+                format!("{PROGRAM_DATA}{}", STANDARD.encode([0; 8])),
+                program_data(3),
+                format!("Program {program_id} success"),
+            ],
+        };
+
+        let amounts = |logs: &RpcLogsResponse| {
+            decode_logs(logs, &program_id, ProgramEvent::decode)
+                .into_iter()
+                .map(|ProgramEvent::Deposit(deposit)| deposit.amount)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(amounts(&logs), vec![1, 3]);
+
+        logs.err = Some(TransactionError::AccountInUse);
+        assert!(amounts(&logs).is_empty());
+    }
+
+    /// Returns a program on a cluster that refuses connections.
+    #[cfg(not(feature = "async"))]
+    fn unreachable_program() -> crate::Program<std::rc::Rc<solana_sdk::signature::Keypair>> {
+        let cluster = crate::Cluster::Custom(
+            "http://127.0.0.1:1".to_string(),
+            "ws://127.0.0.1:1".to_string(),
+        );
+        crate::Client::new(
+            cluster,
+            std::rc::Rc::new(solana_sdk::signature::Keypair::new()),
+        )
+        .program(Pubkey::new_unique())
+        .unwrap()
+    }
+
+    #[cfg(not(feature = "async"))]
+    #[test]
+    fn stop_after_max_retries() {
+        let program = unreachable_program();
+        let config = SubscriptionConfig {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let subscription =
+            subscribe_events!(program, ProgramEvent, config, |_, _, _| { unreachable!() });
+
+        while !subscription.is_finished() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(matches!(
+            subscription.shutdown(),
+            Err(ClientError::SolanaClientPubsubError(_))
+        ));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn decode_event() {
+        let data = Deposit { amount: 1 }.data();
+        assert_eq!(
+            super::decode_event::<Deposit>(&data)
+                .unwrap()
+                .unwrap()
+                .amount,
+            1
+        );
+        assert!(super::decode_event::<Deposit>(&data[..10])
+            .unwrap()
+            .is_err());
+        assert!(super::decode_event::<Deposit>(&[0; 16]).is_none());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn event_stream_yields_connection_error() {
+        let cluster = crate::Cluster::Custom(
+            "http://127.0.0.1:1".to_string(),
+            "ws://127.0.0.1:1".to_string(),
+        );
+        let program = crate::Client::new(
+            cluster,
+            std::sync::Arc::new(solana_sdk::signature::Keypair::new()),
+        )
+        .program(Pubkey::new_unique())
+        .unwrap();
+        let config = SubscriptionConfig {
+            max_retries: 1,
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+
+        let mut stream = program.event_stream_with_config::<Deposit>(config).await;
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(ClientError::SolanaClientPubsubError(_)))
+        ));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[cfg(not(feature = "async"))]
+    #[test]
+    fn shutdown_while_reconnecting() {
+        let program = unreachable_program();
+        let config = SubscriptionConfig {
+            initial_backoff: Duration::from_secs(3600),
+            ..Default::default()
+        };
+        let subscription =
+            subscribe_events!(program, ProgramEvent, config, |_, _, _| { unreachable!() });
+
+        assert!(!subscription.is_finished());
+        assert!(subscription.shutdown().is_ok());
+    }
+}
@@ -45,6 +45,10 @@ const navigation = [
         title: 'Errors',
         href: '/docs/errors',
       },
+      {
+        title: 'Events',
+        href: '/docs/events',
+      },
       {
         title: 'Cross-Program Invocations',
         href: '/docs/cross-program-invocations',
@@ -1,68 +1,894 @@
 extern crate proc_macro;
 
 #[cfg(feature = "event-cpi")]
-use anchor_syn::parser::accounts::event_cpi::{add_event_cpi_accounts, EventAuthority};
-use quote::quote;
-use syn::parse_macro_input;
+use anchor_syn::parser::accounts::event_cpi::{
+    add_event_cpi_accounts, EventAuthority, EventCpiArgs,
+};
+use anchor_syn::parser::event::{estimate_size, MAX_LOGGED_EVENT_SIZE};
+use anchor_syn::EventArgs;
+use heck::SnakeCase;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Token};
 
 /// The event attribute allows a struct to be used with
 /// [emit!](./macro.emit.html) so that programs can log significant events in
-/// their programs that clients can subscribe to. Currently, this macro is for
-/// structs only.
+/// their programs that clients can subscribe to. This macro can be used on
+/// structs and enums.
 ///
-/// See the [`emit!` macro](emit!) for an example.
+/// See the [`emit!` macro](emit!) for an example, and the
+/// [events documentation](https://www.anchor-lang.com/docs/events) for details
+/// and examples of the arguments.
+///
+/// # Arguments
+///
+/// | Argument | Effect |
+/// |---|---|
+/// | `discriminator_len = <N>` | Use `N` bytes of the hash as the discriminator, the full `Event::EVENT_DISCRIMINATOR` |
+/// | `discriminator = [<u8>, ..]` | Use the given bytes as the discriminator |
+/// | `namespace = "<ns>"` | Hash `"<ns>:event:<Name>"` instead of `"event:<Name>"` |
+/// | `version = <N>` | Write `N` as a `u8` after the discriminator, the `VERSION` constant |
+/// | `correlated` | Write the correlation id of the `Context` as an `Option<[u8; 16]>` after the discriminator |
+/// | `idl_name = "<Name>"` | Name the event in the IDL |
+/// | `hidden` | Exclude the event from the IDL |
+/// | `via = "log" \| "cpi"` | Store how the event is emitted in the IDL |
+/// | `zero_copy` | Serialize with `bytemuck` instead of borsh |
+/// | `no_serde` | Don't derive `AnchorSerialize` and `AnchorDeserialize` |
+/// | `derive(<Trait>, ..)` | Also derive the given standard library traits |
+/// | `arbitrary` | Implement `Arbitrary` with the `arbitrary` feature of `anchor-lang` |
+/// | `sequence` | Add a `pub sequence: u64` first field |
+/// | `timestamp` | Add a `pub emitted_at: i64` last field, set from the `Clock` when it's emitted on-chain |
+/// | `discriminator_hex` | Add a `DISCRIMINATOR_HEX` constant |
+/// | `feature = "<name>"` | Compile the event out without the `<name>` feature of the crate |
+/// | `max_size = <N>` | Fail to compile if the estimated size exceeds `N` bytes |
+///
+/// # Field attributes
+///
+/// | Attribute | Effect |
+/// |---|---|
+/// | `#[index]`, `#[index(name = "..")]` | Flag the field as indexed in the IDL, see `peek_indexed_fields` |
+/// | `#[decimals(<N>)]` | Add the implied decimals of the field to the IDL |
+/// | `#[event_flatten]` | Show the fields of the field's struct in the IDL |
+/// | `#[optional_trailing]` | Decode the field as `Default` if the data ends before it |
+///
+/// ```ignore
+/// #[event(version = 2, discriminator_len = 16)]
+/// pub struct Deposited {
+///     #[index]
+///     pub owner: Pubkey,
+///     pub amount: u64,
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn event(
-    _args: proc_macro::TokenStream,
+    args: proc_macro::TokenStream,
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    let event_strct = parse_macro_input!(input as syn::ItemStruct);
+    let args = parse_macro_input!(args as EventArgs);
+    let mut event_item = parse_macro_input!(input as syn::Item);
+    if let Err(e) = anchor_syn::parser::event::check_field_types(&event_item)
+        .and_then(|_| anchor_syn::parser::event::check_lifetimes(&event_item))
+    {
+        return e.to_compile_error().into();
+    }
+
+    if args.sequence {
+        let res = match &mut event_item {
+            syn::Item::Struct(strct) => anchor_syn::parser::event::add_sequence_field(strct),
+            _ => Err(syn::Error::new_spanned(
+                &event_item,
+                "`sequence` events must be structs",
+            )),
+        };
+        if let Err(e) = res {
+            return e.to_compile_error().into();
+        }
+    }
 
-    let event_name = &event_strct.ident;
+    let optional_trailing = match &event_item {
+        syn::Item::Struct(strct) => {
+            match anchor_syn::parser::event::parse_optional_trailing(&strct.fields) {
+                Ok(optional_trailing) => optional_trailing,
+                Err(e) => return e.to_compile_error().into(),
+            }
+        }
+        _ => None,
+    };
+    if let (Some(_), syn::Item::Struct(strct)) = (optional_trailing, &event_item) {
+        // The deserialization of the event is generated, and its fields must be the last ones
+        let err = if args.timestamp || args.zero_copy || args.no_serde {
+            Some("`#[optional_trailing]` fields can't be combined with `timestamp`, `zero_copy` or `no_serde`")
+        } else if strct.generics.type_params().next().is_some() {
+            Some("`#[optional_trailing]` fields can't be used in events with type parameters")
+        } else {
+            None
+        };
+        if let Some(err) = err {
+            return syn::Error::new_spanned(&strct.ident, err)
+                .to_compile_error()
+                .into();
+        }
+    }
 
-    let discriminator: proc_macro2::TokenStream = {
-        let discriminator_preimage = format!("event:{event_name}").into_bytes();
-        let discriminator = anchor_syn::hash::hash(&discriminator_preimage);
-        format!("{:?}", &discriminator.0[..8]).parse().unwrap()
+    if args.timestamp {
+        let res = match &mut event_item {
+            syn::Item::Struct(strct) => anchor_syn::parser::event::add_timestamp_field(strct),
+            _ => Err(syn::Error::new_spanned(
+                &event_item,
+                "`timestamp` events must be structs",
+            )),
+        };
+        if let Err(e) = res {
+            return e.to_compile_error().into();
+        }
+    }
+
+    let (event_name, generics, index_warnings) = match &event_item {
+        syn::Item::Struct(strct) => (&strct.ident, &strct.generics, quote! {}),
+        syn::Item::Enum(enm) => (&enm.ident, &enm.generics, gen_enum_index_warnings(enm)),
+        _ => {
+            return syn::Error::new_spanned(
+                &event_item,
+                "`#[event]` can only be used on structs and enums",
+            )
+            .to_compile_error()
+            .into()
+        }
     };
 
-    let ret = quote! {
-        #[derive(anchor_lang::__private::EventIndex, AnchorSerialize, AnchorDeserialize)]
-        #event_strct
+    // Generic parameters, including const generics, are spliced into the impls. The
+    // discriminator only depends on the name, so all instantiations of an event share it.
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let event_discriminator = args.discriminator(event_name);
+    let discriminator: proc_macro2::TokenStream =
+        format!("{:?}", &event_discriminator[..8]).parse().unwrap();
+    let event_discriminator: proc_macro2::TokenStream =
+        format!("{event_discriminator:?}").parse().unwrap();
 
-        impl anchor_lang::Event for #event_name {
-            fn data(&self) -> Vec<u8> {
-                let mut data = Vec::with_capacity(256);
-                data.extend_from_slice(&#discriminator);
-                self.serialize(&mut data).unwrap();
-                data
+    let size_check = match gen_size_check(&event_item, event_name, &args) {
+        Ok(size_check) => size_check,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let (version_const, event_version) = match args.version {
+        Some(version) => (
+            quote! {
+                impl #impl_generics #event_name #ty_generics #where_clause {
+                    /// Schema version of the event.
+                    pub const VERSION: u8 = #version;
+                }
+            },
+            quote! { const EVENT_VERSION: Option<u8> = Some(#version); },
+        ),
+        None => (quote! {}, quote! {}),
+    };
+
+    let event_correlated = if args.correlated {
+        quote! { const EVENT_CORRELATED: bool = true; }
+    } else {
+        quote! {}
+    };
+
+    let discriminator_hex = if args.discriminator_hex {
+        let mut hex = String::with_capacity(16);
+        for byte in &args.discriminator(event_name)[..8] {
+            hex.push_str(&format!("{byte:02x}"));
+        }
+        quote! {
+            impl #impl_generics #event_name #ty_generics #where_clause {
+                /// Lowercase hex of the discriminator of the event.
+                pub const DISCRIMINATOR_HEX: &'static str = #hex;
             }
         }
+    } else {
+        quote! {}
+    };
+
+    let (zero_copy_attrs, zero_copy_data_into) = if args.zero_copy {
+        match gen_zero_copy(&event_item) {
+            Ok(zero_copy) => zero_copy,
+            Err(e) => return e.to_compile_error().into(),
+        }
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // The timestamp is only set when the event is emitted, `data_into` serializes it as is
+    let event_timestamp = if args.timestamp {
+        quote! { const EVENT_TIMESTAMP: bool = true; }
+    } else {
+        quote! {}
+    };
 
-        impl anchor_lang::Discriminator for #event_name {
+    // `zero_copy` events already derive `Copy` and `Clone`
+    let derives = args
+        .derives
+        .iter()
+        .filter(|derive| !(args.zero_copy && (*derive == "Copy" || *derive == "Clone")));
+    let derives = quote! { #[derive(#(#derives),*)] };
+
+    // Only implemented with the `arbitrary` feature so that on-chain builds don't depend on it
+    #[cfg(feature = "arbitrary")]
+    let arbitrary = if args.arbitrary {
+        gen_arbitrary(&event_item)
+    } else {
+        quote! {}
+    };
+    #[cfg(not(feature = "arbitrary"))]
+    let arbitrary = quote! {};
+
+    let (serde_derives, optional_trailing_deserialize) = match (optional_trailing, &event_item) {
+        _ if args.no_serde => (quote! {}, quote! {}),
+        (Some(first), syn::Item::Struct(strct)) => (
+            quote! { AnchorSerialize },
+            gen_optional_trailing_deserialize(strct, first),
+        ),
+        _ => (quote! { AnchorSerialize, AnchorDeserialize }, quote! {}),
+    };
+
+    let peek_indexed_fields = if args.no_serde {
+        quote! {}
+    } else {
+        gen_peek_indexed_fields(&event_item)
+    };
+
+    #[cfg(feature = "event-cpi")]
+    let cpi_event_instruction = quote! {
+        impl #impl_generics #event_name #ty_generics #where_clause {
+            /// Builds the self-CPI instruction that `emit_cpi!` invokes for the event, see
+            /// `anchor_lang::event::cpi_event_instruction`.
+            pub fn cpi_event_instruction(
+                &self,
+                program_id: anchor_lang::solana_program::pubkey::Pubkey,
+                event_authority: anchor_lang::solana_program::pubkey::Pubkey,
+            ) -> anchor_lang::solana_program::instruction::Instruction {
+                anchor_lang::event::cpi_event_instruction(self, program_id, event_authority)
+            }
+        }
+    };
+    #[cfg(not(feature = "event-cpi"))]
+    let cpi_event_instruction = quote! {};
+
+    let ret = quote! {
+        #[derive(anchor_lang::__private::EventIndex, #serde_derives)]
+        #derives
+        #zero_copy_attrs
+        #event_item
+
+        #arbitrary
+
+        #index_warnings
+
+        #size_check
+
+        #version_const
+
+        #discriminator_hex
+
+        #peek_indexed_fields
+
+        #cpi_event_instruction
+
+        #optional_trailing_deserialize
+
+        impl #impl_generics anchor_lang::Event for #event_name #ty_generics #where_clause {
+            const EVENT_DISCRIMINATOR: &'static [u8] = &#event_discriminator;
+            #event_version
+            #event_correlated
+            #event_timestamp
+            #zero_copy_data_into
+        }
+
+        impl #impl_generics anchor_lang::Discriminator for #event_name #ty_generics #where_clause {
             const DISCRIMINATOR: [u8; 8] = #discriminator;
         }
     };
 
     #[cfg(feature = "idl-build")]
-    {
-        let idl_build = anchor_syn::idl::build::gen_idl_print_function_for_event(&event_strct);
-        return proc_macro::TokenStream::from(quote! {
+    if let (syn::Item::Struct(event_strct), false) = (&event_item, args.hidden) {
+        let idl_build =
+            anchor_syn::idl::build::gen_idl_print_function_for_event(event_strct, &args);
+        #[cfg(feature = "json-schema")]
+        let json_schema = anchor_syn::idl::build::gen_json_schema_function_for_event(event_strct);
+        #[cfg(not(feature = "json-schema"))]
+        let json_schema = quote! {};
+        let ret = quote! {
             #ret
             #idl_build
-        });
+            #json_schema
+        };
+        return proc_macro::TokenStream::from(gen_feature_gate(ret, &event_item, &args));
     }
 
     #[allow(unreachable_code)]
-    proc_macro::TokenStream::from(ret)
+    proc_macro::TokenStream::from(gen_feature_gate(ret, &event_item, &args))
+}
+
+/// Gates the items generated for an `#[event(feature = "..")]` event with the feature, and
+/// generates a stub of the event for the builds without it. The stub has the fields of the
+/// event so that it can still be constructed, and inherent no-op versions of the `Event`
+/// methods the emit macros call, which serialize the event to no data. Inherent methods take
+/// precedence over trait methods, so the macros call them instead of the `Event` methods, and
+/// skip the events without data.
+fn gen_feature_gate(
+    ret: proc_macro2::TokenStream,
+    event_item: &syn::Item,
+    args: &EventArgs,
+) -> proc_macro2::TokenStream {
+    let feature = match &args.feature {
+        Some(feature) => feature,
+        None => return ret,
+    };
+    let items = match syn::parse2::<syn::File>(ret) {
+        Ok(file) => file.items,
+        Err(e) => return e.to_compile_error(),
+    };
+    let (event_name, generics) = match event_item {
+        syn::Item::Struct(strct) => (&strct.ident, &strct.generics),
+        syn::Item::Enum(enm) => (&enm.ident, &enm.generics),
+        _ => unreachable!("Events are structs or enums"),
+    };
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // `zero_copy` events derive `Copy` and `Clone` even if they're not given with `derive`
+    let mut derives = args.derives.clone();
+    if args.zero_copy {
+        for derive in ["Clone", "Copy"] {
+            if !derives.iter().any(|d| d == derive) {
+                derives.push(syn::Ident::new(derive, proc_macro2::Span::call_site()));
+            }
+        }
+    }
+
+    let doc = format!(
+        "Does nothing, the event is compiled out because the `{feature}` feature is disabled."
+    );
+
+    quote! {
+        #(
+            #[cfg(feature = #feature)]
+            #items
+        )*
+
+        #[cfg(not(feature = #feature))]
+        #[derive(anchor_lang::__private::EventIndex)]
+        #[derive(#(#derives),*)]
+        #[allow(dead_code)]
+        #event_item
+
+        #[cfg(not(feature = #feature))]
+        impl #impl_generics #event_name #ty_generics #where_clause {
+            #[doc = #doc]
+            #[inline(always)]
+            #[allow(dead_code)]
+            pub fn emit_with<F: FnMut(&[&[u8]])>(&self, _sink: F) {}
+
+            #[doc = #doc]
+            #[inline(always)]
+            #[allow(dead_code)]
+            pub fn emit_with_correlation_id<F: FnMut(&[&[u8]])>(
+                &self,
+                _correlation_id: Option<anchor_lang::event::CorrelationId>,
+                _sink: F,
+            ) {
+            }
+
+            #[doc = #doc]
+            #[inline(always)]
+            #[allow(dead_code)]
+            pub fn data(&self) -> anchor_lang::__private::Vec<u8> {
+                anchor_lang::__private::Vec::new()
+            }
+
+            #[doc = #doc]
+            #[inline(always)]
+            #[allow(dead_code)]
+            pub fn data_into(&self, _out: &mut anchor_lang::__private::Vec<u8>) {}
+
+            #[doc = #doc]
+            #[inline(always)]
+            #[allow(dead_code)]
+            pub fn correlated_data_into(
+                &self,
+                _out: &mut anchor_lang::__private::Vec<u8>,
+                _correlation_id: Option<anchor_lang::event::CorrelationId>,
+            ) {
+            }
+
+            #[doc = #doc]
+            #[inline(always)]
+            #[allow(dead_code)]
+            pub fn emit_data_into(
+                &self,
+                _out: &mut anchor_lang::__private::Vec<u8>,
+                _correlation_id: Option<anchor_lang::event::CorrelationId>,
+            ) {
+            }
+
+            #[doc = #doc]
+            #[inline(always)]
+            #[allow(dead_code)]
+            pub fn encoded_len(&self) -> usize {
+                0
+            }
+
+            #[doc = #doc]
+            #[inline(always)]
+            #[allow(dead_code)]
+            pub fn compress_emitted_event_data<C: anchor_lang::event::EventCodec>(
+                &self,
+                _codec: &C,
+            ) -> anchor_lang::__private::Vec<u8> {
+                anchor_lang::__private::Vec::new()
+            }
+        }
+    }
+}
+
+/// Generates the `AnchorDeserialize` implementation of events with `#[optional_trailing]`
+/// fields, which are set to their `Default` if the serialized event ends before them.
+fn gen_optional_trailing_deserialize(
+    strct: &syn::ItemStruct,
+    first: usize,
+) -> proc_macro2::TokenStream {
+    let ident = &strct.ident;
+    let (impl_generics, ty_generics, where_clause) = strct.generics.split_for_impl();
+    let members = strct
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match &field.ident {
+            Some(ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(i.into()),
+        })
+        .collect::<Vec<_>>();
+    let cfgs = strct
+        .fields
+        .iter()
+        .map(anchor_syn::parser::event::cfg_attrs)
+        .collect::<Vec<_>>();
+
+    let (required, optional) = members.split_at(first);
+    let (required_cfgs, optional_cfgs) = cfgs.split_at(first);
+
+    quote! {
+        impl #impl_generics anchor_lang::AnchorDeserialize for #ident #ty_generics #where_clause {
+            // Readers can't tell whether they're exhausted, so a field is missing if it fails
+            // to deserialize without reading anything
+            fn deserialize_reader<R: anchor_lang::__private::io::Read>(
+                reader: &mut R,
+            ) -> anchor_lang::__private::io::Result<Self> {
+                let mut reader = anchor_lang::__private::CountingReader::new(reader);
+                let mut truncated = false;
+                Ok(Self {
+                    #(
+                        #(#required_cfgs)*
+                        #required: anchor_lang::AnchorDeserialize::deserialize_reader(
+                            &mut reader,
+                        )?,
+                    )*
+                    #(
+                        #(#optional_cfgs)*
+                        #optional: if truncated {
+                            Default::default()
+                        } else {
+                            let count = reader.count();
+                            match anchor_lang::AnchorDeserialize::deserialize_reader(&mut reader) {
+                                Ok(value) => value,
+                                Err(_) if reader.count() == count => {
+                                    truncated = true;
+                                    Default::default()
+                                }
+                                Err(e) => return Err(e),
+                            }
+                        },
+                    )*
+                })
+            }
+
+            // Slices are only missing a field if they end right before it, a field that is cut
+            // off is still an error
+            fn deserialize(buf: &mut &[u8]) -> anchor_lang::__private::io::Result<Self> {
+                Ok(Self {
+                    #(
+                        #(#required_cfgs)*
+                        #required: anchor_lang::AnchorDeserialize::deserialize(buf)?,
+                    )*
+                    #(
+                        #(#optional_cfgs)*
+                        #optional: if buf.is_empty() {
+                            Default::default()
+                        } else {
+                            anchor_lang::AnchorDeserialize::deserialize(buf)?
+                        },
+                    )*
+                })
+            }
+        }
+    }
+}
+
+/// Generates the `<Event>IndexedFields` struct and the `peek_indexed_fields` function of
+/// events with `#[index]` fields, which deserialize the fields of the event up to and including
+/// the last indexed field, and return the indexed ones.
+///
+/// Only non-generic struct events with named fields get them, since the indexed fields are
+/// returned by name.
+fn gen_peek_indexed_fields(event_item: &syn::Item) -> proc_macro2::TokenStream {
+    let strct = match event_item {
+        syn::Item::Struct(strct) if strct.generics.params.is_empty() => strct,
+        _ => return quote! {},
+    };
+    let fields = match &strct.fields {
+        syn::Fields::Named(fields) => &fields.named,
+        _ => return quote! {},
+    };
+    let indexed = fields
+        .iter()
+        .map(|field| field.attrs.iter().any(|attr| attr.path.is_ident("index")))
+        .collect::<Vec<_>>();
+    let last_indexed = match indexed.iter().rposition(|indexed| *indexed) {
+        Some(last_indexed) => last_indexed,
+        None => return quote! {},
+    };
+
+    let vis = &strct.vis;
+    let event_name = &strct.ident;
+    let indexed_name = quote::format_ident!("{}IndexedFields", event_name);
+    let indexed_fields = fields
+        .iter()
+        .zip(&indexed)
+        .filter(|(_, indexed)| **indexed)
+        .map(|(field, _)| field)
+        .collect::<Vec<_>>();
+
+    let struct_fields = indexed_fields.iter().map(|field| {
+        let attrs = field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("doc") || attr.path.is_ident("cfg"));
+        let (ident, ty) = (&field.ident, &field.ty);
+        quote! { #(#attrs)* pub #ident: #ty }
+    });
+    let reads = fields
+        .iter()
+        .zip(&indexed)
+        .take(last_indexed + 1)
+        .map(|(field, indexed)| {
+            let cfgs = anchor_syn::parser::event::cfg_attrs(field);
+            let ty = &field.ty;
+            let read =
+                quote! { <#ty as anchor_lang::AnchorDeserialize>::deserialize(&mut __data)? };
+            match indexed {
+                true => {
+                    let ident = &field.ident;
+                    quote! { #(#cfgs)* let #ident = #read; }
+                }
+                false => quote! { #(#cfgs)* let _ = #read; },
+            }
+        });
+    let field_inits = indexed_fields.iter().map(|field| {
+        let cfgs = anchor_syn::parser::event::cfg_attrs(field);
+        let ident = &field.ident;
+        quote! { #(#cfgs)* #ident }
+    });
+    let doc = format!(
+        "Indexed fields of [`{event_name}`], returned by [`{event_name}::peek_indexed_fields`]."
+    );
+
+    quote! {
+        #[doc = #doc]
+        #[allow(dead_code)]
+        #vis struct #indexed_name {
+            #(#struct_fields,)*
+        }
+
+        impl #event_name {
+            /// Deserializes only the indexed fields of the event from `data`, which is checked
+            /// like with `Event::try_from_event_data`.
+            ///
+            /// The fields are read in order up to and including the last indexed field, and the
+            /// fields after it aren't read at all, which is cheaper than deserializing large
+            /// events whose indexed fields come first.
+            #[allow(dead_code)]
+            #vis fn peek_indexed_fields(data: &[u8]) -> anchor_lang::Result<#indexed_name> {
+                let mut __data = <Self as anchor_lang::Event>::payload_from_event_data(data)?;
+                #(#reads)*
+                Ok(#indexed_name {
+                    #(#field_inits,)*
+                })
+            }
+        }
+    }
+}
+
+/// Generates the attributes of a `zero_copy` event, and the `Event::correlated_data_into` and
+/// `Event::encoded_len` implementations that cast the event to bytes instead of serializing it
+/// with borsh.
+///
+/// Since plain-old-data structs don't have padding, the bytes are the same as the borsh
+/// serialization of the event.
+fn gen_zero_copy(
+    event_item: &syn::Item,
+) -> syn::Result<(proc_macro2::TokenStream, proc_macro2::TokenStream)> {
+    let strct = match event_item {
+        syn::Item::Struct(strct) => strct,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                event_item,
+                "`zero_copy` events must be structs",
+            ))
+        }
+    };
+    for field in &strct.fields {
+        if let Some(ty) = non_pod_type(&field.ty) {
+            return Err(syn::Error::new_spanned(
+                &field.ty,
+                format!(
+                    "`zero_copy` events can only have plain-old-data fields, \
+                    `{ty}` is not plain-old-data"
+                ),
+            ));
+        }
+    }
+
+    // Same as `#[zero_copy]` accounts, keep user defined `repr` and bytemuck derives
+    let has_repr = strct.attrs.iter().any(|attr| attr.path.is_ident("repr"));
+    let repr = if has_repr {
+        quote! {}
+    } else {
+        quote! { #[repr(C)] }
+    };
+    // Compares the last segment of the derived paths, so that `Pod`, `bytemuck::Pod` and
+    // `::bytemuck::Pod` are all found
+    let has_derive = |name: &str| {
+        strct
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("derive"))
+            .filter_map(|attr| {
+                attr.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)
+                    .ok()
+            })
+            .flatten()
+            .any(|path| {
+                path.segments
+                    .last()
+                    .map_or(false, |segment| segment.ident == name)
+            })
+    };
+    let pod = if has_derive("Pod") {
+        quote! {}
+    } else {
+        quote! { #[derive(::bytemuck::Pod)] }
+    };
+    let zeroable = if has_derive("Zeroable") {
+        quote! {}
+    } else {
+        quote! { #[derive(::bytemuck::Zeroable)] }
+    };
+
+    let attrs = quote! {
+        #[derive(Copy, Clone)]
+        #repr
+        #pod
+        #zeroable
+    };
+    let data_into = quote! {
+        fn correlated_data_into(
+            &self,
+            out: &mut anchor_lang::__private::Vec<u8>,
+            correlation_id: Option<anchor_lang::event::CorrelationId>,
+        ) {
+            anchor_lang::event::write_event_header::<Self>(out, correlation_id);
+            out.extend_from_slice(::bytemuck::bytes_of(self));
+        }
+
+        fn encoded_len(&self) -> usize {
+            anchor_lang::event::event_header_len::<Self>(None) + ::core::mem::size_of::<Self>()
+        }
+    };
+
+    Ok((attrs, data_into))
+}
+
+/// Generates the `arbitrary::Arbitrary` implementation of an event.
+///
+/// The implementation is generated instead of derived so that programs don't need to depend on
+/// `arbitrary`, and so that `Pubkey` fields, which don't implement `Arbitrary`, are supported.
+#[cfg(feature = "arbitrary")]
+fn gen_arbitrary(event_item: &syn::Item) -> proc_macro2::TokenStream {
+    fn gen_fields(fields: &syn::Fields) -> proc_macro2::TokenStream {
+        let values = fields.iter().map(|field| {
+            let is_pubkey = matches!(
+                &field.ty,
+                syn::Type::Path(path)
+                    if path.path.segments.last().map_or(false, |s| s.ident == "Pubkey")
+            );
+            let value = if is_pubkey {
+                quote! {
+                    anchor_lang::prelude::Pubkey::new_from_array(
+                        anchor_lang::__private::arbitrary::Arbitrary::arbitrary(u)?
+                    )
+                }
+            } else {
+                quote! { anchor_lang::__private::arbitrary::Arbitrary::arbitrary(u)? }
+            };
+            // Fields gated with `#[cfg]` are only generated when they exist
+            let cfgs = anchor_syn::parser::event::cfg_attrs(field);
+            match &field.ident {
+                Some(ident) => quote! { #(#cfgs)* #ident: #value },
+                None => quote! { #(#cfgs)* #value },
+            }
+        });
+        match fields {
+            syn::Fields::Named(_) => quote! { { #(#values),* } },
+            syn::Fields::Unnamed(_) => quote! { ( #(#values),* ) },
+            syn::Fields::Unit => quote! {},
+        }
+    }
+
+    let (name, value) = match event_item {
+        syn::Item::Struct(strct) => {
+            let fields = gen_fields(&strct.fields);
+            (&strct.ident, quote! { Self #fields })
+        }
+        syn::Item::Enum(enm) => {
+            let len = enm.variants.len();
+            let arms = enm.variants.iter().enumerate().map(|(i, variant)| {
+                let ident = &variant.ident;
+                let fields = gen_fields(&variant.fields);
+                quote! { #i => Self::#ident #fields }
+            });
+            (
+                &enm.ident,
+                quote! {
+                    match u.choose_index(#len)? {
+                        #(#arms,)*
+                        _ => unreachable!(),
+                    }
+                },
+            )
+        }
+        _ => unreachable!(),
+    };
+
+    quote! {
+        impl<'a> anchor_lang::__private::arbitrary::Arbitrary<'a> for #name {
+            fn arbitrary(
+                u: &mut anchor_lang::__private::arbitrary::Unstructured<'a>,
+            ) -> anchor_lang::__private::arbitrary::Result<Self> {
+                Ok(#value)
+            }
+        }
+    }
+}
+
+/// Returns the name of the type if it's known not to be plain-old-data.
+///
+/// Other types that are not plain-old-data are rejected by the `bytemuck::Pod` derive.
+fn non_pod_type(ty: &syn::Type) -> Option<String> {
+    const NON_POD_TYPES: &[&str] = &[
+        "bool", "char", "String", "Vec", "Option", "Box", "HashMap", "HashSet", "BTreeMap",
+        "BTreeSet",
+    ];
+
+    match ty {
+        syn::Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .filter(|name| NON_POD_TYPES.contains(&name.as_str())),
+        syn::Type::Array(array) => non_pod_type(&array.elem),
+        syn::Type::Paren(paren) => non_pod_type(&paren.elem),
+        syn::Type::Reference(_) | syn::Type::Ptr(_) | syn::Type::Slice(_) | syn::Type::Tuple(_) => {
+            Some(quote! { #ty }.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Checks the estimated size of the event against the `max_size` argument, or warns if the
+/// event can't be logged with `sol_log_data` when `max_size` isn't given.
+fn gen_size_check(
+    event_item: &syn::Item,
+    event_name: &syn::Ident,
+    args: &EventArgs,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let size = estimate_size(event_item, args);
+    let bytes = size.bytes;
+    let note = match args.max_size {
+        Some(max_size) if bytes > max_size => {
+            return Err(syn::Error::new_spanned(
+                event_name,
+                format!(
+                    "`{event_name}` is estimated to be {bytes} bytes, which exceeds the \
+                    `max_size` of {max_size} bytes"
+                ),
+            ))
+        }
+        Some(max_size) if size.is_dynamic => format!(
+            "`{event_name}` is estimated to be at least {bytes} bytes, but has \
+            dynamically-sized fields that can exceed the `max_size` of {max_size} bytes, \
+            consider emitting it with `emit_cpi!`"
+        ),
+        None if bytes > MAX_LOGGED_EVENT_SIZE => format!(
+            "`{event_name}` is estimated to be {bytes} bytes, which is too large to be logged \
+            with `emit!`, consider emitting it with `emit_cpi!`"
+        ),
+        _ => return Ok(quote! {}),
+    };
+
+    Ok(quote_spanned! {event_name.span()=>
+        const _: () = {
+            #[deprecated(note = #note)]
+            const SIZE: () = ();
+            SIZE
+        };
+    })
+}
+
+/// Generates a deprecation warning for every `#[index]` attribute inside the
+/// variants of an enum event, since only struct fields are indexed in the IDL.
+fn gen_enum_index_warnings(enm: &syn::ItemEnum) -> proc_macro2::TokenStream {
+    let warnings = enm
+        .variants
+        .iter()
+        .flat_map(|variant| variant.fields.iter())
+        .flat_map(|field| field.attrs.iter())
+        .filter(|attr| {
+            ["index", "event_flatten", "decimals"]
+                .iter()
+                .any(|name| attr.path.is_ident(name))
+        })
+        .map(|attr| {
+            let note = format!(
+                "`#[{}]` is ignored on enum events",
+                attr.path.to_token_stream()
+            );
+            quote_spanned! {attr.path.span()=>
+                const _: () = {
+                    #[deprecated(note = #note)]
+                    const INDEX: () = ();
+                    INDEX
+                };
+            }
+        });
+
+    quote! {#(#warnings)*}
 }
 
 // EventIndex is a marker macro. It functionally does nothing other than
 // allow one to mark fields with the `#[index]` inert attribute, which is
-// used to add metadata to IDLs.
-#[proc_macro_derive(EventIndex, attributes(index))]
-pub fn derive_event(_item: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    proc_macro::TokenStream::from(quote! {})
+// used to add metadata to IDLs. An optional name for indexers can be given
+// with `#[index(name = "..")]`, otherwise the field name is used. Fields can
+// also be marked with `#[event_flatten]` to inline them in the IDL, or with
+// `#[decimals(<N>)]` to add their number of implied decimals to the IDL.
+#[proc_macro_derive(
+    EventIndex,
+    attributes(index, event_flatten, decimals, optional_trailing)
+)]
+pub fn derive_event(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let item = parse_macro_input!(item as syn::DeriveInput);
+    let errors = match &item.data {
+        syn::Data::Struct(strct) => strct
+            .fields
+            .iter()
+            .filter_map(|field| {
+                anchor_syn::parser::event::parse_index(field)
+                    .and_then(|_| anchor_syn::parser::event::parse_flatten(field))
+                    .and_then(|_| anchor_syn::parser::event::parse_decimals(field))
+                    .err()
+            })
+            .map(|e| e.to_compile_error())
+            .collect(),
+        _ => vec![],
+    };
+
+    proc_macro::TokenStream::from(quote! {#(#errors)*})
 }
 
 /// Logs an event that can be subscribed to by clients.
@@ -71,6 +897,18 @@ pub fn derive_event(_item: proc_macro::TokenStream) -> proc_macro::TokenStream {
 /// ```ignore
 /// Program data: <Base64EncodedEvent>
 /// ```
+///
+/// The event is only borrowed, so `emit!(&event)` leaves `event` usable afterwards.
+/// `emit!(sink, event)` passes the event to an
+/// [`EventSink`](../anchor_lang/event/trait.EventSink.html) instead of logging it, e.g.
+/// `emit!(ctx, event)` logs it through the `Context` of the handler, which counts it against the
+/// `max_events` of `#[program]` and writes its correlation id to `#[event(correlated)]` events.
+/// The syscall is compiled out with the `no-log-data` feature of `anchor-lang`.
+///
+/// The event is logged right away, i.e. even if the instruction fails afterwards, see
+/// [`emit_on_success!`](emit_on_success!). The ordering of events and the other ways to emit
+/// them are described in the [events documentation](https://www.anchor-lang.com/docs/events).
+///
 /// # Example
 ///
 /// ```rust,ignore
@@ -93,11 +931,808 @@ pub fn derive_event(_item: proc_macro::TokenStream) -> proc_macro::TokenStream {
 /// ```
 #[proc_macro]
 pub fn emit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let data: proc_macro2::TokenStream = input.into();
+    let args = parse_macro_input!(input with Punctuated::<syn::Expr, Token![,]>::parse_terminated);
+    let args = args.iter().collect::<Vec<_>>();
+    let ts = match args.as_slice() {
+        [data] => {
+            let log_data = gen_log_data(quote! {data});
+            quote! {
+                {
+                    use anchor_lang::Event as _;
+                    (#data).emit_with(|data| { #log_data });
+                }
+            }
+        }
+        [sink, data] => quote! {
+            {
+                use anchor_lang::event::EventSink as _;
+                use anchor_lang::Event as _;
+                let sink = &#sink;
+                (#data).emit_with_correlation_id(sink.correlation_id(), |data| sink.emit_data(data));
+            }
+        },
+        _ => syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "expected `emit!(event)` or `emit!(sink, event)`",
+        )
+        .to_compile_error(),
+    };
+    proc_macro::TokenStream::from(ts)
+}
+
+/// Generates the call of `anchor_lang::event::log_data` with the given data, which makes the
+/// `sol_log_data` syscall unless the `no-log-data` feature of `anchor-lang` is enabled.
+fn gen_log_data(data: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote! {
+        anchor_lang::event::log_data(#data);
+    }
+}
+
+/// Logs multiple events with a single
+/// [`sol_log_data`](https://docs.rs/solana-program/latest/solana_program/log/fn.sol_log_data.html)
+/// syscall, which is cheaper than calling [`emit!`](emit!) for each event.
+///
+/// The events can be of different types. They are logged as space separated
+/// segments of a single log line, in the given order:
+/// ```ignore
+/// Program data: <Base64EncodedEvent1> <Base64EncodedEvent2> ...
+/// ```
+///
+/// **NOTE:** Subscribers must split the log line and decode each segment
+/// separately.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// emit_many!(
+///     MyEvent { data: 5 },
+///     MyOtherEvent { label: "hello".to_string() },
+/// );
+///
+/// // Arrays of events of the same type are also accepted
+/// emit_many!([MyEvent { data: 1 }, MyEvent { data: 2 }]);
+/// ```
+#[proc_macro]
+pub fn emit_many(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let events =
+        parse_macro_input!(input with Punctuated::<syn::Expr, Token![,]>::parse_terminated);
+    let events = match events.first() {
+        Some(syn::Expr::Array(array)) if events.len() == 1 => array.elems.clone(),
+        _ => events,
+    };
+    let events = events.iter().collect::<Vec<_>>();
+
+    // Serialize all events into a single buffer and log the slices of each event
+    let ends = (0..events.len()).map(|i| quote! { __ends[#i] });
+    let starts = (0..events.len()).map(|i| match i {
+        0 => quote! { 0 },
+        _ => {
+            let prev = i - 1;
+            quote! { __ends[#prev] }
+        }
+    });
+    let len = events.len();
+    let log_data = gen_log_data(quote! { &__segments });
+    // Events compiled out with the `feature` of `#[event]` serialize to nothing and are skipped
+    let log_nonempty_data = gen_log_data(quote! { &__nonempty });
+    proc_macro::TokenStream::from(quote! {
+        {
+            use anchor_lang::Event as _;
+            let mut __buf = anchor_lang::__private::Vec::with_capacity(256);
+            let __ends: [usize; #len] = [#({
+                (#events).emit_data_into(&mut __buf, None);
+                __buf.len()
+            }),*];
+            let __segments: [&[u8]; #len] = [#(&__buf[#starts..#ends]),*];
+            if __segments.iter().all(|segment| !segment.is_empty()) {
+                #log_data
+            } else {
+                let __nonempty = __segments
+                    .iter()
+                    .copied()
+                    .filter(|segment| !segment.is_empty())
+                    .collect::<anchor_lang::__private::Vec<_>>();
+                if !__nonempty.is_empty() {
+                    #log_nonempty_data
+                }
+            }
+        }
+    })
+}
+
+/// Logs an event like [`emit!`](emit!), keyed by the address of an account, e.g. a PDA, for
+/// subscribers that only follow the events of that account.
+///
+/// The address is logged as a leading segment of the same
+/// [`sol_log_data`](https://docs.rs/solana-program/latest/solana_program/log/fn.sol_log_data.html)
+/// syscall, which adds 32 bytes, i.e. 44 base64 characters, to every emission:
+/// ```ignore
+/// Program data: <Base64EncodedAddress> <Base64EncodedEvent>
+/// ```
+///
+/// The account can be any type that implements `Key`, e.g. an `AccountInfo`, an `Account` or
+/// a `Pubkey`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+///     emit_for!(ctx.accounts.vault, Deposited { amount });
+///     Ok(())
+/// }
+/// ```
+///
+/// Subscribers decode both segments of the log line and match the address before decoding the
+/// event:
+///
+/// ```rust,ignore
+/// if let [address, event] = &decode_program_data(&line)[..] {
+///     if address[..] == vault.to_bytes() {
+///         let event = Deposited::try_from_event_data(event)?;
+///     }
+/// }
+/// ```
+///
+/// Parsers that decode every segment of `Program data:` logs as an event, like
+/// [`emit_many!`](emit_many!) logs, skip the address since it doesn't start with a
+/// discriminator of the program's events.
+#[proc_macro]
+pub fn emit_for(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(input with Punctuated::<syn::Expr, Token![,]>::parse_terminated);
+    let args = args.iter().collect::<Vec<_>>();
+    let (account, data) = match args.as_slice() {
+        [account, data] => (account, data),
+        _ => {
+            return syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "expected `emit_for!(account, event)`",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+
+    let log_data = gen_log_data(quote! { &[&__key.to_bytes()[..], &__data] });
+    proc_macro::TokenStream::from(quote! {
+        {
+            use anchor_lang::Event as _;
+            use anchor_lang::Key as _;
+            let __key = (#account).key();
+            let mut __data = anchor_lang::__private::Vec::with_capacity(256);
+            (#data).emit_data_into(&mut __data, None);
+            if !__data.is_empty() {
+                #log_data
+            }
+        }
+    })
+}
+
+/// Logs an event like [`emit!`](emit!), but compressed with an
+/// [`EventCodec`](../anchor_lang/event/trait.EventCodec.html), e.g. for high-volume events
+/// with repetitive data.
+///
+/// The discriminator is logged as is, followed by the id of the codec and the rest of the
+/// event compressed with the codec, see
+/// [`compress_event_data`](../anchor_lang/event/fn.compress_event_data.html). The codec
+/// defaults to the [`ZeroRunCodec`](../anchor_lang/event/struct.ZeroRunCodec.html), which
+/// shortens the runs of zero bytes that borsh writes for small integers and empty options.
+///
+/// Compressing costs compute units for every byte of the event and only pays off for events
+/// that compress well, so compare the compute units of both macros in tests before switching
+/// an event to `emit_compressed!`. The `emitCompressedSized` benchmarks measure the compute
+/// units of events of different sizes, to compare with the `emitSized` benchmarks of `emit!`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// pub fn swap(ctx: Context<Swap>, amount: u64) -> Result<()> {
+///     emit_compressed!(Swapped { amount, fee: 0, referrer: None });
+///
+///     // With another codec
+///     emit_compressed!(IdentityCodec, Swapped { amount, fee: 0, referrer: None });
+///     Ok(())
+/// }
+/// ```
+///
+/// The event starts with its discriminator like the events logged with [`emit!`](emit!), so
+/// parsers must know which events a program compresses. Clients decompress the event before
+/// deserializing it:
+///
+/// ```rust,ignore
+/// let data = decompress_event_data::<Swapped>(&data)?;
+/// let event = Swapped::try_from_event_data(&data)?;
+/// ```
+#[proc_macro]
+pub fn emit_compressed(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(input with Punctuated::<syn::Expr, Token![,]>::parse_terminated);
+    let args = args.iter().collect::<Vec<_>>();
+    let (codec, data) = match args.as_slice() {
+        [data] => (quote! { anchor_lang::event::ZeroRunCodec }, data),
+        [codec, data] => (quote! { #codec }, data),
+        _ => {
+            return syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "expected `emit_compressed!(event)` or `emit_compressed!(codec, event)`",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+
+    let log_data = gen_log_data(quote! { &[&__data] });
+    proc_macro::TokenStream::from(quote! {
+        {
+            use anchor_lang::__private::CompressEmittedEvent as _;
+            let __data = (#data).compress_emitted_event_data(&(#codec));
+            if !__data.is_empty() {
+                #log_data
+            }
+        }
+    })
+}
+
+/// Stores an event in a [`RingBuffer`](../anchor_lang/accounts/ring_buffer/struct.RingBuffer.html)
+/// account instead of logging it, so that the most recent events can be read on-chain, e.g. by
+/// other programs.
+///
+/// The event is serialized like [`emit!`](emit!) serializes it and overwrites the oldest event
+/// once the buffer is full. The macro evaluates to the sequence number of the event, i.e. the
+/// number of events stored in the buffer before it, and returns an error from the enclosing
+/// function if the buffer is not initialized or the event doesn't fit in its slots.
+///
+/// Nothing is logged, so clients subscribed to the logs of the program don't see these events.
+/// Use both macros for events that are consumed on-chain and off-chain.
+///
+/// Events compiled out with the `feature` of [`#[event]`](event) aren't stored, and the macro
+/// evaluates to the [`head`](../anchor_lang/accounts/ring_buffer/struct.RingBuffer.html#method.head)
+/// of the buffer instead.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(Accounts)]
+/// pub struct Deposit<'info> {
+///     #[account(mut)]
+///     pub events: RingBuffer<'info>,
+/// }
+///
+/// pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+///     let sequence = emit_to_account!(ctx.accounts.events, Deposited { amount });
+///     msg!("Deposit {}", sequence);
+///     Ok(())
+/// }
+/// ```
+///
+/// Programs read the events with the same type:
+///
+/// ```rust,ignore
+/// let events = RingBuffer::try_from(&vault_program::ID, &ctx.accounts.events)?;
+/// let deposits = events.decode::<Deposited>();
+/// ```
+#[proc_macro]
+pub fn emit_to_account(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(input with Punctuated::<syn::Expr, Token![,]>::parse_terminated);
+    let args = args.iter().collect::<Vec<_>>();
+    let (account, data) = match args.as_slice() {
+        [account, data] => (account, data),
+        _ => {
+            return syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "expected `emit_to_account!(ring_buffer, event)`",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+
+    proc_macro::TokenStream::from(quote! {
+        {
+            use anchor_lang::Event as _;
+            let mut __data = anchor_lang::__private::Vec::with_capacity(256);
+            (#data).emit_data_into(&mut __data, None);
+            if __data.is_empty() {
+                (#account).head()
+            } else {
+                (#account).push(&__data)?
+            }
+        }
+    })
+}
+
+/// Logs a raw payload as an event with the given 8-byte discriminator, e.g. for proxy or
+/// relayer programs that forward events they don't have Rust types for.
+///
+/// The discriminator and the payload are logged as a single segment, which is the same format
+/// as [`emit!`](emit!) logs:
+/// ```ignore
+/// Program data: <Base64EncodedDiscriminatorAndPayload>
+/// ```
+///
+/// The discriminator must be a `[u8; 8]`. The length of byte string and array literals is
+/// checked at compile time. The payload can be any type that implements `AsRef<[u8]>`, e.g. a
+/// `Vec<u8>` or a `&[u8]`, and is logged as is, i.e. forwarded correlated events keep their
+/// correlation id slot, and the correlation id of the `Context` is never added.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// pub fn relay(ctx: Context<Relay>, discriminator: [u8; 8], payload: Vec<u8>) -> Result<()> {
+///     emit_raw!(discriminator, payload);
+///     emit_raw!(b"relayed!", ctx.accounts.source.key().to_bytes());
+///     Ok(())
+/// }
+/// ```
+#[proc_macro]
+pub fn emit_raw(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(input with Punctuated::<syn::Expr, Token![,]>::parse_terminated);
+    let args = args.iter().collect::<Vec<_>>();
+    let (discriminator, data) = match args.as_slice() {
+        [discriminator, data] => (discriminator, data),
+        _ => {
+            return syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "expected `emit_raw!(discriminator, bytes)`",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+
+    let discriminator = match discriminator {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::ByteStr(lit),
+            ..
+        }) => match lit.value().len() {
+            8 => quote! { *#lit },
+            len => return discriminator_len_error(lit, len),
+        },
+        syn::Expr::Array(array) => match array.elems.len() {
+            8 => quote! { #array },
+            len => return discriminator_len_error(array, len),
+        },
+        _ => quote! { #discriminator },
+    };
+
+    let log_data = gen_log_data(quote! { &[&__data] });
+    proc_macro::TokenStream::from(quote! {
+        {
+            let __discriminator: [u8; 8] = #discriminator;
+            let __data = [
+                &__discriminator[..],
+                ::core::convert::AsRef::<[u8]>::as_ref(&#data),
+            ]
+            .concat();
+            #log_data
+        }
+    })
+}
+
+fn discriminator_len_error(discriminator: impl ToTokens, len: usize) -> proc_macro::TokenStream {
+    syn::Error::new_spanned(
+        discriminator,
+        format!("discriminator must be 8 bytes, found {len} bytes"),
+    )
+    .to_compile_error()
+    .into()
+}
+
+/// Log an event like [`emit!`](emit!), but only once the instruction succeeds.
+///
+/// The event is serialized right away and stored in the
+/// [`InstructionEvents`](../anchor_lang/event/struct.InstructionEvents.html) of the context,
+/// see [`Context::defer_event`](../anchor_lang/context/struct.Context.html#method.defer_event).
+/// The instruction dispatch generated by `#[program]` logs the deferred events in the order they
+/// were emitted after the handler returned `Ok` and the accounts were exited. If the handler
+/// returns an error, the events are dropped, so they never appear in the logs of a failed
+/// instruction.
+///
+/// ```ignore
+/// pub fn transfer(ctx: Context<Transfer>, amount: u64) -> Result<()> {
+///     emit_on_success!(Transferred { amount });
+///
+///     // The event isn't logged if this check fails
+///     require!(amount <= MAX_AMOUNT, MyError::AmountTooLarge);
+///     Ok(())
+/// }
+/// ```
+///
+/// The context is taken from a variable named `ctx` unless it's passed explicitly, e.g. in
+/// helpers that take a reference to it: `emit_on_success!(ctx, Transferred { amount })`.
+///
+/// Deferring only applies to logged events. Events emitted with [`emit_cpi!`](emit_cpi!) are
+/// discarded along with the rest of a failed transaction, but their instruction is recorded as
+/// soon as the self-CPI is invoked, so it shows up in simulations of the failed transaction.
+#[proc_macro]
+pub fn emit_on_success(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(input with Punctuated::<syn::Expr, Token![,]>::parse_terminated);
+    let args = args.iter().collect::<Vec<_>>();
+    let (ctx, data) = match args.as_slice() {
+        [data] => (quote! { ctx }, data),
+        [ctx, data] => (quote! { #ctx }, data),
+        _ => {
+            return syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "expected `emit_on_success!(event)` or `emit_on_success!(ctx, event)`",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+
+    proc_macro::TokenStream::from(quote! {
+        {
+            use anchor_lang::Event as _;
+            let __ctx = &#ctx;
+            (#data).emit_with_correlation_id(__ctx.correlation_id, |data| __ctx.defer_event(data));
+        }
+    })
+}
+
+/// Checks at compile time that the discriminators of the given events don't collide.
+///
+/// Two discriminators collide if they are equal, or if the shorter one is a prefix of the longer
+/// one, since clients would not be able to tell the events apart. A collision results in a
+/// compile error naming both events.
+///
+/// With the `const` form, a constant listing the name and discriminator of the events, as
+/// [`EventMeta`](../anchor_lang/event/struct.EventMeta.html)s in the given order, is also
+/// generated, e.g. to match logged events against the events of the program at runtime.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// declare_events!(MyEvent, MyOtherEvent, nested::Event);
+///
+/// declare_events!(pub const EVENTS = [MyEvent, MyOtherEvent, nested::Event]);
+/// assert_eq!(EVENTS[0].name, "MyEvent");
+/// ```
+#[proc_macro]
+pub fn declare_events(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(input as DeclareEventsArgs);
+    let events = args
+        .events
+        .iter()
+        .map(|event| {
+            syn::Type::from(syn::TypePath {
+                qself: None,
+                path: event.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let collision_checks = gen_discriminator_collision_checks(&events);
+
+    let list = args.list.map(|(vis, ident)| {
+        let metas = args.events.iter().zip(&events).map(|(path, ty)| {
+            let name = path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string())
+                .unwrap_or_default();
+            quote! {
+                anchor_lang::event::EventMeta {
+                    name: #name,
+                    discriminator: <#ty as anchor_lang::Event>::EVENT_DISCRIMINATOR,
+                }
+            }
+        });
+        quote! {
+            #vis const #ident: &[anchor_lang::event::EventMeta] = &[#(#metas),*];
+        }
+    });
+
+    proc_macro::TokenStream::from(quote! {
+        #collision_checks
+        #list
+    })
+}
+
+/// Arguments of [`declare_events!`](declare_events!), i.e. `(Event, ..)` or
+/// `(<vis> const <NAME> = [Event, ..])`.
+struct DeclareEventsArgs {
+    list: Option<(syn::Visibility, syn::Ident)>,
+    events: Punctuated<syn::Path, Token![,]>,
+}
+
+impl syn::parse::Parse for DeclareEventsArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let fork = input.fork();
+        let is_list = fork.parse::<syn::Visibility>().is_ok() && fork.peek(Token![const]);
+        if !is_list {
+            return Ok(Self {
+                list: None,
+                events: Punctuated::parse_terminated(input)?,
+            });
+        }
+
+        let vis = input.parse()?;
+        input.parse::<Token![const]>()?;
+        let ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let content;
+        syn::bracketed!(content in input);
+        let events = Punctuated::parse_terminated(&content)?;
+        input.parse::<Option<Token![;]>>()?;
+        Ok(Self {
+            list: Some((vis, ident)),
+            events,
+        })
+    }
+}
+
+/// Generates a const assertion for every pair of the given events that fails to compile if their
+/// discriminators collide.
+fn gen_discriminator_collision_checks(events: &[syn::Type]) -> proc_macro2::TokenStream {
+    let checks = events.iter().enumerate().flat_map(|(i, a)| {
+        events[i + 1..].iter().map(move |b| {
+            let message = format!(
+                "Discriminator of `{}` collides with the discriminator of `{}`",
+                quote! {#a}.to_string().replace(' ', ""),
+                quote! {#b}.to_string().replace(' ', ""),
+            );
+            quote_spanned! {b.span()=>
+                const _: () = assert!(
+                    !anchor_lang::__private::discriminators_collide(
+                        <#a as anchor_lang::Event>::EVENT_DISCRIMINATOR,
+                        <#b as anchor_lang::Event>::EVENT_DISCRIMINATOR,
+                    ),
+                    #message
+                );
+            }
+        })
+    });
+
+    quote! {#(#checks)*}
+}
+
+/// Generates an enum of the given events that can decode any of them from raw event data.
+///
+/// Each variant either has the name of an event type in scope, or wraps the event type
+/// explicitly. The generated `decode` function matches the data against the discriminators of
+/// all events, which are checked not to collide as with [`declare_events!`](declare_events!).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// generate_event_enum! {
+///     #[derive(Debug)]
+///     pub enum ProgramEvent {
+///         MyEvent,
+///         Other(other::Event),
+///     }
+/// }
+///
+/// // Decode an event from the base64 decoded `Program data:` log
+/// match ProgramEvent::decode(&data) {
+///     Some(ProgramEvent::MyEvent(event)) => {}
+///     Some(ProgramEvent::Other(event)) => {}
+///     None => {}
+/// }
+/// ```
+///
+/// Each event type converts into the enum with `From`, so events of different types can be
+/// turned into the enum uniformly, e.g. `let event: ProgramEvent = MyEvent { data: 5 }.into()`.
+/// The variants must therefore wrap distinct event types.
+///
+/// # Dispatch
+///
+/// The macro also generates a `<Enum>Handler` trait with an `on_<variant>` method per
+/// variant, in snake case, which [`ProgramEvent::dispatch`] calls for each event of the logs of
+/// a transaction. The methods are required, so adding an event to the enum fails to compile
+/// until the handlers handle it, which gives indexers an exhaustive match over the events:
+///
+/// ```rust,ignore
+/// struct Indexer;
+///
+/// impl ProgramEventHandler for Indexer {
+///     fn on_my_event(&mut self, event: MyEvent, ctx: &EventContext) {}
+///     fn on_other(&mut self, event: other::Event, ctx: &EventContext) {}
+/// }
+///
+/// let ctx = EventContext { slot, signature: &signature };
+/// ProgramEvent::dispatch(&ctx, &logs, &mut Indexer);
+///
+/// // Or decode a single log line
+/// let event = ProgramEvent::from_log_line(&logs[2]);
+/// ```
+///
+/// High-throughput indexers can keep an
+/// [`EventScratch`](../anchor_lang/event/struct.EventScratch.html) around and pass it to
+/// `dispatch_with` and `from_log_line_with`, which decode the logs into its buffer instead of
+/// allocating buffers for every line and event. Decoding then only allocates for the fields
+/// that the events own, e.g. once per event with a `Vec` field where `from_log_line` allocates
+/// three times, which roughly halves the decoding time of such events. See the
+/// `event_scratch` tests of `anchor-lang` for the benchmark.
+///
+/// See [`EventContext`](../anchor_lang/event/struct.EventContext.html) for the slot and
+/// signature passed to the handlers.
+#[proc_macro]
+pub fn generate_event_enum(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let event_enum = parse_macro_input!(input as syn::ItemEnum);
+
+    let variants = match event_enum
+        .variants
+        .iter()
+        .map(|variant| match &variant.fields {
+            syn::Fields::Unit => {
+                let ident = &variant.ident;
+                Ok((variant, syn::parse_quote! { #ident }))
+            }
+            syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                Ok((variant, fields.unnamed[0].ty.clone()))
+            }
+            _ => Err(syn::Error::new_spanned(
+                variant,
+                "event variants must either be unit variants or wrap a single event type",
+            )),
+        })
+        .collect::<syn::Result<Vec<(&syn::Variant, syn::Type)>>>()
+    {
+        Ok(variants) => variants,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let syn::ItemEnum {
+        attrs,
+        vis,
+        ident,
+        generics,
+        ..
+    } = &event_enum;
+    let variant_defs = variants.iter().map(|(variant, ty)| {
+        let attrs = &variant.attrs;
+        let variant_ident = &variant.ident;
+        quote! {
+            #(#attrs)*
+            #variant_ident(#ty)
+        }
+    });
+    let decode_arms = variants.iter().map(|(variant, ty)| {
+        let variant_ident = &variant.ident;
+        quote! {
+            if data.starts_with(<#ty as anchor_lang::Event>::EVENT_DISCRIMINATOR) {
+                return <#ty as anchor_lang::Event>::try_from_event_data(data)
+                    .ok()
+                    .map(Self::#variant_ident);
+            }
+        }
+    });
+    let types = variants
+        .iter()
+        .map(|(_, ty)| ty.clone())
+        .collect::<Vec<_>>();
+    let collision_checks = gen_discriminator_collision_checks(&types);
+
+    // Handler trait with a required method per variant, so that handlers must handle all events
+    let handler_ident = quote::format_ident!("{}Handler", ident);
+    let handler_methods = variants
+        .iter()
+        .map(|(variant, _)| {
+            let name = variant.ident.to_string().to_snake_case();
+            quote::format_ident!("on_{}", name)
+        })
+        .collect::<Vec<_>>();
+    let handler_method_defs =
+        variants
+            .iter()
+            .zip(&handler_methods)
+            .map(|((variant, ty), method)| {
+                let doc = format!("Handles a `{}` event.", variant.ident);
+                quote! {
+                    #[doc = #doc]
+                    fn #method(&mut self, event: #ty, ctx: &anchor_lang::event::EventContext<'_>);
+                }
+            });
+    let handle_arms = variants
+        .iter()
+        .zip(&handler_methods)
+        .map(|((variant, _), method)| {
+            let variant_ident = &variant.ident;
+            quote! { Self::#variant_ident(event) => handler.#method(event, ctx) }
+        });
+    // Events convert into the enum, e.g. to collect events of different types with `.into()`
+    let (impl_generics, ty_generics, where_clause) = event_enum.generics.split_for_impl();
+    let from_impls = variants.iter().map(|(variant, ty)| {
+        let variant_ident = &variant.ident;
+        quote! {
+            impl #impl_generics From<#ty> for #ident #ty_generics #where_clause {
+                fn from(event: #ty) -> Self {
+                    Self::#variant_ident(event)
+                }
+            }
+        }
+    });
+    let handler_doc = format!(
+        "Handler of the events of [`{ident}`], see [`{ident}::dispatch`]. Adding an event to \
+        the enum adds a required method, so handlers fail to compile until they handle it."
+    );
+
     proc_macro::TokenStream::from(quote! {
-        {
-            anchor_lang::solana_program::log::sol_log_data(&[&anchor_lang::Event::data(&#data)]);
+        #(#attrs)*
+        #vis enum #ident #generics {
+            #(#variant_defs,)*
+        }
+
+        #(#from_impls)*
+
+        #[doc = #handler_doc]
+        #vis trait #handler_ident {
+            #(#handler_method_defs)*
+        }
+
+        impl #ident {
+            /// Decodes one of the events from `data`, which must start with the discriminator of
+            /// the event. Returns `None` if no event matches or deserialization fails.
+            pub fn decode(data: &[u8]) -> Option<Self> {
+                #(#decode_arms)*
+                None
+            }
+
+            /// Decodes the first event of a `Program data:` log line. Returns `None` for other
+            /// lines, or if no event matches. Use [`Self::dispatch`] to also handle the other
+            /// events of lines logged with `emit_many!`.
+            pub fn from_log_line(line: &str) -> Option<Self> {
+                anchor_lang::event::decode_program_data(line)
+                    .iter()
+                    .find_map(|data| Self::decode(data))
+            }
+
+            /// Passes the event to the method of `handler` for its variant.
+            pub fn handle<H: #handler_ident + ?Sized>(
+                self,
+                ctx: &anchor_lang::event::EventContext<'_>,
+                handler: &mut H,
+            ) {
+                match self {
+                    #(#handle_arms,)*
+                }
+            }
+
+            /// Like [`Self::from_log_line`], but decodes the line into the buffer of `scratch`
+            /// instead of allocating one.
+            pub fn from_log_line_with(
+                line: &str,
+                scratch: &mut anchor_lang::event::EventScratch,
+            ) -> Option<Self> {
+                let mut event = None;
+                scratch.for_each_program_data(line, |data| {
+                    if event.is_none() {
+                        event = Self::decode(data);
+                    }
+                });
+                event
+            }
+
+            /// Decodes the events of the given transaction logs and passes them to `handler` in
+            /// log order, together with the slot and signature of the transaction. Lines that
+            /// aren't events of this enum are skipped. Events of all programs are decoded.
+            pub fn dispatch<H: #handler_ident + ?Sized>(
+                ctx: &anchor_lang::event::EventContext<'_>,
+                logs: &[String],
+                handler: &mut H,
+            ) {
+                Self::dispatch_with(ctx, logs, handler, &mut anchor_lang::event::EventScratch::new())
+            }
+
+            /// Like [`Self::dispatch`], but decodes the logs into the buffer of `scratch`, which
+            /// can be reused across transactions so that decoding doesn't allocate once the
+            /// buffer holds the largest event.
+            pub fn dispatch_with<H: #handler_ident + ?Sized>(
+                ctx: &anchor_lang::event::EventContext<'_>,
+                logs: &[String],
+                handler: &mut H,
+                scratch: &mut anchor_lang::event::EventScratch,
+            ) {
+                for line in logs {
+                    scratch.for_each_program_data(line, |data| {
+                        if let Some(event) = Self::decode(data) {
+                            event.handle(ctx, handler);
+                        }
+                    });
+                }
+            }
         }
+
+        #collision_checks
     })
 }
 
@@ -142,50 +1777,549 @@ pub fn emit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 /// }
 /// ```
 ///
-/// **NOTE:** This macro requires `ctx` to be in scope.
+/// The accounts are taken from `ctx`, which must be in scope, through the
+/// [`EventCpiAccountInfos`](https://docs.rs/anchor-lang/latest/anchor_lang/event/trait.EventCpiAccountInfos.html)
+/// trait that `#[event_cpi]` implements. Handlers that are generic over their accounts, e.g. the
+/// default methods of a trait, can emit events by bounding the accounts with the trait:
 ///
-/// *Only available with `event-cpi` feature enabled.*
+/// ```ignore
+/// fn notify<'info, T: EventCpiAccountInfos<'info>>(ctx: &Context<'_, '_, '_, '_, T>) -> Result<()> {
+///     emit_cpi!(MyEvent { data: 42 });
+///     Ok(())
+/// }
+/// ```
+///
+/// To emit from a helper function that doesn't take the context, or when the context isn't named
+/// `ctx`, pass the program account, the event authority account and its bump explicitly:
+///
+/// ```ignore
+/// fn emit_helper<'info>(
+///     program: &AccountInfo<'info>,
+///     event_authority: &AccountInfo<'info>,
+///     event_authority_bump: u8,
+/// ) -> Result<()> {
+///     emit_cpi!(program, event_authority, event_authority_bump, MyEvent { data: 42 });
+///     Ok(())
+/// }
+/// ```
+///
+/// If the event authority has a seed suffix, see [`#[event_cpi]`](event_cpi), it's taken from
+/// the accounts struct of `ctx`. With explicit accounts, pass the suffix before the event, e.g.
+/// `emit_cpi!(program, event_authority, event_authority_bump, b"vault", MyEvent { data: 42 })`.
+///
+/// With [`#[event_cpi(sequence)]`](event_cpi), each event taken from `ctx` is numbered with the
+/// sequence number stored in the event authority account.
+///
+/// The instruction data of the self-CPI is
+/// [`EVENT_IX_TAG_LE`](https://docs.rs/anchor-lang/latest/anchor_lang/event/constant.EVENT_IX_TAG_LE.html),
+/// i.e. the little-endian bytes of `EVENT_IX_TAG`, followed by the discriminator and the
+/// payload of the event. The big-endian `EVENT_IX_TAG_BE` is never written, parsers that
+/// expect it can use `split_event_ix_data_with` to accept either order.
+///
+/// The self-CPI targets the current program, i.e. `crate::ID`, unless the accounts struct of
+/// `ctx` is annotated with `#[event_cpi(program_id = <expr>)]`. If the self-CPI fails,
+/// `ErrorCode::EventCpiFailed` is returned with the underlying program error in its message.
+///
+/// With the explicit form of the macro, the event authority account is checked to be the PDA
+/// derived from the event authority seeds and bump before the self-CPI, and
+/// `ErrorCode::EventAuthorityMismatch` is returned otherwise, e.g. for a wrong account or bump.
+///
+/// Within
+/// [`record_cpi_events`](https://docs.rs/anchor-lang/latest/anchor_lang/event/fn.record_cpi_events.html),
+/// the instruction is recorded instead of invoked, so that tests can check the instruction data
+/// and accounts without a runtime. The recording is compiled out on-chain, where the self-CPI is
+/// always invoked.
+///
+/// *Only available with `event-cpi` feature enabled.* Without it, `emit_cpi!`,
+/// [`build_cpi_event_ix!`](build_cpi_event_ix!) and [`#[event_cpi]`](event_cpi) fail to compile
+/// with an error asking to enable the feature, and no event authority or self-CPI handling is
+/// generated for the program.
 #[cfg(feature = "event-cpi")]
 #[proc_macro]
 pub fn emit_cpi(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let event_struct = parse_macro_input!(input as syn::Expr);
+    let args = parse_macro_input!(input as EventCpiMacroArgs);
+    let event_cpi_ix = gen_cpi_event_ix(&args, None);
+
+    let authority_check = gen_event_authority_check(&args);
+    let count_check = gen_event_count_check(&args);
+
+    proc_macro::TokenStream::from(quote! {
+        {
+            let event_cpi_ix = #event_cpi_ix;
+            if !event_cpi_ix.is_empty() {
+                #authority_check
+                #count_check
+                event_cpi_ix.invoke()?;
+            }
+        }
+    })
+}
+
+/// Generates the check that the event taken from `ctx` is within the `max_events` of
+/// `#[program(max_events = <N>)]`, which fails with `ErrorCode::TooManyEvents` right away since
+/// the self-CPI can't be undone. Events with explicit accounts aren't counted.
+#[cfg(feature = "event-cpi")]
+fn gen_event_count_check(args: &EventCpiMacroArgs) -> proc_macro2::TokenStream {
+    if let EventCpiMacroArgs::Explicit { .. } = args {
+        return quote! {};
+    }
+
+    quote! {
+        if !ctx.count_events(1) {
+            return Err(anchor_lang::error::ErrorCode::TooManyEvents.into());
+        }
+    }
+}
+
+/// Generates the check that the event authority account of `event_cpi_ix` is the event
+/// authority PDA of the program the self-CPI targets, which is the same as `require_keys_eq!`
+/// with `ErrorCode::EventAuthorityMismatch`.
+///
+/// Only the accounts passed to the explicit form of `emit_cpi!` are checked, since the `seeds`
+/// constraint of `#[event_cpi]` already checks the account taken from `ctx` and deriving the
+/// PDA costs about 1.5k compute units. A misconfigured event authority fails here instead of
+/// with an opaque signer error of the self-CPI.
+#[cfg(feature = "event-cpi")]
+fn gen_event_authority_check(args: &EventCpiMacroArgs) -> proc_macro2::TokenStream {
+    if let EventCpiMacroArgs::Ctx { .. } = args {
+        return quote! {};
+    }
+
+    quote! {
+        {
+            let authority_bump = [event_cpi_ix.authority_bump];
+            let mut authority_seeds: Vec<&[u8]> = event_cpi_ix.authority_seeds.to_vec();
+            authority_seeds.push(&authority_bump);
+            let authority_key = *event_cpi_ix.account_infos[0].key;
+            let expected_authority_key =
+                anchor_lang::solana_program::pubkey::Pubkey::create_program_address(
+                    &authority_seeds,
+                    &event_cpi_ix.instruction.program_id,
+                )
+                .map_err(|_| {
+                    anchor_lang::error!(anchor_lang::error::ErrorCode::EventAuthorityMismatch)
+                })?;
+            if authority_key != expected_authority_key {
+                return Err(anchor_lang::error!(
+                    anchor_lang::error::ErrorCode::EventAuthorityMismatch
+                )
+                .with_pubkeys((authority_key, expected_authority_key)));
+            }
+        }
+    }
+}
 
-    let authority = EventAuthority::get();
-    let authority_name = authority.name_token_stream();
-    let authority_seeds = authority.seeds;
+/// Log an event with [`emit!`](emit!) if it's small, or with [`emit_cpi!`](emit_cpi!) if it's
+/// large.
+///
+/// Logging is cheaper than a self-CPI, but RPCs truncate the logs of a transaction once they
+/// exceed the log limit, which silently drops large events. `emit_auto!` checks
+/// [`Event::encoded_len`](../anchor_lang/trait.Event.html#method.encoded_len) at runtime, and
+/// logs events of at most
+/// [`EMIT_AUTO_THRESHOLD`](../anchor_lang/event/constant.EMIT_AUTO_THRESHOLD.html) bytes,
+/// i.e. 1024 bytes, while larger events are emitted with a self-CPI. The threshold can be
+/// overridden per invocation:
+///
+/// ```ignore
+/// pub fn my_instruction(ctx: Context<MyInstruction>) -> Result<()> {
+///     emit_auto!(MyEvent { data: vec![1, 2, 3] });
+///     emit_auto!(MyEvent { data: vec![1, 2, 3] }, threshold = 256);
+///     Ok(())
+/// }
+///
+/// #[event_cpi]
+/// #[derive(Accounts)]
+/// pub struct MyInstruction {}
+/// ```
+///
+/// The event is serialized once to get its size, and again when it's emitted. Since either
+/// macro may be used, the event CPI accounts are taken from `ctx` like with `emit_cpi!(event)`,
+/// and the accounts struct must be annotated with [`#[event_cpi]`](event_cpi), otherwise it
+/// fails to compile. Clients need to handle both the `Program data:` logs and the self-CPI
+/// instructions of the program, e.g. with the `EventParser` of `anchor-client`. Like with
+/// `emit_cpi!`, the self-CPI is recorded instead of invoked within `record_cpi_events`.
+///
+/// *Only available with `event-cpi` feature enabled.*
+#[cfg(feature = "event-cpi")]
+#[proc_macro]
+pub fn emit_auto(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(input as EmitAutoArgs);
+    let event = &args.event;
+    let threshold = match &args.threshold {
+        Some(threshold) => quote! { #threshold },
+        None => quote! { anchor_lang::event::EMIT_AUTO_THRESHOLD },
+    };
+    let cpi_args = EventCpiMacroArgs::Ctx {
+        event: syn::parse_quote! { __event },
+    };
+    let event_cpi_ix = gen_cpi_event_ix(&cpi_args, None);
+    let count_check = gen_event_count_check(&cpi_args);
 
     proc_macro::TokenStream::from(quote! {
         {
-            let authority_info = ctx.accounts.#authority_name.to_account_info();
-            let authority_bump = ctx.bumps.#authority_name;
+            use anchor_lang::event::EventSink as _;
+            use anchor_lang::Event as _;
+            let __event = &#event;
+            let threshold: usize = #threshold;
+            if __event.encoded_len() <= threshold {
+                __event.emit_with_correlation_id(ctx.correlation_id, |data| ctx.emit_data(data));
+            } else {
+                let event_cpi_ix = #event_cpi_ix;
+                #count_check
+                event_cpi_ix.invoke()?;
+            }
+        }
+    })
+}
+
+/// Arguments of [`emit_auto!`](emit_auto!), i.e. `(event[, threshold = <N>])`.
+#[cfg(feature = "event-cpi")]
+struct EmitAutoArgs {
+    event: syn::Expr,
+    /// Maximum size of the logged events, defaults to `EMIT_AUTO_THRESHOLD`.
+    threshold: Option<syn::Expr>,
+}
+
+#[cfg(feature = "event-cpi")]
+impl syn::parse::Parse for EmitAutoArgs {
+    fn parse(stream: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        let event = stream.parse()?;
+        let mut threshold = None;
+        if !stream.is_empty() {
+            stream.parse::<Token![,]>()?;
+        }
+        if !stream.is_empty() {
+            let key = stream.parse::<syn::Ident>()?;
+            if key != "threshold" {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "expected `threshold = <N>` after the event",
+                ));
+            }
+            stream.parse::<Token![=]>()?;
+            threshold = Some(stream.parse()?);
+            if !stream.is_empty() {
+                stream.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(Self { event, threshold })
+    }
+}
+
+/// Build the self-CPI instruction of [`emit_cpi!`](emit_cpi!) without invoking it.
+///
+/// This is useful for bundling the event instruction with other CPIs, or invoking it at a later
+/// point in the handler. The macro evaluates to an
+/// [`EventCpiInstruction`](../anchor_lang/event/struct.EventCpiInstruction.html), which holds the
+/// instruction, the required account infos and the event authority signer seeds.
+///
+/// # Example
+///
+/// ```ignore
+/// pub fn my_instruction(ctx: Context<MyInstruction>) -> Result<()> {
+///     let event_ix = build_cpi_event_ix!(MyEvent { data: 42 });
+///
+///     // ...
+///
+///     event_ix.invoke()?;
+///     Ok(())
+/// }
+/// ```
+///
+/// Accepts the same arguments as [`emit_cpi!`](emit_cpi!), i.e. the accounts are taken from
+/// `ctx` unless they are passed explicitly.
+///
+/// *Only available with `event-cpi` feature enabled.*
+#[cfg(feature = "event-cpi")]
+#[proc_macro]
+pub fn build_cpi_event_ix(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(input as EventCpiMacroArgs);
+    proc_macro::TokenStream::from(gen_cpi_event_ix(&args, None))
+}
+
+/// Build the instruction that forwards an event to another program, e.g. a shared logging
+/// program that aggregates the events of multiple programs.
+///
+/// The first argument is the account info of the target program, the remaining arguments are
+/// the same as with [`emit_cpi!`](emit_cpi!). Like [`build_cpi_event_ix!`](build_cpi_event_ix!),
+/// the macro evaluates to an
+/// [`EventCpiInstruction`](../anchor_lang/event/struct.EventCpiInstruction.html) that is sent
+/// with `invoke`:
+///
+/// ```ignore
+/// pub fn my_instruction(ctx: Context<MyInstruction>) -> Result<()> {
+///     forward_cpi_event!(ctx.accounts.logger, MyEvent { data: 42 }).invoke()?;
+///     Ok(())
+/// }
+///
+/// #[event_cpi]
+/// #[derive(Accounts)]
+/// pub struct MyInstruction<'info> {
+///     /// CHECK: The logging program that receives the events
+///     #[account(address = logger::ID, executable)]
+///     pub logger: AccountInfo<'info>,
+/// }
+/// ```
+///
+/// The data of the instruction is the same as with `emit_cpi!`, i.e. the event tag followed by
+/// the event, and its accounts are the event authority of the current program as a signer,
+/// followed by the current program.
+///
+/// # Security
+///
+/// A program can only sign for its own PDAs, so the instruction is signed by the event
+/// authority of the **current** program, not of the target program. It's then up to the target
+/// program to handle the instruction, e.g. by logging the event or storing it with its own
+/// `emit_cpi!`. Since anyone can send the same instruction data to the target program, the
+/// target must check that the first account is a signer and is the event authority of the
+/// program given as the second account, i.e. `Pubkey::find_program_address(seeds, program)`
+/// with the `[b"__event_authority"]` seeds, or the seeds with the suffix of
+/// [`#[event_cpi(seed = "..")]`](event_cpi). Only then can it trust which program emitted the
+/// event.
+///
+/// Anchor programs don't accept forwarded events: their event handler only accepts events
+/// signed by their own event authority, so the target must be a program that handles forwarded
+/// events explicitly.
+///
+/// *Only available with `event-cpi` feature enabled.*
+#[cfg(feature = "event-cpi")]
+#[proc_macro]
+pub fn forward_cpi_event(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(input as ForwardCpiEventArgs);
+    proc_macro::TokenStream::from(gen_cpi_event_ix(&args.event_cpi, Some(&args.target)))
+}
+
+/// Arguments of [`forward_cpi_event!`](forward_cpi_event!).
+#[cfg(feature = "event-cpi")]
+struct ForwardCpiEventArgs {
+    /// Account info of the program that the event is forwarded to.
+    target: syn::Expr,
+    /// Remaining arguments, which are the same as with [`emit_cpi!`](emit_cpi!).
+    event_cpi: EventCpiMacroArgs,
+}
+
+#[cfg(feature = "event-cpi")]
+impl syn::parse::Parse for ForwardCpiEventArgs {
+    fn parse(stream: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        let target = stream.parse()?;
+        stream.parse::<Token![,]>()?;
+        Ok(Self {
+            target,
+            event_cpi: stream.parse()?,
+        })
+    }
+}
+
+/// Arguments of [`emit_cpi!`](emit_cpi!) and [`build_cpi_event_ix!`](build_cpi_event_ix!).
+#[cfg(feature = "event-cpi")]
+enum EventCpiMacroArgs {
+    /// `(event)`, the accounts are taken from `ctx`.
+    Ctx { event: syn::Expr },
+    /// `(program_info, authority_info, authority_bump, [authority_seed,] event)`
+    Explicit {
+        program_info: syn::Expr,
+        authority_info: syn::Expr,
+        authority_bump: syn::Expr,
+        authority_seed: Option<syn::Expr>,
+        event: syn::Expr,
+    },
+}
+
+#[cfg(feature = "event-cpi")]
+impl syn::parse::Parse for EventCpiMacroArgs {
+    fn parse(stream: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        // Errors point at the macro invocation since the arguments are often valid expressions
+        // that are just in the wrong order or number
+        let usage = |found: Option<usize>| {
+            let found = match found {
+                Some(found) => format!("expected 1, 4 or 5 arguments, found {found}. "),
+                None => String::new(),
+            };
+            syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!(
+                    "{found}Pass `(event)` to take the event CPI accounts from `ctx`, or \
+                    `(program_info, authority_info, authority_bump, [authority_seed,] event)` \
+                    to pass them explicitly"
+                ),
+            )
+        };
+
+        let args =
+            Punctuated::<syn::Expr, Token![,]>::parse_terminated(stream).map_err(|mut err| {
+                err.combine(usage(None));
+                err
+            })?;
+        let args = args.into_iter().collect::<Vec<_>>();
+        match args.as_slice() {
+            [event] => Ok(Self::Ctx {
+                event: event.clone(),
+            }),
+            [program_info, authority_info, authority_bump, event] => Ok(Self::Explicit {
+                program_info: program_info.clone(),
+                authority_info: authority_info.clone(),
+                authority_bump: authority_bump.clone(),
+                authority_seed: None,
+                event: event.clone(),
+            }),
+            [program_info, authority_info, authority_bump, authority_seed, event] => {
+                Ok(Self::Explicit {
+                    program_info: program_info.clone(),
+                    authority_info: authority_info.clone(),
+                    authority_bump: authority_bump.clone(),
+                    authority_seed: Some(authority_seed.clone()),
+                    event: event.clone(),
+                })
+            }
+            [syn::Expr::Path(path), _] if path.path.is_ident("ctx") => {
+                let mut err = usage(Some(2));
+                err.combine(syn::Error::new(
+                    path.span(),
+                    "`ctx` is used implicitly, remove this argument",
+                ));
+                Err(err)
+            }
+            _ => Err(usage(Some(args.len()))),
+        }
+    }
+}
+
+/// Generate the expression that builds the self-CPI event instruction, or the instruction that
+/// forwards the event to the `target` program.
+#[cfg(feature = "event-cpi")]
+fn gen_cpi_event_ix(
+    args: &EventCpiMacroArgs,
+    target: Option<&syn::Expr>,
+) -> proc_macro2::TokenStream {
+    let authority_seeds = EventAuthority::get().seeds;
+
+    let (event_cpi_accounts, program_info, authority_info, correlation_id, event) = match args {
+        EventCpiMacroArgs::Ctx { event } => (
+            quote! {{
+                // Fails to compile if the accounts struct isn't annotated with `#[event_cpi]`
+                fn event_cpi_accounts<T: anchor_lang::__private::EventCpiAccounts>(
+                    _: &T,
+                    bumps: &T::Bumps,
+                ) -> (
+                    &'static [&'static [u8]],
+                    bool,
+                    Option<anchor_lang::solana_program::pubkey::Pubkey>,
+                    u8,
+                ) {
+                    (
+                        T::EVENT_AUTHORITY_SEEDS,
+                        T::EVENT_SEQUENCE,
+                        T::event_program_id(),
+                        T::event_authority_bump(bumps),
+                    )
+                }
+                event_cpi_accounts(&*ctx.accounts, &ctx.bumps)
+            }},
+            quote! { anchor_lang::event::EventCpiAccountInfos::event_program_info(&*ctx.accounts) },
+            quote! { anchor_lang::event::EventCpiAccountInfos::event_authority_info(&*ctx.accounts) },
+            quote! { ctx.correlation_id },
+            event,
+        ),
+        EventCpiMacroArgs::Explicit {
+            program_info,
+            authority_info,
+            authority_bump,
+            authority_seed,
+            event,
+        } => (
+            match authority_seed {
+                Some(authority_seed) => {
+                    quote! { (&[#authority_seeds, #authority_seed], false, None, #authority_bump) }
+                }
+                None => quote! { (&[#authority_seeds], false, None, #authority_bump) },
+            },
+            quote! { #program_info },
+            quote! { #authority_info },
+            quote! { None },
+            event,
+        ),
+    };
+
+    // Forwarded events also pass the emitting program so that the target can verify the signer
+    let (target_program_id, source_program_meta) = match target {
+        Some(target) => (
+            quote! {{
+                let _ = event_program_id;
+                *anchor_lang::ToAccountInfo::to_account_info(&#target).key
+            }},
+            quote! {
+                anchor_lang::solana_program::instruction::AccountMeta::new_readonly(
+                    *program_info.key,
+                    false,
+                ),
+            },
+        ),
+        None => (quote! { event_program_id.unwrap_or(crate::ID) }, quote! {}),
+    };
+
+    // Only self-CPI events are numbered, forwarded events keep the same data as without sequence
+    let is_self_cpi = target.is_none();
+
+    quote! {
+        {
+            let (authority_seeds, sequenced, event_program_id, authority_bump): (
+                &'static [&'static [u8]],
+                bool,
+                Option<anchor_lang::solana_program::pubkey::Pubkey>,
+                u8,
+            ) = #event_cpi_accounts;
 
-            let disc = anchor_lang::event::EVENT_IX_TAG_LE;
-            let inner_data = anchor_lang::Event::data(&#event_struct);
-            let ix_data: Vec<u8> = disc.into_iter().chain(inner_data.into_iter()).collect();
+            let program_info = anchor_lang::ToAccountInfo::to_account_info(&#program_info);
+            let authority_info = anchor_lang::ToAccountInfo::to_account_info(&#authority_info);
+            let sequenced = sequenced && #is_self_cpi;
+
+            let mut ix_data = Vec::with_capacity(256);
+            // Event authorities with a seed suffix pass their bump, so that the program checks
+            // the signer without deriving the PDA of every suffix
+            if #is_self_cpi && authority_seeds[1..].iter().any(|seed| !seed.is_empty()) {
+                ix_data.extend_from_slice(&anchor_lang::event::EVENT_AUTHORITY_BUMP_IX_TAG_LE);
+                ix_data.push(authority_bump);
+            }
+            if sequenced {
+                // The sequence number is written by `EventCpiInstruction::invoke`
+                ix_data.extend_from_slice(&anchor_lang::event::EVENT_SEQUENCE_IX_TAG_LE);
+                ix_data.extend_from_slice(&[0; 8]);
+            } else {
+                ix_data.extend_from_slice(&anchor_lang::event::EVENT_IX_TAG_LE);
+            }
+            {
+                use anchor_lang::Event as _;
+                (#event).emit_data_into(&mut ix_data, #correlation_id);
+            }
 
             let ix = anchor_lang::solana_program::instruction::Instruction::new_with_bytes(
-                crate::ID,
+                #target_program_id,
                 &ix_data,
                 vec![
                     anchor_lang::solana_program::instruction::AccountMeta::new_readonly(
                         *authority_info.key,
                         true,
                     ),
+                    #source_program_meta
                 ],
             );
-            anchor_lang::solana_program::program::invoke_signed(
-                &ix,
-                &[authority_info],
-                &[&[#authority_seeds, &[authority_bump]]],
-            )
-            .map_err(anchor_lang::error::Error::from)?;
+
+            anchor_lang::event::EventCpiInstruction {
+                instruction: ix,
+                account_infos: [authority_info, program_info],
+                authority_seeds,
+                authority_bump,
+                sequenced,
+            }
         }
-    })
+    }
 }
 
 /// An attribute macro to add necessary event CPI accounts to the given accounts struct.
 ///
-/// Two accounts named `event_authority` and `program` will be appended to the list of accounts.
+/// Two accounts named `event_authority` and `program` will be appended to the list of accounts,
+/// unless `position = "front"` is specified.
 ///
 /// # Example
 ///
@@ -209,18 +2343,251 @@ pub fn emit_cpi(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 ///    /// CHECK: Self-CPI will fail if the program is not the current program
 ///    pub program: AccountInfo<'info>,
 /// }
+///
+/// impl<'info> anchor_lang::__private::EventCpiAccounts for MyInstruction<'info> {
+///     fn event_authority_bump(bumps: &MyInstructionBumps) -> u8 {
+///         bumps.event_authority
+///     }
+/// }
+/// ```
+///
+/// [`emit_cpi!`](emit_cpi!) reads the bump of the event authority through
+/// `EventCpiAccounts::event_authority_bump`, so the bump is always available to contexts whose
+/// accounts struct is annotated, and a context whose accounts struct isn't fails to compile.
+///
+/// # Arguments
+///
+/// - `position = "front" | "back"`: Where to place the event CPI accounts. Defaults to `"back"`,
+///   which appends them after the other accounts. Use `"front"` to prepend them instead, e.g. to
+///   keep the indices of the other accounts stable for existing clients. The IDL follows the same
+///   order.
+///
+/// ```ignore
+/// #[event_cpi(position = "front")]
+/// #[derive(Accounts)]
+/// pub struct MyInstruction<'info> {
+///    pub signer: Signer<'info>,
+/// }
+/// ```
+///
+/// - `seed = "<suffix>"`: Derive the event authority from `[b"__event_authority", b"<suffix>"]`
+///   instead of `[b"__event_authority"]`, e.g. to give independent parts of a program their own
+///   event authority. [`emit_cpi!`](emit_cpi!) signs with the matching seeds. The suffix can be
+///   at most 32 bytes, and an empty suffix is the same as the default event authority.
+///
+///   The self-CPI instructions of these event authorities are prefixed with
+///   [`EVENT_AUTHORITY_BUMP_IX_TAG_LE`](../anchor_lang/event/constant.EVENT_AUTHORITY_BUMP_IX_TAG_LE.html)
+///   and the bump of the event authority, so that the program checks the signer with
+///   `create_program_address` once per distinct suffix instead of deriving their PDAs.
+///   [`split_event_ix_data`](../anchor_lang/event/fn.split_event_ix_data.html) skips the prefix.
+///
+/// ```ignore
+/// #[event_cpi(seed = "vault")]
+/// #[derive(Accounts)]
+/// pub struct MyVaultInstruction<'info> {
+///    pub signer: Signer<'info>,
+/// }
+/// ```
+///
+/// - `sequence`: Number the events emitted with [`emit_cpi!`](emit_cpi!), so that indexers can
+///   detect dropped events. The event authority becomes a writable account of the program that
+///   stores the sequence number of the next event, see
+///   [`EventSequence`](../anchor_lang/event/struct.EventSequence.html). Each event increments
+///   it, and its self-CPI instruction is tagged with
+///   [`EVENT_SEQUENCE_IX_TAG_LE`](../anchor_lang/event/constant.EVENT_SEQUENCE_IX_TAG_LE.html)
+///   followed by the sequence number, instead of `EVENT_IX_TAG_LE`. The numbers are assigned
+///   when the events are invoked, i.e. in the order they're emitted, and are shared by all
+///   instructions with the same event authority. Events built with explicit accounts or
+///   forwarded with [`forward_cpi_event!`](forward_cpi_event!) are not numbered.
+///
+///   The account must be created once before the first event, e.g. in an initialization
+///   instruction. It holds 16 bytes, so creating it costs the rent-exempt minimum of about
+///   0.001 SOL, paid by `payer`:
+///
+/// ```ignore
+/// pub fn init_events(ctx: Context<InitEvents>) -> Result<()> {
+///     anchor_lang::event::EventSequence::init(
+///         &ctx.accounts.event_authority,
+///         &ctx.accounts.payer.to_account_info(),
+///         &ctx.accounts.system_program.to_account_info(),
+///         ctx.program_id,
+///         &[b"__event_authority"],
+///         ctx.bumps.event_authority,
+///     )
+/// }
+///
+/// #[event_cpi(sequence)]
+/// #[derive(Accounts)]
+/// pub struct InitEvents<'info> {
+///    #[account(mut)]
+///    pub payer: Signer<'info>,
+///    pub system_program: Program<'info, System>,
+/// }
+///
+/// #[event_cpi(sequence)]
+/// #[derive(Accounts)]
+/// pub struct MySequencedInstruction<'info> {
+///    pub signer: Signer<'info>,
+/// }
+/// ```
+///
+/// Emitting fails with `ErrorCode::EventSequenceNotInitialized` until the account is created.
+///
+/// - `program_id = <expr>`: Check that the `program` account is the given program with an
+///   `address` constraint, and target it with the CPI of the events emitted with the accounts
+///   of `ctx`, instead of `crate::ID`. This supports programs deployed under multiple IDs, or
+///   that emit their events through a proxy program. The expression is evaluated each time the
+///   accounts are validated and an event is emitted, e.g. `registry::ID` or a function call.
+///
+/// ```ignore
+/// #[event_cpi(program_id = registry::event_program_id())]
+/// #[derive(Accounts)]
+/// pub struct MyProxiedInstruction<'info> {
+///    pub signer: Signer<'info>,
+/// }
 /// ```
 ///
+/// The address of the `event_authority` account can be derived with the `event_authority_pda`
+/// function that [`#[program]`](../anchor_lang/attr.program.html) generates next to the program
+/// module, e.g. `my_program::event_authority_pda(&my_program::ID)`, or with
+/// `event_authority_pda_with_seed` for a `seed` suffix.
+///
 /// See [`emit_cpi!`](emit_cpi!) for a full example.
 ///
 /// *Only available with `event-cpi` feature enabled.*
 #[cfg(feature = "event-cpi")]
 #[proc_macro_attribute]
 pub fn event_cpi(
-    _attr: proc_macro::TokenStream,
+    args: proc_macro::TokenStream,
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(args as EventCpiArgs);
     let accounts_struct = parse_macro_input!(input as syn::ItemStruct);
-    let accounts_struct = add_event_cpi_accounts(&accounts_struct).unwrap();
-    proc_macro::TokenStream::from(quote! {#accounts_struct})
+    let accounts_struct = match add_event_cpi_accounts(&accounts_struct, &args) {
+        Ok(accounts_struct) => accounts_struct,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let ident = &accounts_struct.ident;
+    let (impl_generics, ty_generics, where_clause) = accounts_struct.generics.split_for_impl();
+    // `add_event_cpi_accounts` adds an `'info` lifetime to structs without one
+    let info_lifetime = &accounts_struct
+        .generics
+        .lifetimes()
+        .next()
+        .expect("Event CPI accounts must have a lifetime")
+        .lifetime;
+    let authority_name = EventAuthority::get().name_token_stream();
+    let authority_seeds = args.seed.as_ref().map(|seed| {
+        let seeds = EventAuthority::get().seeds_with_suffix(Some(seed));
+        quote! { const EVENT_AUTHORITY_SEEDS: &'static [&'static [u8]] = &[#seeds]; }
+    });
+    let sequence = args
+        .sequence
+        .then(|| quote! { const EVENT_SEQUENCE: bool = true; });
+    let program_id = args.program_id.as_ref().map(|program_id| {
+        quote! {
+            fn event_program_id() -> Option<anchor_lang::solana_program::pubkey::Pubkey> {
+                Some(#program_id)
+            }
+        }
+    });
+
+    proc_macro::TokenStream::from(quote! {
+        #accounts_struct
+
+        #[automatically_derived]
+        impl #impl_generics anchor_lang::__private::EventCpiAccounts for #ident #ty_generics #where_clause {
+            #authority_seeds
+            #sequence
+            #program_id
+
+            fn event_authority_bump(bumps: &<Self as anchor_lang::Bumps>::Bumps) -> u8 {
+                bumps.#authority_name
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_generics anchor_lang::event::EventCpiAccountInfos<#info_lifetime> for #ident #ty_generics #where_clause {
+            fn event_authority_info(
+                &self,
+            ) -> &anchor_lang::solana_program::account_info::AccountInfo<#info_lifetime> {
+                &self.#authority_name
+            }
+
+            fn event_program_info(
+                &self,
+            ) -> &anchor_lang::solana_program::account_info::AccountInfo<#info_lifetime> {
+                &self.program
+            }
+        }
+    })
+}
+
+/// Stub of [`emit_cpi!`](emit_cpi!) that fails to compile when the `event-cpi` feature is
+/// disabled, so that programs get a clear error instead of an unresolved macro.
+#[cfg(not(feature = "event-cpi"))]
+#[doc(hidden)]
+#[proc_macro]
+pub fn emit_cpi(_input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let error = event_cpi_disabled("emit_cpi!");
+    proc_macro::TokenStream::from(quote! { { #error } })
+}
+
+/// Stub of [`emit_auto!`](emit_auto!) when the `event-cpi` feature is disabled, see
+/// [`emit_cpi!`](emit_cpi!).
+#[cfg(not(feature = "event-cpi"))]
+#[doc(hidden)]
+#[proc_macro]
+pub fn emit_auto(_input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let error = event_cpi_disabled("emit_auto!");
+    proc_macro::TokenStream::from(quote! { { #error } })
+}
+
+/// Stub of [`build_cpi_event_ix!`](build_cpi_event_ix!) when the `event-cpi` feature is
+/// disabled, see [`emit_cpi!`](emit_cpi!).
+#[cfg(not(feature = "event-cpi"))]
+#[doc(hidden)]
+#[proc_macro]
+pub fn build_cpi_event_ix(_input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let error = event_cpi_disabled("build_cpi_event_ix!");
+    proc_macro::TokenStream::from(quote! { { #error } })
+}
+
+/// Stub of [`forward_cpi_event!`](forward_cpi_event!) when the `event-cpi` feature is
+/// disabled, see [`emit_cpi!`](emit_cpi!).
+#[cfg(not(feature = "event-cpi"))]
+#[doc(hidden)]
+#[proc_macro]
+pub fn forward_cpi_event(_input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let error = event_cpi_disabled("forward_cpi_event!");
+    proc_macro::TokenStream::from(quote! { { #error } })
+}
+
+/// Stub of [`#[event_cpi]`](event_cpi) when the `event-cpi` feature is disabled, see
+/// [`emit_cpi!`](emit_cpi!). The accounts struct is kept as is to avoid follow-up errors.
+#[cfg(not(feature = "event-cpi"))]
+#[doc(hidden)]
+#[proc_macro_attribute]
+pub fn event_cpi(
+    _args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let input = proc_macro2::TokenStream::from(input);
+    let error = event_cpi_disabled("#[event_cpi]");
+
+    proc_macro::TokenStream::from(quote! {
+        #error;
+        #input
+    })
+}
+
+/// Generate the compile error of the event CPI macros when the `event-cpi` feature is disabled.
+#[cfg(not(feature = "event-cpi"))]
+fn event_cpi_disabled(name: &str) -> proc_macro2::TokenStream {
+    let msg = format!(
+        "`{name}` requires the `event-cpi` feature, enable it with \
+        `anchor-lang = {{ version = \"...\", features = [\"event-cpi\"] }}` in Cargo.toml"
+    );
+    quote! { compile_error!(#msg) }
 }
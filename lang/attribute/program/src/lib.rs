@@ -5,14 +5,44 @@ use syn::parse_macro_input;
 
 /// The `#[program]` attribute defines the module containing all instruction
 /// handlers defining all entries into a Solana program.
+///
+/// # Arguments
+///
+/// - `max_events = <N>`: Fail instructions that emit more than `N` events with
+///   `ErrorCode::TooManyEvents`. This is a safety rail against loops that emit
+///   events unboundedly, which could hit the log limit or break indexers.
+///
+///   The events are counted by the `Context` of the handler, so the count starts
+///   over for every instruction. Only the events emitted through the context are
+///   counted, i.e. `emit!(ctx, event)`, `emit_on_success!`, and `emit_cpi!` and
+///   `emit_auto!` with the accounts of `ctx`, including by the functions the
+///   handlers pass the context to. Logged events past the limit aren't logged
+///   and the instruction fails once the handler returns, while `emit_cpi!`
+///   fails right away.
+///
+/// ```ignore
+/// #[program(max_events = 32)]
+/// pub mod my_program {
+///     use super::*;
+///
+///     pub fn airdrop(ctx: Context<Airdrop>, amounts: Vec<u64>) -> Result<()> {
+///         for amount in amounts {
+///             // Fails with `ErrorCode::TooManyEvents` after 32 events
+///             emit!(ctx, AirdropEvent { amount });
+///         }
+///         Ok(())
+///     }
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn program(
-    _args: proc_macro::TokenStream,
+    args: proc_macro::TokenStream,
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    parse_macro_input!(input as anchor_syn::Program)
-        .to_token_stream()
-        .into()
+    let args = parse_macro_input!(args as anchor_syn::ProgramArgs);
+    let mut program = parse_macro_input!(input as anchor_syn::Program);
+    program.args = args;
+    program.to_token_stream().into()
 }
 
 /// The `#[interface]` attribute is used to mark an instruction as belonging
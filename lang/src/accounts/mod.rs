@@ -8,6 +8,7 @@ pub mod interface;
 pub mod interface_account;
 pub mod option;
 pub mod program;
+pub mod ring_buffer;
 pub mod signer;
 pub mod system_account;
 pub mod sysvar;
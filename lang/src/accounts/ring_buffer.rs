@@ -0,0 +1,305 @@
+//! Account storing the most recent events of a program, see
+//! [`emit_to_account!`](crate::emit_to_account)
+
+use crate::error::{Error, ErrorCode};
+use crate::*;
+use solana_program::account_info::AccountInfo;
+use solana_program::instruction::AccountMeta;
+use solana_program::pubkey::Pubkey;
+use std::collections::BTreeSet;
+use std::ops::Deref;
+
+/// Discriminator of initialized [`RingBuffer`] accounts, i.e. the first 8 bytes of
+/// `sha256("anchor:ring_buffer")`.
+pub const RING_BUFFER_DISCRIMINATOR: [u8; 8] = [167, 6, 221, 81, 182, 236, 123, 194];
+
+/// Account storing the most recent events emitted with
+/// [`emit_to_account!`](crate::emit_to_account), so that they can be read on-chain, e.g. by
+/// other programs.
+///
+/// Checks:
+///
+/// - `RingBuffer.info.owner == program_id`
+/// - `RingBuffer.info.data` starts with [`RING_BUFFER_DISCRIMINATOR`], unless the account is
+///   created with the `init` or `zero` constraint, in which case it must be
+///   [initialized](RingBuffer::initialize) before events are pushed to it
+///
+/// The account data is a header followed by a fixed number of slots of the same size, which
+/// each hold one serialized event, i.e. the bytes of [`Event::data`]:
+///
+/// ```text
+/// discriminator: [u8; 8]
+/// slot_len:      u32     // maximum length of the data of an event
+/// capacity:      u32     // number of slots
+/// head:          u64     // number of events pushed so far
+/// slots:         [(u32, [u8; slot_len]); capacity]
+/// ```
+///
+/// All integers are little endian. The `n`th event pushed to the buffer, starting at 0, is its
+/// sequence number and is stored in slot `n % capacity`, so once the buffer is full every event
+/// overwrites the oldest one. Use [`RingBuffer::space`] to size the account:
+///
+/// ```ignore
+/// #[derive(Accounts)]
+/// pub struct InitEvents<'info> {
+///     #[account(init, payer = payer, space = RingBuffer::space(16, 64))]
+///     pub events: RingBuffer<'info>,
+///     #[account(mut)]
+///     pub payer: Signer<'info>,
+///     pub system_program: Program<'info, System>,
+/// }
+///
+/// pub fn init_events(ctx: Context<InitEvents>) -> Result<()> {
+///     ctx.accounts.events.initialize(64)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RingBuffer<'info> {
+    info: &'info AccountInfo<'info>,
+}
+
+const SLOT_LEN_OFFSET: usize = 8;
+const CAPACITY_OFFSET: usize = 12;
+const HEAD_OFFSET: usize = 16;
+
+impl<'info> RingBuffer<'info> {
+    /// Length of the header that precedes the slots.
+    pub const HEADER_LEN: usize = 24;
+
+    /// Length of the prefix of every slot that holds the length of its event.
+    pub const SLOT_PREFIX_LEN: usize = 4;
+
+    fn new(info: &'info AccountInfo<'info>) -> RingBuffer<'info> {
+        Self { info }
+    }
+
+    /// Returns the space of a buffer of `capacity` events of at most `slot_len` bytes.
+    pub const fn space(capacity: u32, slot_len: u32) -> usize {
+        Self::HEADER_LEN + capacity as usize * (Self::SLOT_PREFIX_LEN + slot_len as usize)
+    }
+
+    /// Constructs a new buffer from an initialized account owned by `program_id`.
+    #[inline(never)]
+    pub fn try_from(
+        program_id: &Pubkey,
+        info: &'info AccountInfo<'info>,
+    ) -> Result<RingBuffer<'info>> {
+        let buffer = Self::try_from_unchecked(program_id, info)?;
+        let data = info.try_borrow_data()?;
+        if data.len() < Self::HEADER_LEN {
+            return Err(ErrorCode::AccountDiscriminatorNotFound.into());
+        }
+        if data[..8] != RING_BUFFER_DISCRIMINATOR {
+            return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+        drop(data);
+        Ok(buffer)
+    }
+
+    /// Constructs a new buffer from an account owned by `program_id` without checking that it
+    /// is initialized, e.g. right after it is created.
+    #[inline(never)]
+    pub fn try_from_unchecked(
+        program_id: &Pubkey,
+        info: &'info AccountInfo<'info>,
+    ) -> Result<RingBuffer<'info>> {
+        if info.owner != program_id {
+            return Err(Error::from(ErrorCode::AccountOwnedByWrongProgram)
+                .with_pubkeys((*info.owner, *program_id)));
+        }
+        Ok(RingBuffer::new(info))
+    }
+
+    /// Writes the header of an empty buffer of events of at most `slot_len` bytes. The
+    /// capacity is the number of slots that fit in the account.
+    ///
+    /// Fails if the buffer is already initialized, or if not even one slot fits.
+    pub fn initialize(&self, slot_len: u32) -> Result<()> {
+        let mut data = self.info.try_borrow_mut_data()?;
+        if data.len() >= 8 && data[..8] != [0; 8] {
+            return Err(ErrorCode::AccountDiscriminatorAlreadySet.into());
+        }
+        let slot_size = Self::SLOT_PREFIX_LEN + slot_len as usize;
+        let capacity = data.len().saturating_sub(Self::HEADER_LEN) / slot_size;
+        if slot_len == 0 || capacity == 0 {
+            return Err(ErrorCode::RingBufferTooSmall.into());
+        }
+        let capacity = u32::try_from(capacity).unwrap_or(u32::MAX);
+
+        data[..8].copy_from_slice(&RING_BUFFER_DISCRIMINATOR);
+        data[SLOT_LEN_OFFSET..CAPACITY_OFFSET].copy_from_slice(&slot_len.to_le_bytes());
+        data[CAPACITY_OFFSET..HEAD_OFFSET].copy_from_slice(&capacity.to_le_bytes());
+        data[HEAD_OFFSET..Self::HEADER_LEN].copy_from_slice(&0u64.to_le_bytes());
+        Ok(())
+    }
+
+    /// Returns the maximum length of the data of an event, or 0 if the buffer is not
+    /// initialized.
+    pub fn slot_len(&self) -> u32 {
+        self.header().0
+    }
+
+    /// Returns the number of events the buffer holds once it's full, or 0 if the buffer is not
+    /// initialized.
+    pub fn capacity(&self) -> u32 {
+        self.header().1
+    }
+
+    /// Returns the number of events pushed to the buffer so far, which is also the sequence
+    /// number of the next event.
+    pub fn head(&self) -> u64 {
+        self.header().2
+    }
+
+    /// Returns the number of events in the buffer.
+    pub fn len(&self) -> usize {
+        let (_, capacity, head) = self.header();
+        head.min(capacity.into()) as usize
+    }
+
+    /// Returns `true` if no event was pushed to the buffer.
+    pub fn is_empty(&self) -> bool {
+        self.head() == 0
+    }
+
+    /// Stores the serialized event `event_data`, overwriting the oldest event if the buffer is
+    /// full, and returns its sequence number.
+    ///
+    /// Fails if the buffer is not initialized, or if `event_data` is longer than the
+    /// [`slot_len`](RingBuffer::slot_len) of the buffer.
+    pub fn push(&self, event_data: &[u8]) -> Result<u64> {
+        let (slot_len, capacity, head) = self.header();
+        if capacity == 0 {
+            return Err(ErrorCode::AccountNotInitialized.into());
+        }
+        if event_data.len() > slot_len as usize {
+            return Err(ErrorCode::EventTooLargeForRingBuffer.into());
+        }
+
+        let mut data = self.info.try_borrow_mut_data()?;
+        let start = Self::slot_offset(slot_len, head % u64::from(capacity));
+        let (prefix, slot) = data[start..].split_at_mut(Self::SLOT_PREFIX_LEN);
+        prefix.copy_from_slice(&(event_data.len() as u32).to_le_bytes());
+        slot[..event_data.len()].copy_from_slice(event_data);
+        data[HEAD_OFFSET..Self::HEADER_LEN].copy_from_slice(&(head + 1).to_le_bytes());
+        Ok(head)
+    }
+
+    /// Serializes `event` into the buffer, see [`push`](RingBuffer::push).
+    pub fn push_event<E: Event>(&self, event: &E) -> Result<u64> {
+        self.push(&event.data())
+    }
+
+    /// Returns the data of the event with the given sequence number, or `None` if it was
+    /// overwritten or not pushed yet.
+    pub fn get(&self, sequence: u64) -> Option<Vec<u8>> {
+        let (slot_len, capacity, head) = self.header();
+        if sequence >= head || head - sequence > u64::from(capacity) {
+            return None;
+        }
+        let data = self.info.try_borrow_data().ok()?;
+        let start = Self::slot_offset(slot_len, sequence % u64::from(capacity));
+        let (prefix, slot) = data[start..].split_at(Self::SLOT_PREFIX_LEN);
+        let len = u32::from_le_bytes(prefix.try_into().unwrap()).min(slot_len) as usize;
+        Some(slot[..len].to_vec())
+    }
+
+    /// Returns the data of the events in the buffer, from the oldest to the most recent one.
+    pub fn events(&self) -> Vec<Vec<u8>> {
+        let head = self.head();
+        (head - self.len() as u64..head)
+            .filter_map(|sequence| self.get(sequence))
+            .collect()
+    }
+
+    /// Deserializes the events of type `E` in the buffer, from the oldest to the most recent
+    /// one, skipping the events of other types.
+    pub fn decode<E: Event>(&self) -> Vec<E> {
+        self.events()
+            .iter()
+            .filter_map(|data| E::try_from_event_data(data).ok())
+            .collect()
+    }
+
+    fn slot_offset(slot_len: u32, slot: u64) -> usize {
+        Self::HEADER_LEN + slot as usize * (Self::SLOT_PREFIX_LEN + slot_len as usize)
+    }
+
+    // Returns the slot length, the capacity and the head, which are all 0 if the buffer isn't
+    // initialized or its data is invalid.
+    fn header(&self) -> (u32, u32, u64) {
+        let data = match self.info.try_borrow_data() {
+            Ok(data) => data,
+            Err(_) => return (0, 0, 0),
+        };
+        if data.len() < Self::HEADER_LEN || data[..8] != RING_BUFFER_DISCRIMINATOR {
+            return (0, 0, 0);
+        }
+        let slot_len =
+            u32::from_le_bytes(data[SLOT_LEN_OFFSET..CAPACITY_OFFSET].try_into().unwrap());
+        let capacity = u32::from_le_bytes(data[CAPACITY_OFFSET..HEAD_OFFSET].try_into().unwrap());
+        let head = u64::from_le_bytes(data[HEAD_OFFSET..Self::HEADER_LEN].try_into().unwrap());
+        // Don't trust a header whose slots don't fit in the account
+        if Self::space(capacity, slot_len) > data.len() {
+            return (0, 0, 0);
+        }
+        (slot_len, capacity, head)
+    }
+}
+
+impl<'info, B> Accounts<'info, B> for RingBuffer<'info> {
+    #[inline(never)]
+    fn try_accounts(
+        program_id: &Pubkey,
+        accounts: &mut &'info [AccountInfo<'info>],
+        _ix_data: &[u8],
+        _bumps: &mut B,
+        _reallocs: &mut BTreeSet<Pubkey>,
+    ) -> Result<Self> {
+        if accounts.is_empty() {
+            return Err(ErrorCode::AccountNotEnoughKeys.into());
+        }
+        let account = &accounts[0];
+        *accounts = &accounts[1..];
+        RingBuffer::try_from(program_id, account)
+    }
+}
+
+impl<'info> AccountsExit<'info> for RingBuffer<'info> {}
+
+impl<'info> ToAccountMetas for RingBuffer<'info> {
+    fn to_account_metas(&self, is_signer: Option<bool>) -> Vec<AccountMeta> {
+        let is_signer = is_signer.unwrap_or(self.info.is_signer);
+        let meta = match self.info.is_writable {
+            false => AccountMeta::new_readonly(*self.info.key, is_signer),
+            true => AccountMeta::new(*self.info.key, is_signer),
+        };
+        vec![meta]
+    }
+}
+
+impl<'info> ToAccountInfos<'info> for RingBuffer<'info> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![self.info.clone()]
+    }
+}
+
+impl<'info> AsRef<AccountInfo<'info>> for RingBuffer<'info> {
+    fn as_ref(&self) -> &AccountInfo<'info> {
+        self.info
+    }
+}
+
+impl<'info> Deref for RingBuffer<'info> {
+    type Target = AccountInfo<'info>;
+
+    fn deref(&self) -> &Self::Target {
+        self.info
+    }
+}
+
+impl<'info> Key for RingBuffer<'info> {
+    fn key(&self) -> Pubkey {
+        *self.info.key
+    }
+}
@@ -1,5 +1,6 @@
 //! Data structures that are used to provide non-argument inputs to program endpoints
 
+use crate::event::{CorrelationId, EventSink, InstructionEvents};
 use crate::{Accounts, Bumps, ToAccountInfos, ToAccountMetas};
 use solana_program::account_info::AccountInfo;
 use solana_program::instruction::AccountMeta;
@@ -34,6 +35,14 @@ pub struct Context<'a, 'b, 'c, 'info, T: Bumps> {
     /// pass them in as arguments.
     /// Type is the bumps struct generated by #[derive(Accounts)]
     pub bumps: T::Bumps,
+    /// Events held back until the instruction succeeds, see
+    /// [`emit_on_success!`](crate::prelude::emit_on_success), and the number of events emitted
+    /// through the context. Set by the instruction dispatch generated by `#[program]`.
+    pub events: Option<&'a InstructionEvents>,
+    /// Id the instruction was invoked with, see
+    /// [`CpiContext::with_correlation_id`], which the emit macros write to the events of
+    /// [`#[event(correlated)]`](crate::event) types emitted through the context.
+    pub correlation_id: Option<CorrelationId>,
 }
 
 impl<'a, 'b, 'c, 'info, T> fmt::Debug for Context<'a, 'b, 'c, 'info, T>
@@ -46,6 +55,8 @@ where
             .field("accounts", &self.accounts)
             .field("remaining_accounts", &self.remaining_accounts)
             .field("bumps", &self.bumps)
+            .field("events", &self.events)
+            .field("correlation_id", &self.correlation_id)
             .finish()
     }
 }
@@ -65,8 +76,62 @@ where
             accounts,
             remaining_accounts,
             bumps,
+            events: None,
+            correlation_id: None,
         }
     }
+
+    #[must_use]
+    pub fn with_events(mut self, events: &'a InstructionEvents) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Sets the correlation id of the events emitted through the context, e.g. to start a flow
+    /// with an id passed as an instruction argument. See [`CorrelationId`].
+    #[must_use]
+    pub fn with_correlation_id(mut self, id: CorrelationId) -> Self {
+        self.correlation_id = Some(id);
+        self
+    }
+}
+
+impl<'a, 'b, 'c, 'info, T: Bumps> Context<'a, 'b, 'c, 'info, T> {
+    /// Counts `count` events emitted by the instruction against the `max_events` of
+    /// [`#[program(max_events = <N>)]`](crate::program), and returns whether they're within
+    /// it. Without [`events`](Context::events), the events are never limited.
+    pub fn count_events(&self, count: usize) -> bool {
+        self.events
+            .map_or(true, |events| events.count_events(count))
+    }
+
+    /// Stores a serialized event in the [`events`](Context::events) of the instruction until it
+    /// succeeds, which is what [`emit_on_success!`](crate::prelude::emit_on_success) does.
+    ///
+    /// Without `events`, e.g. for a context created with [`Context::new`] outside of the
+    /// instruction dispatch, the event is logged right away.
+    pub fn defer_event(&self, data: &[&[u8]]) {
+        match self.events {
+            Some(events) => events.defer(data),
+            None => crate::event::log_data(data),
+        }
+    }
+}
+
+/// Logs the events of [`emit!(ctx, event)`](crate::prelude::emit) with the correlation id of
+/// the context, counting them against the `max_events` of
+/// [`#[program(max_events = <N>)]`](crate::program).
+impl<'a, 'b, 'c, 'info, T: Bumps> EventSink for Context<'a, 'b, 'c, 'info, T> {
+    fn emit_data(&self, data: &[&[u8]]) {
+        match self.events {
+            Some(events) => events.log(data),
+            None => crate::event::log_data(data),
+        }
+    }
+
+    fn correlation_id(&self) -> Option<CorrelationId> {
+        self.correlation_id
+    }
 }
 
 /// Context specifying non-argument inputs for cross-program-invocations.
@@ -176,6 +241,9 @@ where
     pub remaining_accounts: Vec<AccountInfo<'info>>,
     pub program: AccountInfo<'info>,
     pub signer_seeds: &'a [&'b [&'c [u8]]],
+    /// Correlation id passed to the [`Context`] of the invoked instruction, see
+    /// [`with_correlation_id`](CpiContext::with_correlation_id).
+    pub correlation_id: Option<CorrelationId>,
 }
 
 impl<'a, 'b, 'c, 'info, T> CpiContext<'a, 'b, 'c, 'info, T>
@@ -188,6 +256,7 @@ where
             program,
             remaining_accounts: Vec::new(),
             signer_seeds: &[],
+            correlation_id: None,
         }
     }
 
@@ -202,6 +271,7 @@ where
             program,
             signer_seeds,
             remaining_accounts: Vec::new(),
+            correlation_id: None,
         }
     }
 
@@ -216,6 +286,24 @@ where
         self.remaining_accounts = ra;
         self
     }
+
+    /// Passes `id` to the [`Context`] of the invoked instruction, so that the
+    /// [`#[event(correlated)]`](crate::event) events it emits through the context carry the same
+    /// id as the events of the caller.
+    ///
+    /// The `cpi` functions generated for Anchor programs prefix the instruction data with
+    /// [`CORRELATION_ID_IX_TAG_LE`](crate::event::CORRELATION_ID_IX_TAG_LE) and the id, so the
+    /// callee must be an Anchor program that dispatches the tag.
+    ///
+    /// ```ignore
+    /// let cpi_ctx = CpiContext::new(router, accounts).with_correlation_id(correlation_id);
+    /// router::cpi::route(cpi_ctx, amount)?;
+    /// ```
+    #[must_use]
+    pub fn with_correlation_id(mut self, id: CorrelationId) -> Self {
+        self.correlation_id = Some(id);
+        self
+    }
 }
 
 impl<'info, T: ToAccountInfos<'info> + ToAccountMetas> ToAccountInfos<'info>
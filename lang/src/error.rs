@@ -48,6 +48,42 @@ pub enum ErrorCode {
     /// 1500 - The program was compiled without `event-cpi` feature
     #[msg("The program was compiled without `event-cpi` feature")]
     EventInstructionStub = 1500,
+    /// 1501 - The event discriminator did not match what was expected
+    #[msg("The event discriminator did not match what was expected")]
+    InvalidEventDiscriminator,
+    /// 1502 - The event version did not match what was expected
+    #[msg("The event version did not match what was expected")]
+    InvalidEventVersion,
+    /// 1503 - The self-CPI of an event emitted with `emit_cpi!` failed
+    #[msg("The self-CPI of an event emitted with `emit_cpi!` failed")]
+    EventCpiFailed,
+    /// 1504 - The event authority account of `#[event_cpi(sequence)]` was not initialized
+    #[msg("The event authority account of `#[event_cpi(sequence)]` was not initialized")]
+    EventSequenceNotInitialized,
+    /// 1505 - The instruction emitted more events than the `max_events` of the program
+    #[msg("The instruction emitted more events than the `max_events` of the program")]
+    TooManyEvents,
+    /// 1506 - The event authority account of `emit_cpi!` is not the event authority PDA
+    #[msg("The event authority account of `emit_cpi!` is not the event authority PDA")]
+    EventAuthorityMismatch,
+    /// 1507 - The event is larger than the slots of the ring buffer account
+    #[msg("The event is larger than the slots of the ring buffer account")]
+    EventTooLargeForRingBuffer,
+    /// 1508 - The ring buffer account is too small to hold an event
+    #[msg("The ring buffer account is too small to hold an event")]
+    RingBufferTooSmall,
+    /// 1509 - The codec of the compressed event is unknown
+    #[msg("The codec of the compressed event is unknown")]
+    InvalidEventCodec,
+    /// 1510 - The compressed event data could not be decompressed
+    #[msg("The compressed event data could not be decompressed")]
+    InvalidCompressedEvent,
+    /// 1511 - The log line is not the `Program data:` log of a single event
+    #[msg("The log line is not the `Program data:` log of a single event")]
+    InvalidEventLog,
+    /// 1512 - The correlation id slot of the event data is invalid
+    #[msg("The correlation id slot of the event data is invalid")]
+    InvalidEventCorrelationId,
 
     // Constraints
     /// 2000 - A mut constraint was violated
@@ -1,3 +1,1323 @@
-// Sha256(anchor:event)[..8]
+//! Constants, types and helpers of events, e.g. event emitters for code outside of
+//! [`#[program]`](crate::program) and the types used by events emitted with self-CPI.
+
+use crate::Event;
+#[cfg(feature = "event-cpi")]
+use crate::{
+    context::CpiContext,
+    error::{AnchorError, ErrorCode},
+    prelude::{AccountInfo, Pubkey, Rent, Result, SolanaSysvar},
+    solana_program::{instruction::Instruction, program::invoke_signed},
+    system_program, AnchorDeserialize, AnchorSerialize, Discriminator,
+};
+
+/// Tag of the self-CPI event instructions of [`emit_cpi!`](crate::emit_cpi), i.e. the first 8
+/// bytes of `Sha256("anchor:event")` read as a big-endian integer.
 pub const EVENT_IX_TAG: u64 = 0x1d9acb512ea545e4;
+
+/// Little-endian bytes of [`EVENT_IX_TAG`] that prefix the data of self-CPI event instructions:
+/// `[0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d]`.
+///
+/// Off-chain parsers can match instruction data against this prefix to distinguish event
+/// instructions from other instructions. The event data follows the tag.
 pub const EVENT_IX_TAG_LE: [u8; 8] = EVENT_IX_TAG.to_le_bytes();
+
+/// Big-endian bytes of [`EVENT_IX_TAG`], i.e. `Sha256("anchor:event")[..8]`:
+/// `[0x1d, 0x9a, 0xcb, 0x51, 0x2e, 0xa5, 0x45, 0xe4]`.
+///
+/// This is **not** the prefix of event instructions: [`emit_cpi!`](crate::emit_cpi) always
+/// writes [`EVENT_IX_TAG_LE`]. It is provided for tooling that assumed the big-endian order, see
+/// [`split_event_ix_data_with`].
+pub const EVENT_IX_TAG_BE: [u8; 8] = EVENT_IX_TAG.to_be_bytes();
+
+/// Byte order of the [`EVENT_IX_TAG`] prefix accepted by [`split_event_ix_data_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventIxTagOrder {
+    /// [`EVENT_IX_TAG_LE`], which is what [`emit_cpi!`](crate::emit_cpi) writes.
+    LittleEndian,
+    /// [`EVENT_IX_TAG_BE`], e.g. for instruction data re-encoded by third-party indexers.
+    BigEndian,
+    /// Either [`EVENT_IX_TAG_LE`] or [`EVENT_IX_TAG_BE`].
+    Either,
+}
+
+impl Default for EventIxTagOrder {
+    fn default() -> Self {
+        Self::LittleEndian
+    }
+}
+
+/// Tag of the self-CPI event instructions of accounts structs annotated with
+/// [`#[event_cpi(sequence)]`](crate::event_cpi), i.e. the first 8 bytes of
+/// `Sha256("anchor:event_sequence")` read as a big-endian integer.
+pub const EVENT_SEQUENCE_IX_TAG: u64 = 0x001027f400d45628;
+
+/// Little-endian bytes of [`EVENT_SEQUENCE_IX_TAG`] that prefix the data of sequenced self-CPI
+/// event instructions. The tag is followed by the sequence number as a little-endian `u64`, and
+/// then by the event data, see [`split_event_ix_data`].
+pub const EVENT_SEQUENCE_IX_TAG_LE: [u8; 8] = EVENT_SEQUENCE_IX_TAG.to_le_bytes();
+
+/// Tag of the self-CPI event instructions signed by an event authority with a seed suffix, see
+/// [`#[event_cpi(seed = "..")]`](crate::event_cpi), i.e. the first 8 bytes of
+/// `Sha256("anchor:event_authority_bump")` read as a big-endian integer.
+pub const EVENT_AUTHORITY_BUMP_IX_TAG: u64 = 0x0bdb05d2c5e696cc;
+
+/// Little-endian bytes of [`EVENT_AUTHORITY_BUMP_IX_TAG`] that prefix the data of self-CPI event
+/// instructions signed by an event authority with a seed suffix. The tag is followed by the
+/// bump of the event authority, so that the program checks the signer with
+/// `create_program_address` instead of deriving the PDA of each suffix, and then by the data of
+/// the event instruction without a suffix, see [`split_event_ix_data`].
+pub const EVENT_AUTHORITY_BUMP_IX_TAG_LE: [u8; 8] = EVENT_AUTHORITY_BUMP_IX_TAG.to_le_bytes();
+
+/// Splits the data of a self-CPI event instruction into the sequence number and the event data,
+/// i.e. the discriminator followed by the payload.
+///
+/// The sequence number is only set for events emitted with
+/// [`#[event_cpi(sequence)]`](crate::event_cpi). The bump of event authorities with a seed
+/// suffix is skipped. Returns `None` if the data is not the data of an event instruction.
+///
+/// ```ignore
+/// if let Some((sequence, event)) = split_event_ix_data(&ix.data) {
+///     let event = MyEvent::try_from_event_data(event)?;
+/// }
+/// ```
+pub fn split_event_ix_data(data: &[u8]) -> Option<(Option<u64>, &[u8])> {
+    split_event_ix_data_with(data, EventIxTagOrder::LittleEndian)
+}
+
+/// Same as [`split_event_ix_data`], but accepts the [`EVENT_IX_TAG`] prefix in the given byte
+/// order, e.g. to decode data produced by tooling that assumed [`EVENT_IX_TAG_BE`].
+///
+/// Only the prefix of unsequenced events is affected, sequenced events are always prefixed with
+/// [`EVENT_SEQUENCE_IX_TAG_LE`].
+pub fn split_event_ix_data_with(
+    data: &[u8],
+    order: EventIxTagOrder,
+) -> Option<(Option<u64>, &[u8])> {
+    let data = match data.strip_prefix(&EVENT_AUTHORITY_BUMP_IX_TAG_LE) {
+        Some(data) => data.get(1..)?,
+        None => data,
+    };
+    let tags: &[[u8; 8]] = match order {
+        EventIxTagOrder::LittleEndian => &[EVENT_IX_TAG_LE],
+        EventIxTagOrder::BigEndian => &[EVENT_IX_TAG_BE],
+        EventIxTagOrder::Either => &[EVENT_IX_TAG_LE, EVENT_IX_TAG_BE],
+    };
+    if let Some(event) = tags.iter().find_map(|tag| data.strip_prefix(tag)) {
+        return Some((None, event));
+    }
+
+    let data = data.strip_prefix(&EVENT_SEQUENCE_IX_TAG_LE)?;
+    let sequence = u64::from_le_bytes(data.get(..8)?.try_into().ok()?);
+    Some((Some(sequence), &data[8..]))
+}
+
+/// Serializes `event` prefixed with its discriminator, i.e. the same as [`Event::data`].
+///
+/// This gives generic code a single entry point to encode events of any type, e.g. libraries
+/// that build raw instruction data without knowing the events of the program:
+///
+/// ```ignore
+/// fn event_ix_data<E: Event>(event: &E) -> Vec<u8> {
+///     [&EVENT_IX_TAG_LE[..], &encode_event(event)].concat()
+/// }
+/// ```
+///
+/// The discriminator alone is available as [`Discriminator::DISCRIMINATOR`], or as
+/// [`Event::EVENT_DISCRIMINATOR`] for events with a longer discriminator.
+///
+/// [`Discriminator::DISCRIMINATOR`]: crate::Discriminator::DISCRIMINATOR
+pub fn encode_event<E: Event>(event: &E) -> Vec<u8> {
+    event.data()
+}
+
+/// Prefix of the logs of events emitted with [`emit!`](crate::prelude::emit).
+pub const PROGRAM_DATA_LOG_PREFIX: &str = "Program data: ";
+
+/// Logs `data` with the `sol_log_data` syscall.
+///
+/// The syscall is compiled out if the `no-log-data` feature of `anchor-lang` is enabled, e.g.
+/// for targets without the syscall.
+pub fn log_data(data: &[&[u8]]) {
+    #[cfg(not(feature = "no-log-data"))]
+    crate::solana_program::log::sol_log_data(data);
+    #[cfg(feature = "no-log-data")]
+    let _ = data;
+}
+
+/// Returns the base64 decoded segments of a `Program data:` log line, i.e. one segment for
+/// events logged with [`emit!`](crate::prelude::emit), or one segment per event for
+/// [`emit_many!`](crate::prelude::emit_many). Returns no segments for other lines, and skips
+/// segments that are not valid base64.
+pub fn decode_program_data(line: &str) -> Vec<Vec<u8>> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    match line.strip_prefix(PROGRAM_DATA_LOG_PREFIX) {
+        Some(data) => data
+            .split_whitespace()
+            .filter_map(|segment| STANDARD.decode(segment).ok())
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// Reusable buffer for decoding the events of `Program data:` log lines.
+///
+/// [`decode_program_data`] allocates a new buffer for every line and every event of a line.
+/// Indexers that decode many events can instead decode them into the buffer of a scratch that
+/// they keep around, e.g. with the `dispatch_with` function of enums generated with
+/// [`generate_event_enum!`](crate::prelude::generate_event_enum), so that decoding allocates
+/// only once the buffer has to grow:
+///
+/// ```ignore
+/// let mut scratch = EventScratch::with_capacity(1024);
+/// for logs in transactions {
+///     ProgramEvent::dispatch_with(&ctx, &logs, &mut indexer, &mut scratch);
+/// }
+/// ```
+///
+/// Fields that the decoded events own, e.g. vectors and strings, are still allocated.
+#[derive(Clone, Debug, Default)]
+pub struct EventScratch {
+    buf: Vec<u8>,
+}
+
+impl EventScratch {
+    /// Creates a scratch with an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a scratch whose buffer holds events of up to `capacity` bytes without
+    /// reallocating.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Reserves capacity for events of at least `additional` more bytes than the buffer
+    /// currently holds, see [`Vec::reserve`].
+    pub fn reserve(&mut self, additional: usize) {
+        self.buf.reserve(additional);
+    }
+
+    /// Returns the number of bytes the buffer holds without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Calls `f` with the base64 decoded segments of a `Program data:` log line, like
+    /// [`decode_program_data`] returns them, decoding each segment into the buffer.
+    pub fn for_each_program_data(&mut self, line: &str, mut f: impl FnMut(&[u8])) {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        let data = match line.strip_prefix(PROGRAM_DATA_LOG_PREFIX) {
+            Some(data) => data,
+            None => return,
+        };
+        for segment in data.split_whitespace() {
+            self.buf.clear();
+            if STANDARD.decode_vec(segment, &mut self.buf).is_ok() {
+                f(&self.buf);
+            }
+        }
+    }
+}
+
+/// Returns the events of type `E` logged with [`emit!`](crate::prelude::emit) in `logs`, e.g.
+/// the log messages of a transaction, in the order they were logged. Events of other types and
+/// other log lines are skipped.
+///
+/// Events are decoded from the logs of every program, which includes the events of CPIs.
+pub fn decode_logged_events<E: Event>(logs: impl IntoIterator<Item = impl AsRef<str>>) -> Vec<E> {
+    logs.into_iter()
+        .flat_map(|line| decode_program_data(line.as_ref()))
+        .filter_map(|data| E::try_from_event_data(&data).ok())
+        .collect()
+}
+
+/// Returns the `Program data:` log line of `event`, exactly as the runtime logs it when the event
+/// is emitted with [`emit!`](crate::prelude::emit).
+///
+/// The runtime encodes the data of `sol_log_data` with the standard base64 alphabet, i.e. with
+/// `+` and `/`, and with `=` padding. This allows golden tests of the wire format of events
+/// without a validator:
+///
+/// ```ignore
+/// let line = encode_event_log(&MyEvent { data: 5 });
+/// assert_eq!(line, include_str!("golden/my_event.log").trim_end());
+/// assert_eq!(decode_event_log::<MyEvent>(&line)?, MyEvent { data: 5 });
+/// ```
+pub fn encode_event_log<E: Event>(event: &E) -> String {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    format!("{PROGRAM_DATA_LOG_PREFIX}{}", STANDARD.encode(event.data()))
+}
+
+/// Deserializes the event of a `Program data:` log line, the inverse of [`encode_event_log`].
+///
+/// Unlike [`decode_program_data`], which skips what it can't decode, the line must be exactly
+/// what the runtime logs for a single event: fails with
+/// [`InvalidEventLog`](crate::error::ErrorCode::InvalidEventLog) if the line doesn't start with
+/// [`PROGRAM_DATA_LOG_PREFIX`], holds no or several segments, or whitespace, or is not canonical
+/// padded base64 of the standard alphabet, e.g. URL-safe base64. The decoded data must then be
+/// an event of type `E`, see [`Event::try_from_event_data`].
+pub fn decode_event_log<E: Event>(line: &str) -> crate::Result<E> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let data = line
+        .strip_prefix(PROGRAM_DATA_LOG_PREFIX)
+        .filter(|segment| !segment.is_empty() && !segment.contains(char::is_whitespace))
+        .and_then(|segment| STANDARD.decode(segment).ok())
+        .ok_or(crate::error::ErrorCode::InvalidEventLog)?;
+    E::try_from_event_data(&data)
+}
+
+/// Id shared by the events of a multi-program flow, e.g. a UUID.
+///
+/// [`#[event(correlated)]`](crate::event) events always have an `Option<[u8; 16]>` slot right
+/// after their discriminator, which holds the id they're emitted with, so that indexers can join
+/// the events of the programs involved in a flow. The emit macros take the id from the
+/// [`Context`](crate::context::Context) of the handler, which holds the id the instruction was
+/// invoked with, see
+/// [`CpiContext::with_correlation_id`](crate::context::CpiContext::with_correlation_id). Events
+/// of other types are not affected.
+///
+/// ```ignore
+/// #[event(correlated)]
+/// pub struct SwapStarted {
+///     pub amount: u64,
+/// }
+///
+/// pub fn swap(ctx: Context<Swap>, correlation_id: [u8; 16], amount: u64) -> Result<()> {
+///     let ctx = ctx.with_correlation_id(correlation_id);
+///     emit!(ctx, SwapStarted { amount });
+///     Ok(())
+/// }
+/// ```
+pub type CorrelationId = [u8; 16];
+
+/// Tag of the instructions invoked with a correlation id, see
+/// [`CpiContext::with_correlation_id`](crate::context::CpiContext::with_correlation_id), i.e. the
+/// first 8 bytes of `Sha256("anchor:correlation_id")` read as a big-endian integer.
+pub const CORRELATION_ID_IX_TAG: u64 = 0x9504f80fe4a20224;
+
+/// Little-endian bytes of [`CORRELATION_ID_IX_TAG`] that prefix the data of instructions invoked
+/// with a correlation id. The tag is followed by the correlation id, and then by the data of the
+/// instruction, i.e. its discriminator and arguments, see [`split_correlation_id_ix_data`].
+pub const CORRELATION_ID_IX_TAG_LE: [u8; 8] = CORRELATION_ID_IX_TAG.to_le_bytes();
+
+/// Splits the data of an instruction invoked with a correlation id into the correlation id and
+/// the data of the instruction. Returns `None` if `data` doesn't start with
+/// [`CORRELATION_ID_IX_TAG_LE`] followed by a correlation id.
+///
+/// The dispatcher of Anchor programs passes the id to the [`Context`](crate::context::Context) of
+/// the handler, and off-chain parsers can use this function to join the instructions of a flow.
+pub fn split_correlation_id_ix_data(data: &[u8]) -> Option<(CorrelationId, &[u8])> {
+    let data = data.strip_prefix(&CORRELATION_ID_IX_TAG_LE)?;
+    if data.len() < 16 {
+        return None;
+    }
+    let (id, data) = data.split_at(16);
+    Some((id.try_into().unwrap(), data))
+}
+
+/// Appends the header of the serialized `E` to `out`, i.e. its discriminator, the correlation
+/// id slot of [`#[event(correlated)]`](crate::event) events holding `correlation_id` and its
+/// version, which is what [`Event::correlated_data_into`] writes before the payload.
+pub fn write_event_header<E: Event>(out: &mut Vec<u8>, correlation_id: Option<CorrelationId>) {
+    out.extend_from_slice(E::EVENT_DISCRIMINATOR);
+    if E::EVENT_CORRELATED {
+        match correlation_id {
+            Some(id) => {
+                out.push(1);
+                out.extend_from_slice(&id);
+            }
+            None => out.push(0),
+        }
+    }
+    if let Some(version) = E::EVENT_VERSION {
+        out.push(version);
+    }
+}
+
+/// Returns the length of the header that [`write_event_header`] appends for `E` and
+/// `correlation_id`.
+pub fn event_header_len<E: Event>(correlation_id: Option<CorrelationId>) -> usize {
+    let correlation_id_len = match (E::EVENT_CORRELATED, correlation_id) {
+        (false, _) => 0,
+        (true, None) => 1,
+        (true, Some(id)) => 1 + id.len(),
+    };
+    E::EVENT_DISCRIMINATOR.len() + correlation_id_len + usize::from(E::EVENT_VERSION.is_some())
+}
+
+/// Splits the correlation id slot of a correlated event from the data that follows its
+/// discriminator, or fails with
+/// [`InvalidEventCorrelationId`](crate::error::ErrorCode::InvalidEventCorrelationId).
+pub(crate) fn split_correlation_id(data: &[u8]) -> crate::Result<(Option<CorrelationId>, &[u8])> {
+    match data.split_first() {
+        Some((0, rest)) => Ok((None, rest)),
+        Some((1, rest)) if rest.len() >= 16 => {
+            let (id, rest) = rest.split_at(16);
+            Ok((Some(id.try_into().unwrap()), rest))
+        }
+        _ => Err(crate::error::ErrorCode::InvalidEventCorrelationId.into()),
+    }
+}
+
+/// 8-byte discriminator of an event, i.e. [`Discriminator::DISCRIMINATOR`] of an
+/// [`Event`], returned by [`Event::typed_discriminator`].
+///
+/// Comparing discriminators of this type instead of raw byte arrays lets indexers keep
+/// discriminators apart from other data, and print or parse them as hex:
+///
+/// ```ignore
+/// let discriminator: EventDiscriminator = "f4ab5a2e5b54bdfa".parse()?;
+/// if MyEvent::typed_discriminator().matches(&data) {
+///     let event = MyEvent::try_from_event_data(&data)?;
+/// }
+/// ```
+///
+/// Events declared with a longer `discriminator_len` are prefixed with
+/// [`Event::EVENT_DISCRIMINATOR`], which starts with this discriminator.
+///
+/// [`Discriminator::DISCRIMINATOR`]: crate::Discriminator::DISCRIMINATOR
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EventDiscriminator(pub [u8; 8]);
+
+impl EventDiscriminator {
+    /// Returns the bytes of the discriminator.
+    pub const fn to_bytes(self) -> [u8; 8] {
+        self.0
+    }
+
+    /// Returns whether `data` starts with the discriminator.
+    pub fn matches(&self, data: &[u8]) -> bool {
+        data.starts_with(&self.0)
+    }
+}
+
+impl From<[u8; 8]> for EventDiscriminator {
+    fn from(bytes: [u8; 8]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<EventDiscriminator> for [u8; 8] {
+    fn from(discriminator: EventDiscriminator) -> Self {
+        discriminator.0
+    }
+}
+
+impl AsRef<[u8]> for EventDiscriminator {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for EventDiscriminator {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for EventDiscriminator {
+    type Err = ParseEventDiscriminatorError;
+
+    /// Parses 16 hex digits, optionally prefixed with `0x`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let hex = s.strip_prefix("0x").unwrap_or(s);
+        if hex.len() != 16 || !hex.is_ascii() {
+            return Err(ParseEventDiscriminatorError);
+        }
+
+        let mut bytes = [0; 8];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16)
+                .map_err(|_| ParseEventDiscriminatorError)?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+/// Error of parsing an [`EventDiscriminator`] that isn't 16 hex digits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseEventDiscriminatorError;
+
+impl std::fmt::Display for ParseEventDiscriminatorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "event discriminator must be 16 hex digits")
+    }
+}
+
+impl std::error::Error for ParseEventDiscriminatorError {}
+
+/// Lookup table from event discriminators to tags, e.g. the event types of an indexer, which
+/// matches serialized events with a single hash lookup instead of comparing them with each
+/// discriminator.
+///
+/// Events are matched by their first 8 bytes, so events declared with a longer
+/// `discriminator_len` are matched by [`Discriminator::DISCRIMINATOR`].
+///
+/// ```ignore
+/// let mut matcher = DiscriminatorMatcher::new();
+/// matcher.insert_event::<Deposited>(EventKind::Deposited);
+/// matcher.insert_event::<Withdrawn>(EventKind::Withdrawn);
+///
+/// for data in decode_program_data(line) {
+///     match matcher.match_data(&data) {
+///         Some(EventKind::Deposited) => handle_deposit(Deposited::try_from_event_data(&data)?),
+///         Some(EventKind::Withdrawn) => handle_withdraw(Withdrawn::try_from_event_data(&data)?),
+///         None => {}
+///     }
+/// }
+/// ```
+///
+/// [`Discriminator::DISCRIMINATOR`]: crate::Discriminator::DISCRIMINATOR
+#[derive(Clone, Debug)]
+pub struct DiscriminatorMatcher<T> {
+    tags: std::collections::HashMap<u64, T, std::hash::BuildHasherDefault<DiscriminatorHasher>>,
+}
+
+impl<T> DiscriminatorMatcher<T> {
+    /// Creates an empty matcher.
+    pub fn new() -> Self {
+        Self {
+            tags: Default::default(),
+        }
+    }
+
+    /// Adds `discriminator` with the given tag, returns the previous tag of the discriminator.
+    pub fn insert(&mut self, discriminator: impl Into<EventDiscriminator>, tag: T) -> Option<T> {
+        self.tags
+            .insert(u64::from_le_bytes(discriminator.into().0), tag)
+    }
+
+    /// Adds the discriminator of the event `E` with the given tag, returns the previous tag of
+    /// the discriminator.
+    pub fn insert_event<E: Event>(&mut self, tag: T) -> Option<T> {
+        self.insert(E::DISCRIMINATOR, tag)
+    }
+
+    /// Returns the tag of `discriminator`.
+    pub fn get(&self, discriminator: impl Into<EventDiscriminator>) -> Option<&T> {
+        self.tags.get(&u64::from_le_bytes(discriminator.into().0))
+    }
+
+    /// Returns the tag of the discriminator that `data` starts with, e.g. a serialized event.
+    pub fn match_data(&self, data: &[u8]) -> Option<&T> {
+        let discriminator: [u8; 8] = data.get(..8)?.try_into().unwrap();
+        self.get(discriminator)
+    }
+
+    /// Returns the number of discriminators.
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Returns whether the matcher has no discriminators.
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
+
+impl<T> Default for DiscriminatorMatcher<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D: Into<EventDiscriminator>, T> FromIterator<(D, T)> for DiscriminatorMatcher<T> {
+    fn from_iter<I: IntoIterator<Item = (D, T)>>(iter: I) -> Self {
+        let mut matcher = Self::new();
+        for (discriminator, tag) in iter {
+            matcher.insert(discriminator, tag);
+        }
+        matcher
+    }
+}
+
+/// Hasher of [`DiscriminatorMatcher`], which uses the discriminators as their own hashes since
+/// they're already derived from SHA256.
+#[derive(Clone, Copy, Debug, Default)]
+struct DiscriminatorHasher(u64);
+
+impl std::hash::Hasher for DiscriminatorHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ u64::from(*byte);
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = n;
+    }
+}
+
+/// Name and discriminator of an event, listed in the constant generated by
+/// [`declare_events!(pub const EVENTS = [..])`](crate::declare_events), e.g. for tooling that
+/// reflects on the events of a program:
+///
+/// ```ignore
+/// declare_events!(pub const EVENTS = [Deposited, Withdrawn]);
+///
+/// for event in EVENTS {
+///     println!("{}: {:?}", event.name, event.discriminator);
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EventMeta {
+    /// Name of the event type in Rust.
+    pub name: &'static str,
+    /// [`Event::EVENT_DISCRIMINATOR`] of the event.
+    pub discriminator: &'static [u8],
+}
+
+impl EventMeta {
+    /// Returns whether `data` starts with the discriminator of the event.
+    pub fn matches(&self, data: &[u8]) -> bool {
+        data.starts_with(self.discriminator)
+    }
+}
+
+/// Transaction that emitted the events passed to the handlers generated by
+/// [`generate_event_enum!`](crate::generate_event_enum).
+///
+/// For an enum `ProgramEvent`, the macro generates a `ProgramEventHandler` trait with an
+/// `on_<variant>` method for each variant, in snake case. The trait has no default methods, so
+/// adding an event to the enum fails to compile until every handler handles it:
+///
+/// ```compile_fail
+/// use anchor_lang::event::EventContext;
+/// use anchor_lang::prelude::*;
+///
+/// #[event]
+/// pub struct Deposit {
+///     pub amount: u64,
+/// }
+///
+/// #[event]
+/// pub struct Withdraw {
+///     pub amount: u64,
+/// }
+///
+/// generate_event_enum! {
+///     pub enum ProgramEvent {
+///         Deposit,
+///         Withdraw,
+///     }
+/// }
+///
+/// struct Indexer;
+///
+/// // Error: not all trait items implemented, missing: `on_withdraw`
+/// impl ProgramEventHandler for Indexer {
+///     fn on_deposit(&mut self, event: Deposit, ctx: &EventContext) {}
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventContext<'a> {
+    /// Slot of the transaction.
+    pub slot: u64,
+    /// Base58 encoded signature of the transaction.
+    pub signature: &'a str,
+}
+
+/// Object safe sink of serialized events, which lets code outside of
+/// [`#[program]`](crate::program), e.g. library crates shared across programs, emit events
+/// without hardwiring the `sol_log_data` syscall.
+///
+/// Shared code takes a `&dyn EventSink` provided by the program, and emits events with
+/// [`EventEmitter::emit`] or [`emit!(sink, event)`](crate::prelude::emit). This makes it unit
+/// testable off-chain with an [`EventRecorder`]:
+///
+/// ```ignore
+/// use anchor_lang::event::{EventEmitter, EventSink};
+///
+/// pub fn transfer(events: &dyn EventSink, amount: u64) -> Result<()> {
+///     // ...
+///     events.emit(&Transferred { amount });
+///     Ok(())
+/// }
+///
+/// // In the program
+/// transfer(&LogEmitter, amount)?;
+///
+/// // In tests
+/// let recorder = EventRecorder::default();
+/// transfer(&recorder, 5)?;
+/// assert_eq!(recorder.decode::<Transferred>()[0].amount, 5);
+/// ```
+pub trait EventSink {
+    /// Emits a serialized event, given as the slices that make up the event data, see
+    /// [`Event::emit_with`].
+    fn emit_data(&self, data: &[&[u8]]);
+
+    /// Returns the correlation id that [`#[event(correlated)]`](crate::event) events emitted to
+    /// the sink are serialized with, see [`CorrelationId`].
+    fn correlation_id(&self) -> Option<CorrelationId> {
+        None
+    }
+}
+
+/// Emits typed events to an [`EventSink`]. Implemented for all sinks, including
+/// `dyn EventSink`.
+pub trait EventEmitter: EventSink {
+    /// Serializes and emits `event`.
+    fn emit<E: Event>(&self, event: &E) {
+        event.emit_with_correlation_id(self.correlation_id(), |data| self.emit_data(data));
+    }
+}
+
+impl<T: EventSink + ?Sized> EventEmitter for T {}
+
+/// On-chain [`EventSink`] that logs events with [`log_data`], the same as
+/// [`emit!`](crate::prelude::emit) without a sink.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LogEmitter;
+
+impl EventSink for LogEmitter {
+    fn emit_data(&self, data: &[&[u8]]) {
+        log_data(data);
+    }
+}
+
+/// [`EventSink`] that records the emitted events in memory, e.g. to check the events of
+/// shared code in unit tests.
+#[derive(Debug, Default)]
+pub struct EventRecorder {
+    events: std::cell::RefCell<Vec<Vec<u8>>>,
+}
+
+impl EventRecorder {
+    /// Returns the recorded events in the order they were emitted, each serialized with its
+    /// discriminator, i.e. the same as [`Event::data`].
+    pub fn events(&self) -> Vec<Vec<u8>> {
+        self.events.borrow().clone()
+    }
+
+    /// Returns the recorded events of type `E` in the order they were emitted. Events of other
+    /// types are skipped.
+    pub fn decode<E: Event>(&self) -> Vec<E> {
+        self.events
+            .borrow()
+            .iter()
+            .filter_map(|data| E::try_from_event_data(data).ok())
+            .collect()
+    }
+}
+
+impl EventSink for EventRecorder {
+    fn emit_data(&self, data: &[&[u8]]) {
+        self.events.borrow_mut().push(data.concat());
+    }
+}
+
+/// Events of an instruction, which the instruction dispatch generated by
+/// [`#[program]`](crate::program) attaches to the [`Context`](crate::context::Context) of the
+/// handler.
+///
+/// It holds the events of [`emit_on_success!`](crate::prelude::emit_on_success) until the
+/// instruction succeeds, and counts the events emitted through the context against the
+/// `max_events` of [`#[program(max_events = <N>)]`](crate::program). The dispatch calls
+/// [`finish`](InstructionEvents::finish) after the handler returned `Ok` and the accounts were
+/// exited. If the handler returns an error, the deferred events are dropped, so they never show
+/// up in the logs of a failed instruction.
+///
+/// Tests that call a handler directly can attach one with
+/// [`Context::with_events`](crate::context::Context::with_events) to check the deferred events:
+///
+/// ```ignore
+/// let events = InstructionEvents::new(None);
+/// let ctx = Context::new(&program_id, &mut accounts, &[], bumps).with_events(&events);
+/// my_program::transfer(ctx, 5)?;
+/// assert_eq!(events.deferred(), vec![Transferred { amount: 5 }.data()]);
+/// ```
+#[derive(Debug, Default)]
+pub struct InstructionEvents {
+    max_events: Option<u32>,
+    count: std::cell::Cell<u32>,
+    too_many: std::cell::Cell<bool>,
+    deferred: std::cell::RefCell<Vec<Vec<u8>>>,
+}
+
+impl InstructionEvents {
+    /// Creates the events of an instruction that may emit at most `max_events` events.
+    pub fn new(max_events: Option<u32>) -> Self {
+        Self {
+            max_events,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the number of events counted so far.
+    pub fn count(&self) -> u32 {
+        self.count.get()
+    }
+
+    /// Counts `count` events and returns whether they're within `max_events`. Otherwise, the
+    /// instruction fails with [`TooManyEvents`](crate::error::ErrorCode::TooManyEvents) once
+    /// the handler returns.
+    pub fn count_events(&self, count: usize) -> bool {
+        let count = self
+            .count
+            .get()
+            .saturating_add(u32::try_from(count).unwrap_or(u32::MAX));
+        self.count.set(count);
+        let within = self.max_events.map_or(true, |max| count <= max);
+        if !within {
+            self.too_many.set(true);
+        }
+        within
+    }
+
+    /// Logs a serialized event, given as the slices that make up the event data, with
+    /// [`log_data`] if it's within `max_events`.
+    pub fn log(&self, data: &[&[u8]]) {
+        if self.count_events(1) {
+            log_data(data);
+        }
+    }
+
+    /// Stores a serialized event, given as the slices that make up the event data, until
+    /// [`finish`](InstructionEvents::finish) is called.
+    pub fn defer(&self, data: &[&[u8]]) {
+        if self.count_events(1) {
+            self.deferred.borrow_mut().push(data.concat());
+        }
+    }
+
+    /// Returns the deferred events in the order they were emitted, each serialized like
+    /// [`Event::data`].
+    pub fn deferred(&self) -> Vec<Vec<u8>> {
+        self.deferred.borrow().clone()
+    }
+
+    /// Fails with [`TooManyEvents`](crate::error::ErrorCode::TooManyEvents) if more than
+    /// `max_events` events were emitted, or logs the deferred events with [`log_data`] in the
+    /// order they were emitted.
+    pub fn finish(self) -> crate::Result<()> {
+        if self.too_many.get() {
+            return Err(crate::error::ErrorCode::TooManyEvents.into());
+        }
+        for data in self.deferred.into_inner() {
+            log_data(&[&data]);
+        }
+        Ok(())
+    }
+}
+
+/// Writer that only counts the bytes written to it, used by
+/// [`Event::encoded_len`](crate::Event::encoded_len) to get the serialized size of an event
+/// without allocating.
+#[derive(Debug, Default)]
+pub(crate) struct LenWriter {
+    pub len: usize,
+}
+
+impl std::io::Write for LenWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.len += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Codec that compresses the serialized events logged with
+/// [`emit_compressed!`](crate::prelude::emit_compressed), e.g. to fit more high-volume events
+/// with repetitive data in the log limit of a transaction.
+///
+/// The id of the codec is logged right after the discriminator, and the rest of the event,
+/// i.e. the version of versioned events and the event itself, is compressed with
+/// [`encode`](EventCodec::encode):
+///
+/// ```ignore
+/// Program data: <Base64(Discriminator, CodecId, Compressed(Version, Event))>
+/// ```
+///
+/// Off-chain parsers decompress the event with [`decompress_event_data`] for the codecs of
+/// Anchor, or [`decompress_event_data_with`] for custom codecs. Ids below 128 are reserved for
+/// the codecs of Anchor, custom codecs should use the ids from 128 on.
+///
+/// Codecs run on-chain, where every byte costs compute units, so they should be simple enough
+/// to save more compute units on the `sol_log_data` syscall than they spend compressing.
+pub trait EventCodec {
+    /// Id of the codec, logged after the discriminator of compressed events.
+    const ID: u8;
+
+    /// Appends the compressed `data` to `out`.
+    fn encode(&self, data: &[u8], out: &mut Vec<u8>);
+
+    /// Appends the decompressed `data` to `out`. Fails with
+    /// [`InvalidCompressedEvent`](crate::error::ErrorCode::InvalidCompressedEvent) if `data`
+    /// wasn't compressed with [`encode`](EventCodec::encode).
+    fn decode(&self, data: &[u8], out: &mut Vec<u8>) -> crate::Result<()>;
+}
+
+/// [`EventCodec`] that doesn't compress events, e.g. to log the events of a program in the same
+/// format whether they're compressed or not.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IdentityCodec;
+
+impl EventCodec for IdentityCodec {
+    const ID: u8 = 0;
+
+    fn encode(&self, data: &[u8], out: &mut Vec<u8>) {
+        out.extend_from_slice(data);
+    }
+
+    fn decode(&self, data: &[u8], out: &mut Vec<u8>) -> crate::Result<()> {
+        out.extend_from_slice(data);
+        Ok(())
+    }
+}
+
+/// [`EventCodec`] that replaces every run of up to 255 zero bytes with a zero byte followed by
+/// the length of the run, which is what [`emit_compressed!`](crate::prelude::emit_compressed)
+/// uses by default.
+///
+/// Borsh serializes small integers, `None` options, empty vectors and zeroed arrays as runs of
+/// zero bytes, e.g. a `u64` amount below 256 ends with 7 zero bytes, which this codec stores
+/// in 2 bytes. Data without zero runs grows by one byte per isolated zero byte.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ZeroRunCodec;
+
+impl EventCodec for ZeroRunCodec {
+    const ID: u8 = 1;
+
+    fn encode(&self, data: &[u8], out: &mut Vec<u8>) {
+        let mut i = 0;
+        while i < data.len() {
+            if data[i] == 0 {
+                let run = data[i..]
+                    .iter()
+                    .take(u8::MAX.into())
+                    .take_while(|byte| **byte == 0)
+                    .count();
+                out.extend_from_slice(&[0, run as u8]);
+                i += run;
+            } else {
+                out.push(data[i]);
+                i += 1;
+            }
+        }
+    }
+
+    fn decode(&self, data: &[u8], out: &mut Vec<u8>) -> crate::Result<()> {
+        let mut bytes = data.iter();
+        while let Some(byte) = bytes.next() {
+            match byte {
+                0 => match bytes.next() {
+                    Some(run) if *run > 0 => out.resize(out.len() + usize::from(*run), 0),
+                    _ => return Err(crate::error::ErrorCode::InvalidCompressedEvent.into()),
+                },
+                byte => out.push(*byte),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serializes `event` like [`Event::data`], but with the id of `codec` after the discriminator
+/// and the rest of the event compressed with `codec`, i.e. the data that
+/// [`emit_compressed!`](crate::prelude::emit_compressed) logs.
+///
+/// This also allows comparing the size of compressed events with [`Event::encoded_len`] to
+/// pick a codec for an event.
+pub fn compress_event_data<E: Event, C: EventCodec>(event: &E, codec: &C) -> Vec<u8> {
+    let mut serialized = Vec::with_capacity(256);
+    event.data_into(&mut serialized);
+    compress_serialized_event::<E, C>(&serialized, codec)
+}
+
+/// Compresses `serialized`, the data of an event of type `E`, like [`compress_event_data`].
+pub(crate) fn compress_serialized_event<E: Event, C: EventCodec>(
+    serialized: &[u8],
+    codec: &C,
+) -> Vec<u8> {
+    let (discriminator, rest) = serialized.split_at(E::EVENT_DISCRIMINATOR.len());
+
+    let mut data = Vec::with_capacity(serialized.len() + 1);
+    data.extend_from_slice(discriminator);
+    data.push(C::ID);
+    codec.encode(rest, &mut data);
+    data
+}
+
+/// Decompresses the data of an event of type `E` logged with
+/// [`emit_compressed!`](crate::prelude::emit_compressed) and one of the codecs of Anchor, i.e.
+/// [`IdentityCodec`] or [`ZeroRunCodec`], and returns the data [`Event::data`] would have
+/// returned, which can be deserialized with
+/// [`Event::try_from_event_data`](crate::Event::try_from_event_data).
+///
+/// Fails with [`InvalidEventCodec`](crate::error::ErrorCode::InvalidEventCodec) if the event
+/// was compressed with another codec, which can be decompressed with
+/// [`decompress_event_data_with`].
+///
+/// ```ignore
+/// let event = MyEvent::try_from_event_data(&decompress_event_data::<MyEvent>(&data)?)?;
+/// ```
+pub fn decompress_event_data<E: Event>(data: &[u8]) -> crate::Result<Vec<u8>> {
+    let id = data
+        .strip_prefix(E::EVENT_DISCRIMINATOR)
+        .ok_or(crate::error::ErrorCode::InvalidEventDiscriminator)?
+        .first()
+        .copied();
+    if id == Some(IdentityCodec::ID) {
+        decompress_event_data_with::<E, _>(data, &IdentityCodec)
+    } else if id == Some(ZeroRunCodec::ID) {
+        decompress_event_data_with::<E, _>(data, &ZeroRunCodec)
+    } else {
+        Err(crate::error::ErrorCode::InvalidEventCodec.into())
+    }
+}
+
+/// Same as [`decompress_event_data`], but for events compressed with the given `codec`, e.g. a
+/// custom codec.
+pub fn decompress_event_data_with<E: Event, C: EventCodec>(
+    data: &[u8],
+    codec: &C,
+) -> crate::Result<Vec<u8>> {
+    let data = data
+        .strip_prefix(E::EVENT_DISCRIMINATOR)
+        .ok_or(crate::error::ErrorCode::InvalidEventDiscriminator)?;
+    let compressed = match data.split_first() {
+        Some((id, compressed)) if *id == C::ID => compressed,
+        _ => return Err(crate::error::ErrorCode::InvalidEventCodec.into()),
+    };
+
+    let mut decompressed = Vec::with_capacity(E::EVENT_DISCRIMINATOR.len() + 2 * data.len());
+    decompressed.extend_from_slice(E::EVENT_DISCRIMINATOR);
+    codec.decode(compressed, &mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// Maximum size in bytes of the events that [`emit_auto!`](crate::emit_auto) logs with
+/// [`emit!`](crate::prelude::emit), larger events are emitted with a self-CPI.
+///
+/// The base64 encoding of a 1024 byte event takes about 1.4 KB of the 10 KB log limit of a
+/// transaction, which leaves room for the other logs and events of the transaction.
+#[cfg(feature = "event-cpi")]
+pub const EMIT_AUTO_THRESHOLD: usize = 1024;
+
+/// Event CPI accounts of an accounts struct annotated with [`#[event_cpi]`](crate::event_cpi),
+/// which implements this trait.
+///
+/// [`emit_cpi!`](crate::emit_cpi) reads the accounts of `ctx` through this trait, so that events
+/// can also be emitted by handlers that are generic over their accounts, e.g. the default
+/// methods of a trait that is implemented for the instructions of a program:
+///
+/// ```ignore
+/// pub trait Notify {
+///     fn notify<'info, T: EventCpiAccountInfos<'info>>(
+///         ctx: &Context<'_, '_, '_, '_, T>,
+///         data: u64,
+///     ) -> Result<()> {
+///         emit_cpi!(MyEvent { data });
+///         Ok(())
+///     }
+/// }
+/// ```
+#[cfg(feature = "event-cpi")]
+pub trait EventCpiAccountInfos<'info>: crate::__private::EventCpiAccounts {
+    /// Returns the event authority account.
+    fn event_authority_info(&self) -> &AccountInfo<'info>;
+    /// Returns the program account that the event authority invokes.
+    fn event_program_info(&self) -> &AccountInfo<'info>;
+}
+
+/// Self-CPI event instruction built by [`build_cpi_event_ix!`](crate::build_cpi_event_ix) that
+/// can be invoked later.
+#[cfg(feature = "event-cpi")]
+pub struct EventCpiInstruction<'info> {
+    /// Self-CPI instruction whose data is the event tag followed by the event data.
+    pub instruction: Instruction,
+    /// Accounts required to invoke the instruction, i.e. the event authority and the program.
+    pub account_infos: [AccountInfo<'info>; 2],
+    /// Seeds of the event authority PDA, excluding the bump.
+    pub authority_seeds: &'static [&'static [u8]],
+    /// Bump of the event authority PDA.
+    pub authority_bump: u8,
+    /// Whether the event authority counts the events, see
+    /// [`#[event_cpi(sequence)]`](crate::event_cpi). The sequence number is assigned when the
+    /// instruction is invoked, so that events are numbered in the order they're emitted.
+    pub sequenced: bool,
+}
+
+#[cfg(feature = "event-cpi")]
+impl<'info> EventCpiInstruction<'info> {
+    /// Invokes the instruction, signing with the event authority PDA.
+    ///
+    /// If the instruction is [`sequenced`](Self::sequenced), the sequence number of the event
+    /// authority is advanced first, see [`EventSequence::advance`].
+    ///
+    /// Failures are returned as [`ErrorCode::EventCpiFailed`], with the underlying program
+    /// error appended to the error message.
+    ///
+    /// Within [`record_cpi_events`], which isn't available on-chain, the instruction is recorded
+    /// instead. Nothing is invoked if the instruction [`is_empty`](Self::is_empty).
+    pub fn invoke(&self) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let bump = [self.authority_bump];
+        let mut seeds: Vec<&[u8]> = self.authority_seeds.to_vec();
+        seeds.push(&bump);
+
+        let instruction = self.sequenced_instruction()?;
+        #[cfg(not(target_os = "solana"))]
+        {
+            let recorded = RECORDED_CPI_EVENTS.with(|events| match &mut *events.borrow_mut() {
+                Some(events) => {
+                    events.push(instruction.clone().into_owned());
+                    true
+                }
+                None => false,
+            });
+            if recorded {
+                return Ok(());
+            }
+        }
+        invoke_signed(&instruction, &self.account_infos, &[&seeds]).map_err(|err| {
+            let error_code = ErrorCode::EventCpiFailed;
+            AnchorError {
+                error_name: error_code.name(),
+                error_code_number: error_code.into(),
+                error_msg: format!("{error_code}: {err}"),
+                error_origin: None,
+                compared_values: None,
+            }
+            .into()
+        })
+    }
+
+    /// Returns `true` if the instruction carries no event data, i.e. the event is compiled out
+    /// because the `feature` of its [`#[event]`](crate::event) attribute is disabled.
+    pub fn is_empty(&self) -> bool {
+        let sequence_len = if self.sequenced { 8 } else { 0 };
+        self.instruction.data.len() <= self.bump_len() + EVENT_IX_TAG_LE.len() + sequence_len
+    }
+
+    /// Length of the bump prefixed to the event tag, see
+    /// [`EVENT_AUTHORITY_BUMP_IX_TAG_LE`].
+    fn bump_len(&self) -> usize {
+        if self
+            .instruction
+            .data
+            .starts_with(&EVENT_AUTHORITY_BUMP_IX_TAG_LE)
+        {
+            EVENT_AUTHORITY_BUMP_IX_TAG_LE.len() + 1
+        } else {
+            0
+        }
+    }
+
+    fn sequenced_instruction(&self) -> Result<std::borrow::Cow<'_, Instruction>> {
+        if !self.sequenced {
+            return Ok(std::borrow::Cow::Borrowed(&self.instruction));
+        }
+
+        let sequence = EventSequence::advance(&self.account_infos[0])?;
+        let mut instruction = self.instruction.clone();
+        instruction.data[self.bump_len() + EVENT_SEQUENCE_IX_TAG_LE.len()..][..8]
+            .copy_from_slice(&sequence.to_le_bytes());
+        Ok(std::borrow::Cow::Owned(instruction))
+    }
+}
+
+#[cfg(all(feature = "event-cpi", not(target_os = "solana")))]
+thread_local! {
+    static RECORDED_CPI_EVENTS: std::cell::RefCell<Option<Vec<Instruction>>> = Default::default();
+}
+
+/// Calls `f` with the self-CPIs of [`emit_cpi!`](crate::emit_cpi) and
+/// [`emit_auto!`](crate::emit_auto) recorded instead of invoked, and returns its result along
+/// with the recorded instructions in the order they were emitted.
+///
+/// This allows checking the emitted events in tests that call a handler directly, without a
+/// runtime. Sequenced instructions are numbered like when they're invoked, see
+/// [`#[event_cpi(sequence)]`](crate::event_cpi). Scopes can be nested, in which case the
+/// innermost one records the instructions.
+///
+/// ```ignore
+/// #[test]
+/// fn test_deposit() {
+///     let (result, events) = record_cpi_events(|| deposit(ctx, 5));
+///     result.unwrap();
+///
+///     let (_, event) = split_event_ix_data(&events[0].data).unwrap();
+///     assert_eq!(Deposited::try_from_event_data(event)?.amount, 5);
+/// }
+/// ```
+///
+/// Only available off-chain, so that the self-CPIs of programs are always invoked.
+#[cfg(all(feature = "event-cpi", not(target_os = "solana")))]
+pub fn record_cpi_events<R>(f: impl FnOnce() -> R) -> (R, Vec<Instruction>) {
+    struct Restore(Option<Option<Vec<Instruction>>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            if let Some(outer) = self.0.take() {
+                RECORDED_CPI_EVENTS.with(|events| events.replace(outer));
+            }
+        }
+    }
+
+    let mut restore = Restore(Some(
+        RECORDED_CPI_EVENTS.with(|events| events.replace(Some(vec![]))),
+    ));
+    let result = f();
+    let outer = restore.0.take().unwrap();
+    let recorded = RECORDED_CPI_EVENTS.with(|events| events.replace(outer));
+    (result, recorded.unwrap_or_default())
+}
+
+/// Builds the self-CPI event instruction that [`emit_cpi!`](crate::emit_cpi) invokes for
+/// `event`, e.g. for tests that send the instruction to the program directly to check that
+/// it's rejected without the signature of the event authority.
+///
+/// The instruction isn't numbered, see [`#[event_cpi(sequence)]`](crate::event_cpi), and is
+/// also generated for each event as `MyEvent::cpi_event_instruction`.
+#[cfg(feature = "event-cpi")]
+pub fn cpi_event_instruction<E: Event>(
+    event: &E,
+    program_id: Pubkey,
+    event_authority: Pubkey,
+) -> Instruction {
+    let mut data = Vec::with_capacity(256);
+    data.extend_from_slice(&EVENT_IX_TAG_LE);
+    event.data_into(&mut data);
+    Instruction::new_with_bytes(
+        program_id,
+        &data,
+        vec![crate::solana_program::instruction::AccountMeta::new_readonly(event_authority, true)],
+    )
+}
+
+/// Data of the event authority account of accounts structs annotated with
+/// [`#[event_cpi(sequence)]`](crate::event_cpi), which numbers the events emitted with
+/// [`emit_cpi!`](crate::emit_cpi).
+///
+/// The account is stored at the address of the event authority PDA and is owned by the
+/// program. It must be created with [`EventSequence::init`] before the first event is emitted.
+#[cfg(feature = "event-cpi")]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EventSequence {
+    /// Sequence number of the next event, starting at 0.
+    pub next: u64,
+}
+
+#[cfg(feature = "event-cpi")]
+impl Discriminator for EventSequence {
+    /// `Sha256("account:EventSequence")[..8]`
+    const DISCRIMINATOR: [u8; 8] = [238, 66, 228, 199, 47, 140, 72, 189];
+}
+
+#[cfg(feature = "event-cpi")]
+impl EventSequence {
+    /// Space of the account, i.e. the discriminator followed by the sequence number.
+    pub const SPACE: usize = 8 + 8;
+
+    /// Deserializes the sequence of the given event authority account.
+    ///
+    /// Returns [`ErrorCode::EventSequenceNotInitialized`] if the account wasn't created with
+    /// [`EventSequence::init`].
+    pub fn try_from_account_info(event_authority: &AccountInfo) -> Result<Self> {
+        let data = event_authority.try_borrow_data()?;
+        match data.strip_prefix(&Self::DISCRIMINATOR) {
+            Some(mut data) if data.len() >= 8 => Ok(Self::deserialize(&mut data)?),
+            _ => Err(ErrorCode::EventSequenceNotInitialized.into()),
+        }
+    }
+
+    /// Returns the sequence number of the next event of the given event authority account and
+    /// increments it. The account must be writable.
+    pub fn advance(event_authority: &AccountInfo) -> Result<u64> {
+        let mut sequence = Self::try_from_account_info(event_authority)?;
+        let next = sequence.next;
+        sequence.next += 1;
+
+        let mut data = event_authority.try_borrow_mut_data()?;
+        sequence.serialize(&mut &mut data[Self::DISCRIMINATOR.len()..])?;
+        Ok(next)
+    }
+
+    /// Creates the event authority account of the program with the given authority seeds and
+    /// bump, funded by `payer`.
+    ///
+    /// The account holds [`EventSequence::SPACE`] bytes, so the payer pays the rent-exempt
+    /// minimum of a 16 byte account once, i.e. about 0.001 SOL. Returns
+    /// [`ErrorCode::AccountDiscriminatorAlreadySet`] if the account already exists.
+    pub fn init<'info>(
+        event_authority: &AccountInfo<'info>,
+        payer: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+        program_id: &Pubkey,
+        authority_seeds: &[&[u8]],
+        authority_bump: u8,
+    ) -> Result<()> {
+        if Self::discriminator_matches(&event_authority.try_borrow_data()?) {
+            return Err(ErrorCode::AccountDiscriminatorAlreadySet.into());
+        }
+
+        let bump = [authority_bump];
+        let mut seeds = authority_seeds.to_vec();
+        seeds.push(&bump);
+        let signer_seeds: &[&[&[u8]]] = &[&seeds];
+
+        // Same as the `init` constraint, the account may already have lamports
+        let required_lamports = Rent::get()?.minimum_balance(Self::SPACE);
+        let current_lamports = event_authority.lamports();
+        if current_lamports == 0 {
+            let cpi_accounts = system_program::CreateAccount {
+                from: payer.clone(),
+                to: event_authority.clone(),
+            };
+            let cpi_context = CpiContext::new(system_program.clone(), cpi_accounts);
+            system_program::create_account(
+                cpi_context.with_signer(signer_seeds),
+                required_lamports,
+                Self::SPACE as u64,
+                program_id,
+            )?;
+        } else {
+            let missing_lamports = required_lamports.saturating_sub(current_lamports);
+            if missing_lamports > 0 {
+                let cpi_accounts = system_program::Transfer {
+                    from: payer.clone(),
+                    to: event_authority.clone(),
+                };
+                let cpi_context = CpiContext::new(system_program.clone(), cpi_accounts);
+                system_program::transfer(cpi_context, missing_lamports)?;
+            }
+            let cpi_accounts = system_program::Allocate {
+                account_to_allocate: event_authority.clone(),
+            };
+            let cpi_context = CpiContext::new(system_program.clone(), cpi_accounts);
+            system_program::allocate(cpi_context.with_signer(signer_seeds), Self::SPACE as u64)?;
+            let cpi_accounts = system_program::Assign {
+                account_to_assign: event_authority.clone(),
+            };
+            let cpi_context = CpiContext::new(system_program.clone(), cpi_accounts);
+            system_program::assign(cpi_context.with_signer(signer_seeds), program_id)?;
+        }
+
+        let mut data = event_authority.try_borrow_mut_data()?;
+        data[..8].copy_from_slice(&Self::DISCRIMINATOR);
+        Self::default().serialize(&mut &mut data[8..])?;
+        Ok(())
+    }
+}
@@ -38,7 +38,6 @@ mod bpf_writer;
 mod common;
 pub mod context;
 pub mod error;
-#[doc(hidden)]
 pub mod event;
 #[doc(hidden)]
 pub mod idl;
@@ -50,7 +49,44 @@ pub use anchor_attribute_access_control::access_control;
 pub use anchor_attribute_account::{account, declare_id, zero_copy};
 pub use anchor_attribute_constant::constant;
 pub use anchor_attribute_error::*;
-pub use anchor_attribute_event::{emit, event};
+pub use anchor_attribute_event::{
+    declare_events, emit, emit_compressed, emit_for, emit_many, emit_on_success, emit_raw,
+    emit_to_account, event,
+};
+
+/// Events convert into the generated enum with `From`, and are matched by variant:
+///
+/// ```
+/// use anchor_lang::prelude::*;
+///
+/// #[event]
+/// pub struct Deposit {
+///     pub amount: u64,
+/// }
+///
+/// #[event]
+/// pub struct Withdraw {
+///     pub amount: u64,
+/// }
+///
+/// generate_event_enum! {
+///     pub enum ProgramEvent {
+///         Deposit,
+///         Withdraw,
+///     }
+/// }
+///
+/// let events: Vec<ProgramEvent> = vec![
+///     Deposit { amount: 5 }.into(),
+///     Withdraw { amount: 3 }.into(),
+/// ];
+/// let balance = events.iter().fold(0, |balance, event| match event {
+///     ProgramEvent::Deposit(deposit) => balance + deposit.amount,
+///     ProgramEvent::Withdraw(withdraw) => balance - withdraw.amount,
+/// });
+/// assert_eq!(balance, 2);
+/// ```
+pub use anchor_attribute_event::generate_event_enum;
 pub use anchor_attribute_program::program;
 pub use anchor_derive_accounts::Accounts;
 pub use anchor_derive_serde::{AnchorDeserialize, AnchorSerialize};
@@ -61,8 +97,10 @@ pub use borsh::de::BorshDeserialize as AnchorDeserialize;
 pub use borsh::ser::BorshSerialize as AnchorSerialize;
 pub use solana_program;
 
-#[cfg(feature = "event-cpi")]
-pub use anchor_attribute_event::{emit_cpi, event_cpi};
+// Without the `event-cpi` feature, these are stubs that fail to compile with a clear error
+pub use anchor_attribute_event::{
+    build_cpi_event_ix, emit_auto, emit_cpi, event_cpi, forward_cpi_event,
+};
 
 #[cfg(feature = "idl-build")]
 pub use anchor_syn::{self, idl::build::IdlBuild};
@@ -290,7 +328,227 @@ pub trait InstructionData: Discriminator + AnchorSerialize {
 
 /// An event that can be emitted via a Solana log. See [`emit!`](crate::prelude::emit) for an example.
 pub trait Event: AnchorSerialize + AnchorDeserialize + Discriminator {
-    fn data(&self) -> Vec<u8>;
+    /// Discriminator that prefixes the serialized event.
+    ///
+    /// This is the same as [`Discriminator::DISCRIMINATOR`] unless the event is declared with a
+    /// longer `discriminator_len`, in which case the trait constant holds its first 8 bytes.
+    const EVENT_DISCRIMINATOR: &'static [u8] = &Self::DISCRIMINATOR;
+
+    /// Schema version of the event declared with `#[event(version = <N>)]`, which is serialized
+    /// as a single byte right after the discriminator.
+    const EVENT_VERSION: Option<u8> = None;
+
+    /// Whether the event is declared with `#[event(correlated)]`, in which case an
+    /// `Option<[u8; 16]>` correlation id slot is serialized right after the discriminator, see
+    /// [`CorrelationId`](event::CorrelationId).
+    const EVENT_CORRELATED: bool = false;
+
+    /// Whether the event is declared with `#[event(timestamp)]`, in which case its last field,
+    /// `emitted_at`, is set from the `Clock` by the emit macros, see
+    /// [`emit_data_into`](Event::emit_data_into).
+    const EVENT_TIMESTAMP: bool = false;
+
+    /// Returns [`Discriminator::DISCRIMINATOR`] as an [`EventDiscriminator`], e.g. to compare it
+    /// with other discriminators or to print it as hex.
+    ///
+    /// [`EventDiscriminator`]: event::EventDiscriminator
+    fn typed_discriminator() -> event::EventDiscriminator {
+        event::EventDiscriminator(Self::DISCRIMINATOR)
+    }
+
+    /// Serializes the event, i.e. the discriminator, the correlation id slot of correlated
+    /// events, the version of versioned events and the event itself.
+    fn data(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(256);
+        self.data_into(&mut data);
+        data
+    }
+
+    /// Appends the serialized event to `out`, which allows reusing the same buffer to serialize
+    /// multiple events instead of allocating for each of them with [`data`](Event::data).
+    ///
+    /// The correlation id slot of correlated events is `None`, see
+    /// [`correlated_data_into`](Event::correlated_data_into).
+    fn data_into(&self, out: &mut Vec<u8>) {
+        self.correlated_data_into(out, None);
+    }
+
+    /// Appends the serialized event to `out` like [`data_into`](Event::data_into), with
+    /// `correlation_id` in the correlation id slot of
+    /// [`EVENT_CORRELATED`](Event::EVENT_CORRELATED) events.
+    fn correlated_data_into(
+        &self,
+        out: &mut Vec<u8>,
+        correlation_id: Option<event::CorrelationId>,
+    ) {
+        event::write_event_header::<Self>(out, correlation_id);
+        self.serialize(out).unwrap();
+    }
+
+    /// Appends the event to `out` the way the emit macros serialize it, i.e. like
+    /// [`correlated_data_into`](Event::correlated_data_into), but with the `emitted_at` field
+    /// of [`EVENT_TIMESTAMP`](Event::EVENT_TIMESTAMP) events set to the unix timestamp of the
+    /// `Clock`. [`data`](Event::data) and `data_into` leave the event as is.
+    fn emit_data_into(&self, out: &mut Vec<u8>, correlation_id: Option<event::CorrelationId>) {
+        let start = out.len();
+        self.correlated_data_into(out, correlation_id);
+        if Self::EVENT_TIMESTAMP {
+            __private::write_event_timestamp(&mut out[start..]);
+        }
+    }
+
+    /// Returns the number of bytes [`data`](Event::data) produces, i.e. the length of the
+    /// discriminator, the correlation id slot of correlated events, the version of versioned
+    /// events and the serialized event.
+    ///
+    /// This allows handlers to check the size of an event before emitting it, e.g. to use
+    /// [`emit_cpi!`](crate::emit_cpi) for events that are too large to be logged, or to skip
+    /// optional events under a tight compute budget:
+    ///
+    /// ```ignore
+    /// let event = MyEvent { data: 5 };
+    /// if event.encoded_len() <= MAX_LOGGED_LEN {
+    ///     emit!(event);
+    /// } else {
+    ///     emit_cpi!(event);
+    /// }
+    /// ```
+    ///
+    /// Borsh doesn't provide size hints, so the event is serialized into a writer that only
+    /// counts the bytes, without allocating. `zero_copy` events return their size without
+    /// serializing.
+    fn encoded_len(&self) -> usize {
+        let mut writer = event::LenWriter::default();
+        self.serialize(&mut writer).unwrap();
+        event::event_header_len::<Self>(None) + writer.len
+    }
+
+    /// Passes the serialized event to `sink`, which is what [`emit!`](crate::prelude::emit)
+    /// does with the `sol_log_data` syscall.
+    ///
+    /// This allows emitting events without a runtime, e.g. to capture them in unit tests:
+    ///
+    /// ```ignore
+    /// let mut logs = vec![];
+    /// MyEvent { data: 5 }.emit_with(|data| logs.push(data.concat()));
+    /// ```
+    fn emit_with<F: FnMut(&[&[u8]])>(&self, sink: F) {
+        self.emit_with_correlation_id(None, sink);
+    }
+
+    /// Passes the serialized event to `sink` like [`emit_with`](Event::emit_with), with
+    /// `correlation_id` in the correlation id slot of
+    /// [`EVENT_CORRELATED`](Event::EVENT_CORRELATED) events, which is what
+    /// [`emit!(ctx, event)`](crate::prelude::emit) does with the id of the `Context`.
+    fn emit_with_correlation_id<F: FnMut(&[&[u8]])>(
+        &self,
+        correlation_id: Option<event::CorrelationId>,
+        mut sink: F,
+    ) {
+        let mut data = Vec::with_capacity(256);
+        self.emit_data_into(&mut data, correlation_id);
+        sink(&[&data]);
+    }
+
+    /// Deserializes the event from `data`, which must start with
+    /// [`EVENT_DISCRIMINATOR`](Event::EVENT_DISCRIMINATOR), e.g. the decoded
+    /// `Program data:` log emitted by [`emit!`](crate::prelude::emit).
+    ///
+    /// For versioned events, the version in `data` must match
+    /// [`EVENT_VERSION`](Event::EVENT_VERSION). The correlation id slot of correlated events is
+    /// skipped.
+    fn try_from_event_data(data: &[u8]) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut data = Self::payload_from_event_data(data)?;
+        AnchorDeserialize::deserialize(&mut data).map_err(Into::into)
+    }
+
+    /// Returns the serialized event in `data` without the discriminator, the correlation id and
+    /// the version, i.e. the bytes [`try_from_event_data`](Event::try_from_event_data)
+    /// deserializes, with the same checks of the discriminator and the version.
+    fn payload_from_event_data(data: &[u8]) -> Result<&[u8]> {
+        let data = data
+            .strip_prefix(Self::EVENT_DISCRIMINATOR)
+            .ok_or(error::ErrorCode::InvalidEventDiscriminator)?;
+        let data = if Self::EVENT_CORRELATED {
+            event::split_correlation_id(data)?.1
+        } else {
+            data
+        };
+        match Self::EVENT_VERSION {
+            Some(version) => match data.split_first() {
+                Some((v, rest)) if *v == version => Ok(rest),
+                _ => Err(error::ErrorCode::InvalidEventVersion.into()),
+            },
+            None => Ok(data),
+        }
+    }
+
+    /// Returns the version serialized in `data` without deserializing the event, so that
+    /// off-chain parsers can pick the right deserializer. Returns `None` for unversioned events.
+    fn try_version_from_event_data(data: &[u8]) -> Result<Option<u8>> {
+        let data = data
+            .strip_prefix(Self::EVENT_DISCRIMINATOR)
+            .ok_or(error::ErrorCode::InvalidEventDiscriminator)?;
+        let data = if Self::EVENT_CORRELATED {
+            event::split_correlation_id(data)?.1
+        } else {
+            data
+        };
+        match Self::EVENT_VERSION {
+            Some(_) => data
+                .first()
+                .copied()
+                .map(Some)
+                .ok_or_else(|| error::ErrorCode::InvalidEventVersion.into()),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the correlation id that the event in `data` was emitted with, see
+    /// [`CorrelationId`](event::CorrelationId), or `None` for events emitted without an id, so
+    /// that indexers can join the events of a multi-program flow. Always returns `None` for
+    /// events that are not declared with `#[event(correlated)]`.
+    ///
+    /// ```ignore
+    /// let event = MyEvent::try_from_event_data(&data)?;
+    /// if let Some(id) = MyEvent::correlation_id_from_event_data(&data)? {
+    ///     flows.entry(id).or_default().push(event);
+    /// }
+    /// ```
+    fn correlation_id_from_event_data(data: &[u8]) -> Result<Option<event::CorrelationId>> {
+        let data = data
+            .strip_prefix(Self::EVENT_DISCRIMINATOR)
+            .ok_or(error::ErrorCode::InvalidEventDiscriminator)?;
+        if !Self::EVENT_CORRELATED {
+            return Ok(None);
+        }
+        event::split_correlation_id(data).map(|(id, _)| id)
+    }
+
+    /// Deserializes a versioned event from `data` and returns it together with its version,
+    /// which lets clients route the data of old and new schema versions to the right type.
+    ///
+    /// Fails with [`InvalidEventVersion`](error::ErrorCode::InvalidEventVersion) if the version
+    /// in `data` is not [`EVENT_VERSION`](Event::EVENT_VERSION), or if the event is not declared
+    /// with `#[event(version = <N>)]`.
+    ///
+    /// ```ignore
+    /// match MyEventV2::decode_versioned(&data) {
+    ///     Ok((_, event)) => handle_v2(event),
+    ///     Err(_) => handle_v1(MyEventV1::decode_versioned(&data)?.1),
+    /// }
+    /// ```
+    fn decode_versioned(data: &[u8]) -> Result<(u8, Self)>
+    where
+        Self: Sized,
+    {
+        let version = Self::try_version_from_event_data(data)?
+            .ok_or(error::ErrorCode::InvalidEventVersion)?;
+        Self::try_from_event_data(data).map(|event| (version, event))
+    }
 }
 
 // The serialized event data to be emitted via a Solana log.
@@ -307,6 +565,47 @@ pub trait Discriminator {
     fn discriminator() -> [u8; 8] {
         Self::DISCRIMINATOR
     }
+
+    /// Returns whether `data` starts with the discriminator. Returns `false` instead of
+    /// panicking if `data` is shorter than the discriminator.
+    fn discriminator_matches(data: &[u8]) -> bool {
+        data.starts_with(&Self::DISCRIMINATOR)
+    }
+}
+
+/// Returns the default discriminator of the event named `name`, i.e. the first 8 bytes of
+/// `Sha256("event:<name>")`, without declaring the event, e.g. for code generators of
+/// off-chain SDKs.
+///
+/// This is the [`Discriminator::DISCRIMINATOR`] that [`#[event]`](event) generates for events
+/// without a custom `discriminator` or `namespace`.
+///
+/// ```
+/// # use anchor_lang::{prelude::*, Discriminator};
+/// #[event]
+/// pub struct MyEvent {
+///     pub data: u64,
+/// }
+///
+/// assert_eq!(anchor_lang::event_discriminator("MyEvent"), MyEvent::DISCRIMINATOR);
+/// ```
+pub fn event_discriminator(name: &str) -> [u8; 8] {
+    hashed_discriminator(&format!("event:{name}"))
+}
+
+/// Returns the default discriminator of the account named `name`, i.e. the first 8 bytes of
+/// `Sha256("account:<name>")`, the account equivalent of [`event_discriminator`].
+///
+/// This is the [`Discriminator::DISCRIMINATOR`] that [`#[account]`](account) generates for
+/// accounts without a namespace.
+pub fn account_discriminator(name: &str) -> [u8; 8] {
+    hashed_discriminator(&format!("account:{name}"))
+}
+
+fn hashed_discriminator(preimage: &str) -> [u8; 8] {
+    let mut discriminator = [0; 8];
+    discriminator.copy_from_slice(&solana_program::hash::hash(preimage.as_bytes()).to_bytes()[..8]);
+    discriminator
 }
 
 /// Defines the space of an account for initialization.
@@ -390,11 +689,14 @@ pub mod prelude {
         access_control, account, accounts::account::Account,
         accounts::account_loader::AccountLoader, accounts::interface::Interface,
         accounts::interface_account::InterfaceAccount, accounts::program::Program,
-        accounts::signer::Signer, accounts::system_account::SystemAccount,
-        accounts::sysvar::Sysvar, accounts::unchecked_account::UncheckedAccount, constant,
-        context::Context, context::CpiContext, declare_id, emit, err, error, event, program,
-        require, require_eq, require_gt, require_gte, require_keys_eq, require_keys_neq,
-        require_neq, solana_program::bpf_loader_upgradeable::UpgradeableLoaderState, source,
+        accounts::ring_buffer::RingBuffer, accounts::signer::Signer,
+        accounts::system_account::SystemAccount, accounts::sysvar::Sysvar,
+        accounts::unchecked_account::UncheckedAccount, constant, context::Context,
+        context::CpiContext, declare_events, declare_id, emit, emit_compressed, emit_for,
+        emit_many, emit_on_success, emit_raw, emit_to_account, err, error, event,
+        generate_event_enum, program, require, require_eq, require_gt, require_gte,
+        require_keys_eq, require_keys_neq, require_neq,
+        solana_program::bpf_loader_upgradeable::UpgradeableLoaderState, source,
         system_program::System, zero_copy, AccountDeserialize, AccountSerialize, Accounts,
         AccountsClose, AccountsExit, AnchorDeserialize, AnchorSerialize, Id, InitSpace, Key,
         Lamports, Owner, ProgramData, Result, Space, ToAccountInfo, ToAccountInfos, ToAccountMetas,
@@ -418,8 +720,7 @@ pub mod prelude {
     pub use solana_program::sysvar::Sysvar as SolanaSysvar;
     pub use thiserror;
 
-    #[cfg(feature = "event-cpi")]
-    pub use super::{emit_cpi, event_cpi};
+    pub use super::{build_cpi_event_ix, emit_auto, emit_cpi, event_cpi, forward_cpi_event};
 
     #[cfg(feature = "idl-build")]
     pub use super::IdlBuild;
@@ -435,12 +736,155 @@ pub mod __private {
 
     pub use anchor_attribute_event::EventIndex;
 
+    #[cfg(feature = "arbitrary")]
+    pub use arbitrary;
+
     pub use base64;
 
     pub use bytemuck;
 
+    /// Items of `std` used by the code generated by [`#[event]`](crate::event), which is
+    /// referred to through this module so that events can also be declared in `#![no_std]`
+    /// crates.
+    pub use std::{io, vec::Vec};
+
     use solana_program::pubkey::Pubkey;
 
+    /// Reader that counts the bytes read from it, which the deserialization of events with
+    /// `#[optional_trailing]` fields uses to tell missing fields from fields that are cut off.
+    pub struct CountingReader<'a, R> {
+        reader: &'a mut R,
+        count: usize,
+    }
+
+    impl<'a, R: std::io::Read> CountingReader<'a, R> {
+        pub fn new(reader: &'a mut R) -> Self {
+            Self { reader, count: 0 }
+        }
+
+        /// Returns the number of bytes read so far.
+        pub fn count(&self) -> usize {
+            self.count
+        }
+    }
+
+    impl<R: std::io::Read> std::io::Read for CountingReader<'_, R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.reader.read(buf)?;
+            self.count += len;
+            Ok(len)
+        }
+    }
+
+    /// Writes the unix timestamp of the `Clock` sysvar over the `emitted_at` field of a
+    /// serialized `#[event(timestamp)]` event, i.e. the last 8 bytes of `data`, which the emit
+    /// macros do through [`Event::emit_data_into`](crate::Event::emit_data_into).
+    ///
+    /// The field is left as is if the clock isn't available, e.g. when events are serialized
+    /// off-chain by clients or in unit tests.
+    pub fn write_event_timestamp(data: &mut [u8]) {
+        use solana_program::sysvar::Sysvar;
+
+        if let (Ok(clock), Some(start)) = (
+            solana_program::clock::Clock::get(),
+            data.len().checked_sub(8),
+        ) {
+            data[start..].copy_from_slice(&clock.unix_timestamp.to_le_bytes());
+        }
+    }
+
+    /// Compresses events the way [`emit_compressed!`](crate::prelude::emit_compressed) emits
+    /// them.
+    ///
+    /// Events compiled out with the `feature` of [`#[event]`](crate::event) don't implement
+    /// `Event`, and shadow the method with one that returns no data.
+    pub trait CompressEmittedEvent: crate::Event {
+        /// Compresses the event with `codec` like
+        /// [`compress_event_data`](crate::event::compress_event_data), with the timestamp of
+        /// `#[event(timestamp)]` events set.
+        fn compress_emitted_event_data<C: crate::event::EventCodec>(&self, codec: &C) -> Vec<u8> {
+            let mut serialized = Vec::with_capacity(256);
+            self.emit_data_into(&mut serialized, None);
+            crate::event::compress_serialized_event::<Self, C>(&serialized, codec)
+        }
+    }
+
+    impl<E: crate::Event> CompressEmittedEvent for E {}
+
+    /// Marker trait implemented by accounts structs annotated with
+    /// [`#[event_cpi]`](crate::event_cpi).
+    ///
+    /// [`emit_cpi!`](crate::emit_cpi) requires the accounts of the current
+    /// context to implement this trait. If it's not implemented, add the
+    /// `#[event_cpi]` attribute to your `Accounts` struct.
+    #[cfg(feature = "event-cpi")]
+    pub trait EventCpiAccounts: crate::Bumps {
+        /// Seeds of the event authority PDA, excluding the bump.
+        const EVENT_AUTHORITY_SEEDS: &'static [&'static [u8]] = &[b"__event_authority"];
+        /// Whether the event authority numbers the events, i.e. `#[event_cpi(sequence)]`.
+        const EVENT_SEQUENCE: bool = false;
+
+        /// Program targeted by the self-CPI of the events, i.e. `#[event_cpi(program_id = ..)]`.
+        /// `None` for the current program.
+        fn event_program_id() -> Option<Pubkey> {
+            None
+        }
+
+        /// Bump of the event authority PDA, which `#[event_cpi]` always declares with `seeds`
+        /// and `bump`, read from the bumps of the accounts struct.
+        fn event_authority_bump(bumps: &Self::Bumps) -> u8;
+    }
+
+    /// Returns the event authority seeds of an accounts struct, see [`EventCpiAccounts`].
+    ///
+    /// Used by the event dispatcher of [`#[program]`](crate::program) to find the event
+    /// authorities of all instructions, including the ones that don't use `#[event_cpi]`:
+    /// `(&EventAuthoritySeeds::<T>(PhantomData)).get()` resolves to [`EventAuthoritySeedsOf::get`] if
+    /// `T` implements [`EventCpiAccounts`], and to [`NoEventAuthoritySeeds::get`] otherwise.
+    #[cfg(feature = "event-cpi")]
+    #[doc(hidden)]
+    pub struct EventAuthoritySeeds<T>(pub core::marker::PhantomData<T>);
+
+    #[cfg(feature = "event-cpi")]
+    #[doc(hidden)]
+    pub trait EventAuthoritySeedsOf {
+        fn get(&self) -> Option<&'static [&'static [u8]]>;
+    }
+
+    #[cfg(feature = "event-cpi")]
+    impl<T: EventCpiAccounts> EventAuthoritySeedsOf for EventAuthoritySeeds<T> {
+        fn get(&self) -> Option<&'static [&'static [u8]]> {
+            Some(T::EVENT_AUTHORITY_SEEDS)
+        }
+    }
+
+    #[cfg(feature = "event-cpi")]
+    #[doc(hidden)]
+    pub trait NoEventAuthoritySeeds {
+        fn get(&self) -> Option<&'static [&'static [u8]]> {
+            None
+        }
+    }
+
+    #[cfg(feature = "event-cpi")]
+    impl<T> NoEventAuthoritySeeds for &EventAuthoritySeeds<T> {}
+
+    /// Returns whether two event discriminators can't be told apart, i.e. whether the shorter
+    /// discriminator is a prefix of the longer one. Used by
+    /// [`declare_events!`](crate::declare_events).
+    #[doc(hidden)]
+    pub const fn discriminators_collide(a: &[u8], b: &[u8]) -> bool {
+        let len = if a.len() < b.len() { a.len() } else { b.len() };
+        let mut i = 0;
+        while i < len {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
     // Used to calculate the maximum between two expressions.
     // It is necessary for the calculation of the enum space.
     #[doc(hidden)]
@@ -719,6 +1163,53 @@ macro_rules! err {
     };
 }
 
+/// Asserts that the logs of a transaction contain an event that matches the given fields, e.g.
+/// in integration tests.
+///
+/// The logs are decoded with [`decode_logged_events`](crate::event::decode_logged_events),
+/// which means they can be any iterator of log lines, e.g. the `log_messages` of a transaction.
+/// Every field is compared with `==`, and all fields of the event must be given unless the
+/// fields end with `..`, which matches the event on the given fields only. Panics if none of the
+/// logged events of the type match.
+///
+/// # Example
+/// ```rust,ignore
+/// #[event]
+/// pub struct MyEvent {
+///     pub data: u64,
+///     pub label: String,
+/// }
+///
+/// assert_event!(&tx_logs, MyEvent { data: 5, label: "five" });
+/// assert_event!(&tx_logs, MyEvent { data: 5, .. });
+/// ```
+#[macro_export]
+macro_rules! assert_event {
+    ($logs:expr, $event:path { $($field:ident : $value:expr),* $(,)? }) => {
+        $crate::assert_event!(@check $logs, $event, { $($field: $value),* }, {})
+    };
+    ($logs:expr, $event:path { $($field:ident : $value:expr,)* .. }) => {
+        $crate::assert_event!(@check $logs, $event, { $($field: $value),* }, { .. })
+    };
+    (@check $logs:expr, $event:path, { $($field:ident : $value:expr),* }, { $($rest:tt)* }) => {{
+        let events = $crate::event::decode_logged_events::<$event>($logs);
+        let found = events.iter().any(|event| {
+            // Fails to compile if a field is missing without `..`
+            let $event { $($field: _,)* $($rest)* } = event;
+            true $(&& event.$field == $value)*
+        });
+        if !found {
+            panic!(
+                "no logged `{}` event matches `{}` ({} logged `{}` events)",
+                stringify!($event),
+                stringify!({ $($field: $value,)* $($rest)* }),
+                events.len(),
+                stringify!($event),
+            );
+        }
+    }};
+}
+
 /// Creates a [`Source`](crate::error::Source)
 #[macro_export]
 macro_rules! source {
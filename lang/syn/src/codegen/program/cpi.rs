@@ -35,6 +35,10 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
                         let ix = {
                             let ix = instruction::#ix_variant;
                             let mut data = Vec::with_capacity(256);
+                            if let Some(correlation_id) = ctx.correlation_id {
+                                data.extend_from_slice(&anchor_lang::event::CORRELATION_ID_IX_TAG_LE);
+                                data.extend_from_slice(&correlation_id);
+                            }
                             data.extend_from_slice(&#sighash_tts);
                             AnchorSerialize::serialize(&ix, &mut data)
                                 .map_err(|_| anchor_lang::error::ErrorCode::InstructionDidNotSerialize)?;
@@ -22,6 +22,7 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
                         program_id,
                         accounts,
                         ix_data,
+                        correlation_id,
                     )
                 }
             }
@@ -32,7 +33,12 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
         Err(anchor_lang::error::ErrorCode::InstructionFallbackNotFound.into())
     });
 
-    let event_cpi_handler = generate_event_cpi_handler();
+    let missing_ix = gen_fallback(program).unwrap_or(quote! {
+        Err(anchor_lang::error::ErrorCode::InstructionMissing.into())
+    });
+
+    let event_cpi_handler = generate_event_cpi_handler(false);
+    let seeded_event_cpi_handler = generate_event_cpi_handler(true);
 
     quote! {
         /// Performs method dispatch.
@@ -50,10 +56,14 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
         /// With this 8 byte identifier, Anchor performs method dispatch,
         /// matching the given 8 byte identifier to the associated method
         /// handler, which leads to user defined code being eventually invoked.
+        ///
+        /// `correlation_id` is the id of instructions invoked with
+        /// `CpiContext::with_correlation_id`, which is passed to the `Context`.
         fn dispatch<'info>(
             program_id: &Pubkey,
             accounts: &'info [AccountInfo<'info>],
             data: &[u8],
+            correlation_id: Option<anchor_lang::event::CorrelationId>,
         ) -> anchor_lang::Result<()> {
             // Split the instruction data into the first 8 byte method
             // identifier (sighash) and the serialized instruction data.
@@ -86,9 +96,23 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
                         Err(anchor_lang::error::ErrorCode::IdlInstructionStub.into())
                     }
                 }
-                anchor_lang::event::EVENT_IX_TAG_LE => {
+                anchor_lang::event::CORRELATION_ID_IX_TAG_LE => {
+                    // Instruction invoked with `CpiContext::with_correlation_id`, which is
+                    // dispatched again without the tag and the id, with the id
+                    let (correlation_id, data) = anchor_lang::event::split_correlation_id_ix_data(data)
+                        .ok_or(anchor_lang::error::ErrorCode::InstructionDidNotDeserialize)?;
+                    if data.len() < 8 {
+                        return #missing_ix;
+                    }
+                    dispatch(program_id, accounts, data, Some(correlation_id))
+                }
+                anchor_lang::event::EVENT_IX_TAG_LE
+                | anchor_lang::event::EVENT_SEQUENCE_IX_TAG_LE => {
                     #event_cpi_handler
                 }
+                anchor_lang::event::EVENT_AUTHORITY_BUMP_IX_TAG_LE => {
+                    #seeded_event_cpi_handler
+                }
                 _ => {
                     #fallback_fn
                 }
@@ -109,15 +133,37 @@ pub fn gen_fallback(program: &Program) -> Option<proc_macro2::TokenStream> {
 }
 
 /// Generate the event-cpi instruction handler based on whether the `event-cpi` feature is enabled.
-pub fn generate_event_cpi_handler() -> proc_macro2::TokenStream {
+///
+/// `seeded` handles the events signed by an event authority with a seed suffix, whose bump
+/// precedes the event, see `EVENT_AUTHORITY_BUMP_IX_TAG`.
+pub fn generate_event_cpi_handler(seeded: bool) -> proc_macro2::TokenStream {
     #[cfg(feature = "event-cpi")]
-    quote! {
-        // `event-cpi` feature is enabled, dispatch self-cpi instruction
-        __private::__events::__event_dispatch(program_id, accounts, &ix_data)
+    if seeded {
+        quote! {
+            // `event-cpi` feature is enabled, dispatch self-cpi instruction with the bump of
+            // the event authority
+            match ix_data.split_first() {
+                Some((authority_bump, ix_data)) => __private::__events::__event_dispatch(
+                    program_id,
+                    accounts,
+                    ix_data,
+                    Some(*authority_bump),
+                ),
+                None => Err(anchor_lang::error::ErrorCode::InstructionDidNotDeserialize.into()),
+            }
+        }
+    } else {
+        quote! {
+            // `event-cpi` feature is enabled, dispatch self-cpi instruction
+            __private::__events::__event_dispatch(program_id, accounts, &ix_data, None)
+        }
     }
     #[cfg(not(feature = "event-cpi"))]
-    quote! {
-        // `event-cpi` feature is not enabled
-        Err(anchor_lang::error::ErrorCode::EventInstructionStub.into())
+    {
+        let _ = seeded;
+        quote! {
+            // `event-cpi` feature is not enabled
+            Err(anchor_lang::error::ErrorCode::EventInstructionStub.into())
+        }
     }
 }
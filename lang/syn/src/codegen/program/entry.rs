@@ -66,7 +66,7 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
                 return #fallback_maybe;
             }
 
-            dispatch(program_id, accounts, data)
+            dispatch(program_id, accounts, data, None)
         }
 
         /// Module representing the program.
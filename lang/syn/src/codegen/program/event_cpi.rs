@@ -0,0 +1,37 @@
+use quote::quote;
+
+/// Generate the event authority PDA helper if the `event-cpi` feature is enabled.
+pub fn generate() -> proc_macro2::TokenStream {
+    #[cfg(feature = "event-cpi")]
+    {
+        let authority = crate::parser::accounts::event_cpi::EventAuthority::get();
+        let authority_seeds = authority.seeds;
+
+        quote! {
+            /// Returns the address and bump of the event authority PDA that signs the self-CPI
+            /// instructions of [`emit_cpi!`](anchor_lang::prelude::emit_cpi).
+            pub fn event_authority_pda(
+                program_id: &anchor_lang::solana_program::pubkey::Pubkey,
+            ) -> (anchor_lang::solana_program::pubkey::Pubkey, u8) {
+                anchor_lang::solana_program::pubkey::Pubkey::find_program_address(
+                    &[#authority_seeds],
+                    program_id,
+                )
+            }
+
+            /// Returns the address and bump of the event authority PDA with the given seed
+            /// suffix, i.e. of accounts structs annotated with `#[event_cpi(seed = "<suffix>")]`.
+            pub fn event_authority_pda_with_seed(
+                program_id: &anchor_lang::solana_program::pubkey::Pubkey,
+                seed: &[u8],
+            ) -> (anchor_lang::solana_program::pubkey::Pubkey, u8) {
+                anchor_lang::solana_program::pubkey::Pubkey::find_program_address(
+                    &[#authority_seeds, seed],
+                    program_id,
+                )
+            }
+        }
+    }
+    #[cfg(not(feature = "event-cpi"))]
+    quote! {}
+}
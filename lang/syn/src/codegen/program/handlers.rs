@@ -9,6 +9,10 @@ use quote::{quote, ToTokens};
 // so.
 pub fn generate(program: &Program) -> proc_macro2::TokenStream {
     let program_name = &program.name;
+    let max_events = match program.args.max_events {
+        Some(max_events) => quote! { Some(#max_events) },
+        None => quote! { None },
+    };
     // A constant token stream that stores the accounts and functions, required to live
     // inside the target program in order to get the program ID.
     let idl_accounts_and_functions = idl_accounts_and_functions();
@@ -91,7 +95,7 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
         }
     };
 
-    let event_cpi_mod = generate_event_cpi_mod();
+    let event_cpi_mod = generate_event_cpi_mod(program);
 
     let non_inlined_handlers: Vec<proc_macro2::TokenStream> = program
         .ixs
@@ -112,12 +116,56 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
                     anchor_lang::solana_program::program::set_return_data(&return_data);
                 },
             };
+            let ctx = quote! {
+                anchor_lang::context::Context {
+                    correlation_id: __correlation_id,
+                    ..anchor_lang::context::Context::new(
+                        __program_id,
+                        &mut __accounts,
+                        __remaining_accounts,
+                        __bumps,
+                    )
+                }
+            };
+            let invoke_handler = if program.args.max_events.is_some() || uses_events(ix) {
+                quote! {
+                    let __events = anchor_lang::event::InstructionEvents::new(#max_events);
+                    let result = #program_name::#ix_method_name(
+                        #ctx.with_events(&__events),
+                        #(#ix_arg_names),*
+                    )?;
+
+                    // Maybe set Solana return data.
+                    #maybe_set_return_data
+
+                    // Exit routine.
+                    __accounts.exit(__program_id)?;
+
+                    // Fail if too many events were emitted, or log the events of
+                    // `emit_on_success!` now that the instruction succeeded.
+                    __events.finish()
+                }
+            } else {
+                quote! {
+                    let result = #program_name::#ix_method_name(
+                        #ctx,
+                        #(#ix_arg_names),*
+                    )?;
+
+                    // Maybe set Solana return data.
+                    #maybe_set_return_data
+
+                    // Exit routine.
+                    __accounts.exit(__program_id)
+                }
+            };
             quote! {
                 #[inline(never)]
                 pub fn #ix_method_name<'info>(
                     __program_id: &Pubkey,
                     __accounts: &'info[AccountInfo<'info>],
                     __ix_data: &[u8],
+                    __correlation_id: Option<anchor_lang::event::CorrelationId>,
                 ) -> anchor_lang::Result<()> {
                     #[cfg(not(feature = "no-log-ix-name"))]
                     anchor_lang::prelude::msg!(#ix_name_log);
@@ -143,21 +191,7 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
                     )?;
 
                     // Invoke user defined handler.
-                    let result = #program_name::#ix_method_name(
-                        anchor_lang::context::Context::new(
-                            __program_id,
-                            &mut __accounts,
-                            __remaining_accounts,
-                            __bumps,
-                        ),
-                        #(#ix_arg_names),*
-                    )?;
-
-                    // Maybe set Solana return data.
-                    #maybe_set_return_data
-
-                    // Exit routine.
-                    __accounts.exit(__program_id)
+                    #invoke_handler
                 }
             }
         })
@@ -189,18 +223,51 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
     }
 }
 
+// Returns whether the handler may need the events of its context, i.e. if it uses
+// `emit_on_success!` or passes the context to other functions, which may. Counting the
+// events of the handler is only needed with `max_events`.
+fn uses_events(ix: &crate::Ix) -> bool {
+    let ctx = match ix.raw_method.sig.inputs.first() {
+        Some(syn::FnArg::Typed(arg)) => match &*arg.pat {
+            syn::Pat::Ident(pat) => Some(pat.ident.clone()),
+            _ => None,
+        },
+        _ => None,
+    };
+    let attrs = ix.raw_method.attrs.iter().map(|attr| attr.tokens.clone());
+    let block = std::iter::once(ix.raw_method.block.to_token_stream());
+    attrs
+        .chain(block)
+        .any(|tokens| uses_events_in(tokens, ctx.as_ref()))
+}
+
+fn uses_events_in(tokens: proc_macro2::TokenStream, ctx: Option<&syn::Ident>) -> bool {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    tokens.iter().enumerate().any(|(i, token)| match token {
+        proc_macro2::TokenTree::Ident(ident) if ident == "emit_on_success" => true,
+        // The context is used other than through its fields, e.g. `helper(&ctx)`
+        proc_macro2::TokenTree::Ident(ident) if Some(ident) == ctx => !matches!(
+            tokens.get(i + 1),
+            Some(proc_macro2::TokenTree::Punct(punct)) if punct.as_char() == '.'
+        ),
+        proc_macro2::TokenTree::Group(group) => uses_events_in(group.stream(), ctx),
+        _ => false,
+    })
+}
+
 fn generate_ix_variant_name(name: String) -> proc_macro2::TokenStream {
     let n = name.to_camel_case();
     n.parse().unwrap()
 }
 
 /// Generate the event module based on whether the `event-cpi` feature is enabled.
-fn generate_event_cpi_mod() -> proc_macro2::TokenStream {
+fn generate_event_cpi_mod(program: &Program) -> proc_macro2::TokenStream {
     #[cfg(feature = "event-cpi")]
     {
         let authority = crate::parser::accounts::event_cpi::EventAuthority::get();
         let authority_name = authority.name;
         let authority_seeds = authority.seeds;
+        let accounts_idents = program.ixs.iter().map(|ix| &ix.anchor_ident);
 
         quote! {
             /// __events mod defines handler for self-cpi based event logging
@@ -212,6 +279,7 @@ fn generate_event_cpi_mod() -> proc_macro2::TokenStream {
                     program_id: &Pubkey,
                     accounts: &[AccountInfo],
                     event_data: &[u8],
+                    authority_bump: Option<u8>,
                 ) -> anchor_lang::Result<()> {
                     let given_event_authority = next_account_info(&mut accounts.iter())?;
                     if !given_event_authority.is_signer {
@@ -221,21 +289,74 @@ fn generate_event_cpi_mod() -> proc_macro2::TokenStream {
                         .with_account_name(#authority_name));
                     }
 
-                    let (expected_event_authority, _) =
-                        Pubkey::find_program_address(&[#authority_seeds], &program_id);
-                    if given_event_authority.key() != expected_event_authority {
-                        return Err(anchor_lang::error::Error::from(
-                            anchor_lang::error::ErrorCode::ConstraintSeeds,
-                        )
-                        .with_account_name(#authority_name)
-                        .with_pubkeys((given_event_authority.key(), expected_event_authority)));
+                    match authority_bump {
+                        Some(authority_bump) => {
+                            if !is_event_authority_with_seed(
+                                program_id,
+                                given_event_authority.key,
+                                authority_bump,
+                            ) {
+                                return Err(anchor_lang::error::Error::from(
+                                    anchor_lang::error::ErrorCode::ConstraintSeeds,
+                                )
+                                .with_account_name(#authority_name));
+                            }
+                        }
+                        None => {
+                            let (expected_event_authority, _) = event_authority_pda(program_id);
+                            if given_event_authority.key() != expected_event_authority {
+                                return Err(anchor_lang::error::Error::from(
+                                    anchor_lang::error::ErrorCode::ConstraintSeeds,
+                                )
+                                .with_account_name(#authority_name)
+                                .with_pubkeys((given_event_authority.key(), expected_event_authority)));
+                            }
+                        }
                     }
 
                     Ok(())
                 }
+
+                /// Returns whether the given key is the event authority with the given bump of an
+                /// instruction whose accounts struct has a seed suffix, i.e.
+                /// `#[event_cpi(seed = "..")]`.
+                ///
+                /// Each seed suffix is only checked once, with `create_program_address`, since
+                /// deriving the PDAs with `find_program_address` on every event is expensive.
+                fn is_event_authority_with_seed(
+                    program_id: &Pubkey,
+                    key: &Pubkey,
+                    authority_bump: u8,
+                ) -> bool {
+                    use anchor_lang::__private::{
+                        EventAuthoritySeeds, EventAuthoritySeedsOf as _, NoEventAuthoritySeeds as _,
+                    };
+
+                    let default_seeds: &[&[u8]] = &[#authority_seeds];
+                    let all_seeds: &[Option<&[&[u8]]>] = &[
+                        #((&EventAuthoritySeeds::<#accounts_idents>(std::marker::PhantomData)).get(),)*
+                    ];
+                    let bump = [authority_bump];
+                    all_seeds
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, seeds)| Some((i, (*seeds)?)))
+                        // Instructions often share a suffix, only its first occurrence is checked
+                        .filter(|(i, seeds)| {
+                            *seeds != default_seeds && !all_seeds[..*i].contains(&Some(*seeds))
+                        })
+                        .any(|(_, seeds)| {
+                            let seeds = [seeds, &[&bump[..]]].concat();
+                            Pubkey::create_program_address(&seeds, program_id)
+                                .map_or(false, |address| address == *key)
+                        })
+                }
             }
         }
     }
     #[cfg(not(feature = "event-cpi"))]
-    quote! {}
+    {
+        let _ = program;
+        quote! {}
+    }
 }
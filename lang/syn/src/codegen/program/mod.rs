@@ -6,6 +6,7 @@ pub mod common;
 mod cpi;
 mod dispatch;
 mod entry;
+mod event_cpi;
 mod handlers;
 mod idl;
 mod instruction;
@@ -20,6 +21,7 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
     let instruction = instruction::generate(program);
     let cpi = cpi::generate(program);
     let accounts = accounts::generate(program);
+    let event_cpi = event_cpi::generate();
 
     #[allow(clippy::let_and_return)]
     let ret = {
@@ -34,6 +36,7 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
             #instruction
             #cpi
             #accounts
+            #event_cpi
         }
     };
 
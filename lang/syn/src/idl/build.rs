@@ -1,6 +1,6 @@
 pub use serde_json;
 
-use crate::{parser::docs, AccountField, AccountsStruct, Error, Program};
+use crate::{parser::docs, AccountField, AccountsStruct, Error, EventArgs, Program};
 use heck::MixedCase;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
@@ -56,6 +56,13 @@ pub fn get_seeds_feature() -> bool {
         .unwrap_or(false)
 }
 
+#[inline(always)]
+pub fn get_event_discriminators_feature() -> bool {
+    std::option_env!("ANCHOR_IDL_BUILD_EVENT_DISCRIMINATORS_FEATURE")
+        .map(|val| val == "TRUE")
+        .unwrap_or(false)
+}
+
 // Returns TokenStream for IdlType enum and the syn::TypePath for the defined
 // type if any.
 // Returns Err when the type wasn't parsed successfully.
@@ -284,25 +291,48 @@ pub fn idl_field_ts_from_syn_field(
 #[allow(clippy::result_unit_err)]
 pub fn idl_event_field_ts_from_syn_field(
     field: &syn::Field,
+    field_index: usize,
+    no_docs: bool,
+    type_params: &Vec<syn::Ident>,
 ) -> Result<(TokenStream, Vec<syn::TypePath>), ()> {
     let (idl, _) = get_module_paths();
 
-    let name = field.ident.as_ref().unwrap().to_string().to_mixed_case();
-    let (ty, defined) = idl_type_ts_from_syn_type(&field.ty, &vec![])?;
+    let (name, rust_name) = crate::parser::event::idl_field_name(field, field_index);
+    let rust_name = match rust_name {
+        Some(rust_name) => quote! { Some(#rust_name.into()) },
+        None => quote! { None },
+    };
+    let docs = match docs::parse(&field.attrs) {
+        Some(docs) if !no_docs => quote! {Some(vec![#(#docs.into()),*])},
+        _ => quote! {None},
+    };
+    let (ty, defined) = idl_type_ts_from_syn_type(&field.ty, type_params)?;
 
-    let index: bool = field
-        .attrs
-        .get(0)
-        .and_then(|attr| attr.path.segments.first())
-        .map(|segment| segment.ident == "index")
-        .unwrap_or(false);
+    let event_index = crate::parser::event::parse_index(field).map_err(|_| ())?;
+    let index = event_index.is_some();
+    let index_name = match event_index.and_then(|index| index.name) {
+        Some(name) => quote! { Some(#name.into()) },
+        None => quote! { None },
+    };
+    let decimals = match crate::parser::event::parse_decimals(field).map_err(|_| ())? {
+        Some(decimals) => quote! { Some(#decimals) },
+        None => quote! { None },
+    };
 
     Ok((
         quote! {
-            #idl::IdlEventField {
-                name: #name.into(),
-                ty: #ty,
-                index: #index,
+            {
+                let ty = #ty;
+                #idl::IdlEventField {
+                    name: #name.into(),
+                    rust_name: #rust_name,
+                    docs: #docs,
+                    optional: #idl::IdlEventField::optional_marker(#index, &ty),
+                    ty,
+                    index: #index,
+                    index_name: #index_name,
+                    decimals: #decimals,
+                }
             }
         },
         defined,
@@ -563,32 +593,88 @@ pub fn gen_idl_build_impl_for_enum(enm: ItemEnum, no_docs: bool) -> TokenStream
 }
 
 // generates the IDL generation impl for for an event
-pub fn gen_idl_build_impl_for_event(event_strct: &ItemStruct) -> TokenStream {
+pub fn gen_idl_build_impl_for_event(
+    event_strct: &ItemStruct,
+    args: &EventArgs,
+    no_docs: bool,
+) -> TokenStream {
+    // The fields and the defined types of fields gated with `#[cfg]` are only added to the
+    // IDL of the builds the fields exist in
     fn parse_fields(
-        fields: &syn::FieldsNamed,
-    ) -> Result<(Vec<TokenStream>, Vec<syn::TypePath>), ()> {
+        fields: &syn::Fields,
+        no_docs: bool,
+        type_params: &Vec<syn::Ident>,
+    ) -> Result<(Vec<TokenStream>, Vec<TokenStream>), ()> {
         let (fields, defined) = fields
-            .named
             .iter()
-            .map(idl_event_field_ts_from_syn_field)
+            .enumerate()
+            .map(|(i, field)| {
+                let (field_ts, defined) =
+                    idl_event_field_ts_from_syn_field(field, i, no_docs, type_params)?;
+                let is_flattened = crate::parser::event::parse_flatten(field).map_err(|_| ())?;
+                let fields_ts = if is_flattened {
+                    gen_flattened_event_fields(field, i)
+                } else {
+                    quote! { vec![#field_ts] }
+                };
+                let cfgs = crate::parser::event::cfg_attrs(field);
+                let defined = defined
+                    .into_iter()
+                    .map(|defined| {
+                        quote! {
+                            #(#cfgs)*
+                            {
+                                <#defined>::__anchor_private_insert_idl_defined(defined_types);
+
+                                let path = <#defined>::__anchor_private_full_path();
+                                <#defined>::__anchor_private_gen_idl_type()
+                                    .and_then(|ty| defined_types.insert(path, ty));
+                            }
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                Ok((quote! { #(#cfgs)* fields.push(#fields_ts); }, defined))
+            })
             .collect::<Result<Vec<_>, _>>()?
             .into_iter()
             .unzip::<_, _, Vec<_>, Vec<_>>();
-        let defined = defined
-            .into_iter()
-            .flatten()
-            .collect::<Vec<syn::TypePath>>();
+        let defined = defined.into_iter().flatten().collect::<Vec<_>>();
 
         Ok((fields, defined))
     }
 
+    // Generic array lengths and types are named in the IDL, so that the IDL of the event
+    // doesn't depend on how it's instantiated
+    let type_params = event_strct
+        .generics
+        .params
+        .iter()
+        .filter_map(|p| match p {
+            syn::GenericParam::Type(ty) => Some(ty.ident.clone()),
+            syn::GenericParam::Const(c) => Some(c.ident.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
     let res = match &event_strct.fields {
-        syn::Fields::Named(fields) => parse_fields(fields),
-        _ => Err(()),
+        syn::Fields::Unit => Err(()),
+        fields => parse_fields(fields, no_docs, &type_params),
     };
 
     let (idl, _) = get_module_paths();
-    let name = event_strct.ident.to_string();
+    let name = args.idl_name(&event_strct.ident);
+    let discriminator = match args.idl_discriminator(&event_strct.ident) {
+        Some(discriminator) => quote! { Some(vec![#(#discriminator),*]) },
+        None => quote! { None },
+    };
+    let version = match args.version {
+        Some(version) => quote! { Some(#version) },
+        None => quote! { None },
+    };
+    let correlated = args.correlated;
+    let via = match args.via {
+        crate::EventVia::Log => quote! { #idl::IdlEventVia::Log },
+        crate::EventVia::Cpi => quote! { #idl::IdlEventVia::Cpi },
+    };
 
     let (ret_ts, types_ts) = match res {
         Ok((fields, defined)) => {
@@ -596,19 +682,20 @@ pub fn gen_idl_build_impl_for_event(event_strct: &ItemStruct) -> TokenStream {
                 Some(
                     #idl::IdlEvent {
                         name: #name.into(),
-                        fields: vec![#(#fields),*],
+                        discriminator: #discriminator,
+                        version: #version,
+                        correlated: #correlated,
+                        fields: {
+                            #[allow(unused_mut)]
+                            let mut fields: Vec<Vec<#idl::IdlEventField>> = vec![];
+                            #(#fields)*
+                            fields.concat()
+                        },
+                        via: #via,
                     }
                 )
             };
-            let types_ts = quote! {
-                #({
-                    <#defined>::__anchor_private_insert_idl_defined(defined_types);
-
-                    let path = <#defined>::__anchor_private_full_path();
-                    <#defined>::__anchor_private_gen_idl_type()
-                        .and_then(|ty| defined_types.insert(path, ty));
-                });*
-            };
+            let types_ts = quote! { #(#defined)* };
             (ret_ts, types_ts)
         }
         Err(()) => (quote! { None }, quote! {}),
@@ -617,19 +704,92 @@ pub fn gen_idl_build_impl_for_event(event_strct: &ItemStruct) -> TokenStream {
     let ident = &event_strct.ident;
     let input_generics = &event_strct.generics;
     let (impl_generics, ty_generics, where_clause) = input_generics.split_for_impl();
+    let gen_fn_name = gen_idl_event_fn_name(ident);
 
+    // Generated outside of the impl so that the IDL of generic events can be printed without
+    // instantiating them
     quote! {
+        #[allow(non_snake_case)]
+        fn #gen_fn_name(
+            defined_types: &mut std::collections::HashMap<String, #idl::IdlTypeDefinition>,
+        ) -> Option<#idl::IdlEvent> {
+            #types_ts
+            #ret_ts
+        }
+
         impl #impl_generics #ident #ty_generics #where_clause {
             pub fn __anchor_private_gen_idl_event(
                 defined_types: &mut std::collections::HashMap<String, #idl::IdlTypeDefinition>,
             ) -> Option<#idl::IdlEvent> {
-                #types_ts
-                #ret_ts
+                #gen_fn_name(defined_types)
+            }
+        }
+    }
+}
+
+// generates the `json_schema` function of an event from its IDL, see
+// `idl::json_schema::event_json_schema`
+pub fn gen_json_schema_function_for_event(event_strct: &ItemStruct) -> TokenStream {
+    if let syn::Fields::Unit = event_strct.fields {
+        return quote! {};
+    }
+
+    let (_, serde_json) = get_module_paths();
+    let ident = &event_strct.ident;
+    let (impl_generics, ty_generics, where_clause) = event_strct.generics.split_for_impl();
+    let msg = format!("the IDL of the event `{ident}` can't be generated");
+
+    quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Returns the JSON schema of the event, derived from its IDL, see
+            /// `anchor_lang::anchor_syn::idl::json_schema::event_json_schema`.
+            pub fn json_schema() -> #serde_json::Value {
+                let mut defined_types = std::collections::HashMap::new();
+                let event = Self::__anchor_private_gen_idl_event(&mut defined_types).expect(#msg);
+                anchor_lang::anchor_syn::idl::json_schema::event_json_schema(
+                    &event,
+                    &defined_types,
+                )
             }
         }
     }
 }
 
+fn gen_idl_event_fn_name(event_ident: &Ident) -> Ident {
+    format_ident!("__anchor_private_gen_idl_event_{}", event_ident.to_string())
+}
+
+// Returns TokenStream for the IdlEventFields of the struct type of an `#[event_flatten]`
+// event field. The fields don't have a `rust_name` since they aren't fields of the event in
+// Rust.
+fn gen_flattened_event_fields(field: &syn::Field, field_index: usize) -> TokenStream {
+    let (idl, _) = get_module_paths();
+    let ty = &field.ty;
+    let msg = format!(
+        "`#[event_flatten]` field `{}` must be a struct with named fields",
+        crate::parser::event::field_name(field, field_index)
+    );
+
+    quote! {
+        match <#ty>::__anchor_private_gen_idl_type().map(|ty_def| ty_def.ty) {
+            Some(#idl::IdlTypeDefinitionTy::Struct { fields }) => fields
+                .into_iter()
+                .map(|field| #idl::IdlEventField {
+                    name: field.name,
+                    rust_name: None,
+                    docs: field.docs,
+                    ty: field.ty,
+                    index: false,
+                    index_name: None,
+                    optional: None,
+                    decimals: None,
+                })
+                .collect::<Vec<_>>(),
+            _ => panic!(#msg),
+        }
+    }
+}
+
 // generates the IDL generation impl for the Accounts struct
 pub fn gen_idl_build_impl_for_accounts_struct(
     accs_strct: &AccountsStruct,
@@ -842,12 +1002,33 @@ pub fn gen_idl_print_function_for_program(program: &Program, no_docs: bool) -> T
     }
 }
 
-pub fn gen_idl_print_function_for_event(event: &ItemStruct) -> TokenStream {
+pub fn gen_idl_print_function_for_event(event: &ItemStruct, args: &EventArgs) -> TokenStream {
     let (idl, serde_json) = get_module_paths();
 
     let ident = &event.ident;
     let fn_name = format_ident!("__anchor_private_print_idl_event_{}", ident.to_string());
-    let impl_gen = gen_idl_build_impl_for_event(event);
+    let gen_fn_name = gen_idl_event_fn_name(ident);
+    let impl_gen = gen_idl_build_impl_for_event(event, args, get_no_docs());
+
+    let print_discriminator_const = if get_event_discriminators_feature() {
+        let name = args.idl_discriminator_const_name(ident);
+        let discriminator = args.discriminator(ident);
+        let len = discriminator.len();
+        let value = format!("{discriminator:?}");
+        quote! {
+            let idl = #idl::IdlConst {
+                name: #name.into(),
+                ty: #idl::IdlType::Array(Box::new(#idl::IdlType::U8), #len),
+                value: #value.into(),
+            };
+
+            println!("---- IDL begin const ----");
+            println!("{}", #serde_json::to_string_pretty(&idl).unwrap());
+            println!("---- IDL end const ----");
+        }
+    } else {
+        quote! {}
+    };
 
     quote! {
         #impl_gen
@@ -855,7 +1036,7 @@ pub fn gen_idl_print_function_for_event(event: &ItemStruct) -> TokenStream {
         #[test]
         pub fn #fn_name() {
             let mut defined_types: std::collections::HashMap<String, #idl::IdlTypeDefinition> = std::collections::HashMap::new();
-            let event = #ident::__anchor_private_gen_idl_event(&mut defined_types);
+            let event = #gen_fn_name(&mut defined_types);
 
             if let Some(event) = event {
                 let json = #serde_json::json!({
@@ -866,6 +1047,8 @@ pub fn gen_idl_print_function_for_event(event: &ItemStruct) -> TokenStream {
                 println!("---- IDL begin event ----");
                 println!("{}", #serde_json::to_string_pretty(&json).unwrap());
                 println!("---- IDL end event ----");
+
+                #print_discriminator_const
             }
         }
     }
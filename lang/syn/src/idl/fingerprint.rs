@@ -0,0 +1,325 @@
+//! Fingerprints of the wire layout of the events of an IDL, which detect events whose layout
+//! changed without a version bump, i.e. changes that silently break deployed consumers.
+
+use super::types::{
+    EnumFields, Idl, IdlDefinedTypeArg, IdlEvent, IdlField, IdlType, IdlTypeDefinition,
+    IdlTypeDefinitionTy,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// Fingerprint of an event, and the version it was declared with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventFingerprint {
+    /// Version given with `#[event(version = <N>)]`, if any.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub version: Option<u8>,
+    /// Lowercase hex of the SHA256 hash of the layout of the event, see [`event_fingerprint`].
+    pub fingerprint: String,
+}
+
+/// Fingerprints of the events of a program by event name, sorted so that the serialized
+/// fingerprints are stable.
+pub type EventFingerprints = BTreeMap<String, EventFingerprint>;
+
+/// Returns the fingerprints of the events of `idl`.
+pub fn events_fingerprints(idl: &Idl) -> EventFingerprints {
+    idl.events
+        .iter()
+        .flatten()
+        .map(|event| {
+            let fingerprint = EventFingerprint {
+                version: event.version,
+                fingerprint: event_fingerprint(event, &idl.types),
+            };
+            (event.name.clone(), fingerprint)
+        })
+        .collect()
+}
+
+/// Returns the lowercase hex of the SHA256 hash of the layout of `event`, i.e. the names and
+/// types of its fields in order, its custom discriminator if it has one, and whether it has a
+/// correlation id slot.
+///
+/// Defined types are replaced by their own layout, looked up in `types`, so that changing a
+/// nested struct changes the fingerprint of the events that contain it, while renaming it
+/// doesn't. Docs, index metadata and the version of the event are not part of the layout.
+pub fn event_fingerprint(event: &IdlEvent, types: &[IdlTypeDefinition]) -> String {
+    let types = types
+        .iter()
+        .map(|ty| (ty.name.as_str(), ty))
+        .collect::<BTreeMap<_, _>>();
+    let mut visiting = vec![];
+    let fields = event
+        .fields
+        .iter()
+        .map(|field| json!([field.name, layout(&field.ty, &types, &mut visiting)]))
+        .collect::<Vec<_>>();
+    let mut layout = json!({
+        "discriminator": event.discriminator,
+        "fields": fields,
+    });
+    // Only added for correlated events so that the fingerprints of other events don't change
+    if event.correlated {
+        layout["correlated"] = json!(true);
+    }
+
+    crate::hash::hash(layout.to_string().as_bytes())
+        .to_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Returns a message for each event of `current` whose layout differs from its fingerprint in
+/// `committed` while its version is the same. Added and removed events are not breaking.
+pub fn breaking_changes(committed: &EventFingerprints, current: &EventFingerprints) -> Vec<String> {
+    current
+        .iter()
+        .filter_map(|(name, current)| {
+            let committed = committed.get(name)?;
+            if committed.fingerprint == current.fingerprint || committed.version != current.version
+            {
+                return None;
+            }
+            let next_version = committed
+                .version
+                .map_or(1, |version| version.saturating_add(1));
+            Some(format!(
+                "Event `{name}` changed its layout without a version bump, \
+                declare it with `#[event(version = {next_version})]`"
+            ))
+        })
+        .collect()
+}
+
+fn layout(
+    ty: &IdlType,
+    types: &BTreeMap<&str, &IdlTypeDefinition>,
+    visiting: &mut Vec<String>,
+) -> Value {
+    match ty {
+        IdlType::Option(ty) => json!({ "option": layout(ty, types, visiting) }),
+        IdlType::Vec(ty) => json!({ "vec": layout(ty, types, visiting) }),
+        IdlType::Array(ty, len) => json!({ "array": [layout(ty, types, visiting), len] }),
+        IdlType::GenericLenArray(ty, len) => {
+            json!({ "array": [layout(ty, types, visiting), len] })
+        }
+        IdlType::Defined(name) => defined_layout(name, types, visiting),
+        IdlType::DefinedWithTypeArgs { name, args } => {
+            let args = args
+                .iter()
+                .map(|arg| match arg {
+                    IdlDefinedTypeArg::Type(ty) => layout(ty, types, visiting),
+                    arg => serde_json::to_value(arg).unwrap_or(Value::Null),
+                })
+                .collect::<Vec<_>>();
+            json!({ "defined": defined_layout(name, types, visiting), "args": args })
+        }
+        ty => serde_json::to_value(ty).unwrap_or(Value::Null),
+    }
+}
+
+/// Returns the layout of the defined type `name`. Types that are unknown or already being
+/// expanded, i.e. recursive types, are referred to by name.
+fn defined_layout(
+    name: &str,
+    types: &BTreeMap<&str, &IdlTypeDefinition>,
+    visiting: &mut Vec<String>,
+) -> Value {
+    let def = match types.get(name) {
+        Some(def) if !visiting.iter().any(|visited| visited == name) => def,
+        _ => return json!({ "ref": name }),
+    };
+
+    visiting.push(name.to_owned());
+    let layout = match &def.ty {
+        IdlTypeDefinitionTy::Struct { fields } => {
+            json!({ "struct": fields_layout(fields, types, visiting) })
+        }
+        IdlTypeDefinitionTy::Enum { variants } => {
+            let variants = variants
+                .iter()
+                .map(|variant| {
+                    let fields = match &variant.fields {
+                        Some(EnumFields::Named(fields)) => {
+                            json!(fields_layout(fields, types, visiting))
+                        }
+                        Some(EnumFields::Tuple(tys)) => json!(tys
+                            .iter()
+                            .map(|ty| layout(ty, types, visiting))
+                            .collect::<Vec<_>>()),
+                        None => Value::Null,
+                    };
+                    json!([variant.name, fields])
+                })
+                .collect::<Vec<_>>();
+            json!({ "enum": variants })
+        }
+        IdlTypeDefinitionTy::Alias { value } => layout(value, types, visiting),
+    };
+    visiting.pop();
+    layout
+}
+
+fn fields_layout(
+    fields: &[IdlField],
+    types: &BTreeMap<&str, &IdlTypeDefinition>,
+    visiting: &mut Vec<String>,
+) -> Vec<Value> {
+    fields
+        .iter()
+        .map(|field| json!([field.name, layout(&field.ty, types, visiting)]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idl::types::{IdlEnumVariant, IdlEventField};
+
+    fn field(name: &str, ty: IdlType) -> IdlEventField {
+        IdlEventField {
+            name: name.into(),
+            rust_name: None,
+            docs: None,
+            ty,
+            index: false,
+            index_name: None,
+            optional: None,
+            decimals: None,
+        }
+    }
+
+    fn event(fields: Vec<IdlEventField>) -> IdlEvent {
+        IdlEvent {
+            name: "Swapped".into(),
+            discriminator: None,
+            version: None,
+            correlated: false,
+            via: Default::default(),
+            fields,
+        }
+    }
+
+    fn leg(price: IdlType) -> IdlTypeDefinition {
+        IdlTypeDefinition {
+            name: "Leg".into(),
+            docs: None,
+            generics: None,
+            ty: IdlTypeDefinitionTy::Struct {
+                fields: vec![IdlField {
+                    name: "price".into(),
+                    docs: None,
+                    ty: price,
+                }],
+            },
+        }
+    }
+
+    #[test]
+    fn event_fingerprint_layout() {
+        let base = event(vec![
+            field("amount", IdlType::U64),
+            field(
+                "legs",
+                IdlType::Vec(Box::new(IdlType::Defined("Leg".into()))),
+            ),
+        ]);
+        let types = [leg(IdlType::I64)];
+        let fingerprint = event_fingerprint(&base, &types);
+        assert_eq!(fingerprint.len(), 64);
+
+        // Metadata that doesn't change the wire format
+        let mut documented = base.clone();
+        documented.fields[0].docs = Some(vec!["Amount in lamports".into()]);
+        documented.fields[0].index = true;
+        documented.version = Some(2);
+        assert_eq!(event_fingerprint(&documented, &types), fingerprint);
+
+        let mut renamed = types.clone();
+        renamed[0].name = "Route".into();
+        let mut renamed_event = base.clone();
+        renamed_event.fields[1].ty = IdlType::Vec(Box::new(IdlType::Defined("Route".into())));
+        assert_eq!(event_fingerprint(&renamed_event, &renamed), fingerprint);
+
+        // Changes of the wire format
+        let mut reordered = base.clone();
+        reordered.fields.reverse();
+        let mut retyped = base.clone();
+        retyped.fields[0].ty = IdlType::U32;
+        let mut field_renamed = base.clone();
+        field_renamed.fields[0].name = "amountIn".into();
+        let mut discriminator = base.clone();
+        discriminator.discriminator = Some(vec![1; 8]);
+        let mut correlated = base.clone();
+        correlated.correlated = true;
+        for changed in [reordered, retyped, field_renamed, discriminator, correlated] {
+            assert_ne!(event_fingerprint(&changed, &types), fingerprint);
+        }
+        assert_ne!(event_fingerprint(&base, &[leg(IdlType::U64)]), fingerprint);
+    }
+
+    #[test]
+    fn event_fingerprint_recursive_types() {
+        let node = IdlTypeDefinition {
+            name: "Node".into(),
+            docs: None,
+            generics: None,
+            ty: IdlTypeDefinitionTy::Enum {
+                variants: vec![
+                    IdlEnumVariant {
+                        name: "Leaf".into(),
+                        fields: None,
+                    },
+                    IdlEnumVariant {
+                        name: "Branch".into(),
+                        fields: Some(EnumFields::Tuple(vec![IdlType::Vec(Box::new(
+                            IdlType::Defined("Node".into()),
+                        ))])),
+                    },
+                ],
+            },
+        };
+        let tree = event(vec![field("root", IdlType::Defined("Node".into()))]);
+        assert_eq!(
+            event_fingerprint(&tree, &[node.clone()]),
+            event_fingerprint(&tree, &[node])
+        );
+    }
+
+    #[test]
+    fn breaking_changes_require_version_bump() {
+        let fingerprint = |version: Option<u8>, fingerprint: &str| EventFingerprint {
+            version,
+            fingerprint: fingerprint.into(),
+        };
+        let committed = EventFingerprints::from([
+            ("Swapped".into(), fingerprint(None, "aa")),
+            ("Deposited".into(), fingerprint(Some(1), "bb")),
+            ("Removed".into(), fingerprint(None, "cc")),
+        ]);
+
+        let current = EventFingerprints::from([
+            ("Swapped".into(), fingerprint(None, "aa")),
+            ("Deposited".into(), fingerprint(Some(2), "dd")),
+            ("Added".into(), fingerprint(None, "ee")),
+        ]);
+        assert!(breaking_changes(&committed, &current).is_empty());
+
+        let current = EventFingerprints::from([
+            ("Swapped".into(), fingerprint(None, "ff")),
+            ("Deposited".into(), fingerprint(Some(1), "dd")),
+        ]);
+        assert_eq!(
+            breaking_changes(&committed, &current),
+            [
+                "Event `Deposited` changed its layout without a version bump, \
+                declare it with `#[event(version = 2)]`",
+                "Event `Swapped` changed its layout without a version bump, \
+                declare it with `#[event(version = 1)]`",
+            ]
+        );
+    }
+}
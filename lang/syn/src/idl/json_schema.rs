@@ -0,0 +1,410 @@
+//! JSON schema of the events of an IDL, e.g. to document the events that an API gateway serves
+//! as JSON.
+
+use super::types::{
+    EnumFields, IdlEvent, IdlField, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy,
+};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// URI of the JSON schema dialect of the returned schemas.
+pub const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Returns the JSON schema of `event`, an object with a property per field of the event.
+///
+/// `defined_types` maps the names used by [`IdlType::Defined`] to their definitions, i.e. the
+/// names of the types of an IDL, or the paths of the types collected with `idl-build`. Defined
+/// types that the event refers to, directly or through other defined types, are added to the
+/// `$defs` of the schema under their name without the module path, so nested structs and
+/// enums are described once however often they're used. References to types that aren't in `defined_types` accept any
+/// value.
+///
+/// Types follow their IDL representation:
+///
+/// - Integers of up to 32 bits are `integer`s bounded by the range of the type, and wider
+///   integers are decimal `string`s since JSON numbers can't represent them exactly.
+/// - Public keys are base58 encoded `string`s, and `bytes` are base64 encoded `string`s.
+/// - `Option<T>` is `T` or `null`, vectors are `array`s, and arrays are `array`s of exactly
+///   their length. Generic types and arrays of generic length aren't constrained further.
+/// - Enum variants are objects with a single property named after the variant, whose value
+///   is an empty object for unit variants, an object of the fields for named fields, and an
+///   array of the fields for tuple fields.
+pub fn event_json_schema(
+    event: &IdlEvent,
+    defined_types: &HashMap<String, IdlTypeDefinition>,
+) -> Value {
+    let mut defs = Defs {
+        defined_types,
+        names: HashMap::new(),
+        schemas: BTreeMap::new(),
+    };
+    let fields = event
+        .fields
+        .iter()
+        .map(|field| (field.name.as_str(), field.docs.as_ref(), &field.ty))
+        .collect::<Vec<_>>();
+    let mut schema = defs.object_schema(&fields);
+
+    let object = schema.as_object_mut().unwrap();
+    object.insert("$schema".into(), JSON_SCHEMA_DIALECT.into());
+    object.insert("title".into(), event.name.clone().into());
+    if !defs.schemas.is_empty() {
+        object.insert(
+            "$defs".into(),
+            Value::Object(defs.schemas.into_iter().collect()),
+        );
+    }
+    schema
+}
+
+/// Schemas of the defined types referred to by an event, keyed by their name in `$defs`.
+struct Defs<'a> {
+    defined_types: &'a HashMap<String, IdlTypeDefinition>,
+    /// `$defs` name of the defined types that have been added, by their name in the IDL.
+    names: HashMap<String, String>,
+    schemas: BTreeMap<String, Value>,
+}
+
+impl Defs<'_> {
+    fn object_schema(&mut self, fields: &[(&str, Option<&Vec<String>>, &IdlType)]) -> Value {
+        let mut properties = Map::new();
+        for (name, docs, ty) in fields {
+            let mut schema = self.type_schema(ty);
+            if let Some(docs) = docs {
+                schema
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("description".into(), docs.join("\n").into());
+            }
+            properties.insert(name.to_string(), schema);
+        }
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": fields.iter().map(|(name, ..)| *name).collect::<Vec<_>>(),
+            "additionalProperties": false,
+        })
+    }
+
+    fn struct_schema(&mut self, fields: &[IdlField]) -> Value {
+        let fields = fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.docs.as_ref(), &field.ty))
+            .collect::<Vec<_>>();
+        self.object_schema(&fields)
+    }
+
+    fn type_schema(&mut self, ty: &IdlType) -> Value {
+        match ty {
+            IdlType::Bool => json!({ "type": "boolean" }),
+            IdlType::U8 => integer_schema(u8::MIN.into(), u8::MAX.into()),
+            IdlType::I8 => integer_schema(i8::MIN.into(), i8::MAX.into()),
+            IdlType::U16 => integer_schema(u16::MIN.into(), u16::MAX.into()),
+            IdlType::I16 => integer_schema(i16::MIN.into(), i16::MAX.into()),
+            IdlType::U32 => integer_schema(u32::MIN.into(), u32::MAX.into()),
+            IdlType::I32 => integer_schema(i32::MIN.into(), i32::MAX.into()),
+            IdlType::U64 | IdlType::U128 | IdlType::U256 => {
+                json!({ "type": "string", "pattern": "^[0-9]+$" })
+            }
+            IdlType::I64 | IdlType::I128 | IdlType::I256 => {
+                json!({ "type": "string", "pattern": "^-?[0-9]+$" })
+            }
+            IdlType::F32 | IdlType::F64 => json!({ "type": "number" }),
+            IdlType::Bytes => json!({ "type": "string", "contentEncoding": "base64" }),
+            IdlType::String => json!({ "type": "string" }),
+            IdlType::PublicKey => {
+                json!({ "type": "string", "pattern": "^[1-9A-HJ-NP-Za-km-z]{32,44}$" })
+            }
+            IdlType::Option(ty) => json!({ "anyOf": [self.type_schema(ty), { "type": "null" }] }),
+            IdlType::Vec(ty) => json!({ "type": "array", "items": self.type_schema(ty) }),
+            IdlType::Array(ty, len) => json!({
+                "type": "array",
+                "items": self.type_schema(ty),
+                "minItems": len,
+                "maxItems": len,
+            }),
+            IdlType::GenericLenArray(ty, _) => {
+                json!({ "type": "array", "items": self.type_schema(ty) })
+            }
+            IdlType::Generic(_) => json!({}),
+            IdlType::Defined(name) | IdlType::DefinedWithTypeArgs { name, .. } => {
+                match self.def_name(name) {
+                    Some(def_name) => json!({ "$ref": format!("#/$defs/{def_name}") }),
+                    None => json!({}),
+                }
+            }
+        }
+    }
+
+    /// Adds the schema of the defined type to the `$defs` if it's not there yet, and returns
+    /// its name in the `$defs`.
+    fn def_name(&mut self, name: &str) -> Option<String> {
+        if let Some(def_name) = self.names.get(name) {
+            return Some(def_name.clone());
+        }
+        let ty_def = self.defined_types.get(name)?;
+
+        // Types collected with `idl-build` are named by their path. Types of different modules
+        // can share a name, which is only used for the first one.
+        let short_name = ty_def.name.rsplit("::").next().unwrap_or(&ty_def.name);
+        let def_name = if self.schemas.contains_key(short_name) {
+            name.to_owned()
+        } else {
+            short_name.to_owned()
+        };
+        self.names.insert(name.to_owned(), def_name.clone());
+        // Reserve the name so that recursive types refer to it
+        self.schemas.insert(def_name.clone(), Value::Null);
+
+        let mut schema = match &ty_def.ty {
+            IdlTypeDefinitionTy::Struct { fields } => self.struct_schema(fields),
+            IdlTypeDefinitionTy::Enum { variants } => {
+                let variants = variants
+                    .iter()
+                    .map(|variant| {
+                        let fields = match &variant.fields {
+                            None => self.struct_schema(&[]),
+                            Some(EnumFields::Named(fields)) => self.struct_schema(fields),
+                            Some(EnumFields::Tuple(tys)) => {
+                                let items = tys
+                                    .iter()
+                                    .map(|ty| self.type_schema(ty))
+                                    .collect::<Vec<_>>();
+                                json!({
+                                    "type": "array",
+                                    "prefixItems": items,
+                                    "minItems": tys.len(),
+                                    "maxItems": tys.len(),
+                                })
+                            }
+                        };
+                        variant_schema(&variant.name, fields)
+                    })
+                    .collect::<Vec<_>>();
+                json!({ "oneOf": variants })
+            }
+            IdlTypeDefinitionTy::Alias { value } => self.type_schema(value),
+        };
+        if let Some(docs) = &ty_def.docs {
+            if let Some(object) = schema.as_object_mut() {
+                object.insert("description".into(), docs.join("\n").into());
+            }
+        }
+        self.schemas.insert(def_name.clone(), schema);
+        Some(def_name)
+    }
+}
+
+/// Returns the schema of an enum variant, an object with a single property named after the
+/// variant.
+fn variant_schema(name: &str, fields: Value) -> Value {
+    json!({
+        "type": "object",
+        "properties": { name: fields },
+        "required": [name],
+        "additionalProperties": false,
+    })
+}
+
+fn integer_schema(min: i64, max: i64) -> Value {
+    json!({ "type": "integer", "minimum": min, "maximum": max })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idl::types::{IdlEnumVariant, IdlEventField};
+
+    fn field(name: &str, ty: IdlType) -> IdlEventField {
+        IdlEventField {
+            name: name.into(),
+            rust_name: None,
+            docs: None,
+            ty,
+            index: false,
+            index_name: None,
+            optional: None,
+            decimals: None,
+        }
+    }
+
+    fn idl_field(name: &str, ty: IdlType) -> IdlField {
+        IdlField {
+            name: name.into(),
+            docs: None,
+            ty,
+        }
+    }
+
+    fn type_def(name: &str, ty: IdlTypeDefinitionTy) -> IdlTypeDefinition {
+        IdlTypeDefinition {
+            name: name.into(),
+            docs: None,
+            generics: None,
+            ty,
+        }
+    }
+
+    #[test]
+    fn event_json_schema() {
+        let mut amount = field("amount", IdlType::U64);
+        amount.docs = Some(vec!["Amount in lamports".into()]);
+        let event = IdlEvent {
+            name: "Swapped".into(),
+            discriminator: None,
+            version: None,
+            correlated: false,
+            via: Default::default(),
+            fields: vec![
+                field("authority", IdlType::PublicKey),
+                amount,
+                field("bump", IdlType::U8),
+                field("memo", IdlType::Option(Box::new(IdlType::String))),
+                field(
+                    "legs",
+                    IdlType::Array(Box::new(IdlType::Defined("state::Leg".into())), 2),
+                ),
+            ],
+        };
+        let defined_types = HashMap::from([
+            (
+                "state::Leg".to_owned(),
+                type_def(
+                    "Leg",
+                    IdlTypeDefinitionTy::Struct {
+                        fields: vec![
+                            idl_field("side", IdlType::Defined("state::Side".into())),
+                            idl_field("price", IdlType::I64),
+                        ],
+                    },
+                ),
+            ),
+            (
+                "state::Side".to_owned(),
+                type_def(
+                    "Side",
+                    IdlTypeDefinitionTy::Enum {
+                        variants: vec![
+                            IdlEnumVariant {
+                                name: "Bid".into(),
+                                fields: None,
+                            },
+                            IdlEnumVariant {
+                                name: "Ask".into(),
+                                fields: Some(EnumFields::Tuple(vec![IdlType::Bool])),
+                            },
+                        ],
+                    },
+                ),
+            ),
+        ]);
+
+        let empty = json!({
+            "type": "object",
+            "properties": {},
+            "required": [],
+            "additionalProperties": false,
+        });
+        assert_eq!(
+            super::event_json_schema(&event, &defined_types),
+            json!({
+                "$schema": JSON_SCHEMA_DIALECT,
+                "title": "Swapped",
+                "type": "object",
+                "properties": {
+                    "authority": {
+                        "type": "string",
+                        "pattern": "^[1-9A-HJ-NP-Za-km-z]{32,44}$",
+                    },
+                    "amount": {
+                        "type": "string",
+                        "pattern": "^[0-9]+$",
+                        "description": "Amount in lamports",
+                    },
+                    "bump": { "type": "integer", "minimum": 0, "maximum": 255 },
+                    "memo": { "anyOf": [{ "type": "string" }, { "type": "null" }] },
+                    "legs": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/Leg" },
+                        "minItems": 2,
+                        "maxItems": 2,
+                    },
+                },
+                "required": ["authority", "amount", "bump", "memo", "legs"],
+                "additionalProperties": false,
+                "$defs": {
+                    "Leg": {
+                        "type": "object",
+                        "properties": {
+                            "side": { "$ref": "#/$defs/Side" },
+                            "price": { "type": "string", "pattern": "^-?[0-9]+$" },
+                        },
+                        "required": ["side", "price"],
+                        "additionalProperties": false,
+                    },
+                    "Side": {
+                        "oneOf": [
+                            {
+                                "type": "object",
+                                "properties": { "Bid": empty },
+                                "required": ["Bid"],
+                                "additionalProperties": false,
+                            },
+                            {
+                                "type": "object",
+                                "properties": {
+                                    "Ask": {
+                                        "type": "array",
+                                        "prefixItems": [{ "type": "boolean" }],
+                                        "minItems": 1,
+                                        "maxItems": 1,
+                                    },
+                                },
+                                "required": ["Ask"],
+                                "additionalProperties": false,
+                            },
+                        ],
+                    },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn event_json_schema_recursive_and_unknown_types() {
+        let event = IdlEvent {
+            name: "Tree".into(),
+            discriminator: None,
+            version: None,
+            correlated: false,
+            via: Default::default(),
+            fields: vec![
+                field("root", IdlType::Defined("Node".into())),
+                field("extra", IdlType::Defined("Unknown".into())),
+            ],
+        };
+        let defined_types = HashMap::from([(
+            "Node".to_owned(),
+            type_def(
+                "Node",
+                IdlTypeDefinitionTy::Struct {
+                    fields: vec![idl_field(
+                        "children",
+                        IdlType::Vec(Box::new(IdlType::Defined("Node".into()))),
+                    )],
+                },
+            ),
+        )]);
+
+        let schema = super::event_json_schema(&event, &defined_types);
+        assert_eq!(
+            schema["properties"]["root"],
+            json!({ "$ref": "#/$defs/Node" })
+        );
+        assert_eq!(schema["properties"]["extra"], json!({}));
+        assert_eq!(
+            schema["$defs"]["Node"]["properties"]["children"]["items"],
+            json!({ "$ref": "#/$defs/Node" })
+        );
+    }
+}
@@ -1,3 +1,6 @@
+pub mod fingerprint;
+pub mod json_schema;
+pub mod sql;
 pub mod types;
 
 #[cfg(feature = "idl-build")]
@@ -2,7 +2,7 @@ use crate::idl::types::*;
 use crate::parser::context::CrateContext;
 use crate::parser::{self, accounts, docs, error, program};
 use crate::Ty;
-use crate::{AccountField, AccountsStruct};
+use crate::{AccountField, AccountsStruct, EventArgs};
 use anyhow::anyhow;
 use anyhow::Result;
 use heck::MixedCase;
@@ -26,6 +26,7 @@ pub fn parse(
     path: impl AsRef<Path>,
     version: String,
     seeds_feature: bool,
+    event_discriminators_feature: bool,
     no_docs: bool,
     safety_checks: bool,
 ) -> Result<Idl> {
@@ -96,35 +97,14 @@ pub fn parse(
         })
         .collect::<Vec<_>>();
 
-    let events = parse_events(&ctx)
+    let event_structs = parse_events(&ctx);
+    let event_args = parse_event_args(&event_structs)?;
+    let events = event_structs
         .iter()
-        .map(|e: &&syn::ItemStruct| {
-            let fields = match &e.fields {
-                syn::Fields::Named(n) => n,
-                _ => panic!("Event fields must be named"),
-            };
-            let fields = fields
-                .named
-                .iter()
-                .map(|f: &syn::Field| {
-                    let index = match f.attrs.first() {
-                        None => false,
-                        Some(i) => parser::tts_to_string(&i.path) == "index",
-                    };
-                    IdlEventField {
-                        name: f.ident.clone().unwrap().to_string().to_mixed_case(),
-                        ty: to_idl_type(&ctx, &f.ty),
-                        index,
-                    }
-                })
-                .collect::<Vec<IdlEventField>>();
-
-            IdlEvent {
-                name: e.ident.to_string(),
-                fields,
-            }
-        })
-        .collect::<Vec<IdlEvent>>();
+        .zip(&event_args)
+        .filter(|(_, args)| !args.hidden)
+        .map(|(e, args)| idl_event(&ctx, e, args, no_docs))
+        .collect::<Result<Vec<IdlEvent>>>()?;
 
     // All user defined types.
     let mut accounts = vec![];
@@ -145,16 +125,32 @@ pub fn parse(
         if ty_def.name != error_name {
             if account_names.contains(&ty_def.name) {
                 accounts.push(ty_def);
-            } else if !events.iter().any(|e| e.name == ty_def.name) {
+            } else if !event_structs.iter().any(|e| e.ident == ty_def.name) {
                 types.push(ty_def);
             }
         }
     }
 
-    let constants = parse_consts(&ctx)
+    let mut constants = parse_consts(&ctx)
         .iter()
         .map(|c: &&syn::ItemConst| to_idl_const(c))
         .collect::<Vec<IdlConst>>();
+    if event_discriminators_feature {
+        constants.extend(
+            event_structs
+                .iter()
+                .zip(&event_args)
+                .filter(|(_, args)| !args.hidden)
+                .map(|(e, args)| {
+                    let discriminator = args.discriminator(&e.ident);
+                    IdlConst {
+                        name: args.idl_discriminator_const_name(&e.ident),
+                        ty: IdlType::Array(Box::new(IdlType::U8), discriminator.len()),
+                        value: format!("{discriminator:?}"),
+                    }
+                }),
+        );
+    }
 
     Ok(Idl {
         version,
@@ -242,6 +238,103 @@ fn parse_events(ctx: &CrateContext) -> Vec<&syn::ItemStruct> {
         .collect()
 }
 
+/// Returns the IDL events of the `#[event]` structs among `structs`, e.g. for tooling that
+/// assembles partial IDLs without parsing a whole program with [`parse`].
+///
+/// The other structs are only used to resolve the fields of `#[event_flatten]` fields. Since
+/// there is no crate to look up constants in, array lengths must be literals.
+pub fn parse_idl_events(structs: &[syn::ItemStruct], no_docs: bool) -> Result<Vec<IdlEvent>> {
+    let ctx = CrateContext::from_items(structs.iter().cloned().map(syn::Item::Struct).collect());
+    let event_structs = parse_events(&ctx);
+    let event_args = parse_event_args(&event_structs)?;
+    event_structs
+        .iter()
+        .zip(&event_args)
+        .filter(|(_, args)| !args.hidden)
+        .map(|(e, args)| idl_event(&ctx, e, args, no_docs))
+        .collect()
+}
+
+/// Same as [`parse_idl_events`], but returns the events as the JSON array of the `events`
+/// field of an IDL, i.e. with their discriminator, fields, docs and index metadata.
+pub fn parse_idl_events_json(
+    structs: &[syn::ItemStruct],
+    no_docs: bool,
+) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(parse_idl_events(structs, no_docs)?)?)
+}
+
+fn parse_event_args(event_structs: &[&syn::ItemStruct]) -> Result<Vec<EventArgs>> {
+    let args = event_structs
+        .iter()
+        .map(|e| {
+            let attr = e
+                .attrs
+                .iter()
+                .find(|attr| attr.path.segments.last().unwrap().ident == "event")
+                .unwrap();
+            parser::event::parse_attr(attr)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    Ok(args)
+}
+
+fn idl_event(
+    ctx: &CrateContext,
+    e: &syn::ItemStruct,
+    args: &EventArgs,
+    no_docs: bool,
+) -> Result<IdlEvent> {
+    let mut e = e.clone();
+    if args.sequence {
+        parser::event::add_sequence_field(&mut e)?;
+    }
+    if args.timestamp {
+        parser::event::add_timestamp_field(&mut e)?;
+    }
+    if let syn::Fields::Unit = e.fields {
+        panic!("Event must have fields");
+    }
+    let fields = e
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            if parser::event::parse_flatten(f)? {
+                return parse_flattened_event_fields(ctx, f, i, no_docs);
+            }
+
+            let index = parser::event::parse_index(f)?;
+            let (name, rust_name) = parser::event::idl_field_name(f, i);
+            let ty = to_idl_type(ctx, &f.ty);
+            Ok(vec![IdlEventField {
+                rust_name,
+                name,
+                docs: if !no_docs {
+                    docs::parse(&f.attrs)
+                } else {
+                    None
+                },
+                optional: IdlEventField::optional_marker(index.is_some(), &ty),
+                ty,
+                index: index.is_some(),
+                index_name: index.and_then(|index| index.name),
+                decimals: parser::event::parse_decimals(f)?,
+            }])
+        })
+        .collect::<Result<Vec<Vec<IdlEventField>>>>()?
+        .concat();
+
+    Ok(IdlEvent {
+        name: args.idl_name(&e.ident),
+        discriminator: args.idl_discriminator(&e.ident),
+        version: args.version,
+        correlated: args.correlated,
+        fields,
+        via: args.via.into(),
+    })
+}
+
 fn parse_accounts(ctx: &CrateContext) -> Vec<&syn::ItemStruct> {
     ctx.structs()
         .filter(|item_strct| {
@@ -262,6 +355,53 @@ fn parse_accounts(ctx: &CrateContext) -> Vec<&syn::ItemStruct> {
         .collect()
 }
 
+// Parse the fields of the struct type of an `#[event_flatten]` event field.
+//
+// The fields don't have a `rust_name` since they aren't fields of the event in Rust.
+fn parse_flattened_event_fields(
+    ctx: &CrateContext,
+    field: &syn::Field,
+    field_index: usize,
+    no_docs: bool,
+) -> Result<Vec<IdlEventField>> {
+    let field_name = parser::event::field_name(field, field_index);
+    let strct = match &field.ty {
+        syn::Type::Path(ty) => ty
+            .path
+            .segments
+            .last()
+            .and_then(|segment| ctx.structs().find(|strct| strct.ident == segment.ident)),
+        _ => None,
+    };
+    let fields = match strct.map(|strct| &strct.fields) {
+        Some(syn::Fields::Named(fields)) => fields,
+        _ => {
+            return Err(anyhow!(
+                "`#[event_flatten]` field `{field_name}` must be a struct with named fields"
+            ))
+        }
+    };
+
+    Ok(fields
+        .named
+        .iter()
+        .map(|f| IdlEventField {
+            name: f.ident.as_ref().unwrap().to_string().to_mixed_case(),
+            rust_name: None,
+            docs: if !no_docs {
+                docs::parse(&f.attrs)
+            } else {
+                None
+            },
+            ty: to_idl_type(ctx, &f.ty),
+            index: false,
+            index_name: None,
+            optional: None,
+            decimals: None,
+        })
+        .collect())
+}
+
 // Parse all structs implementing the `Accounts` trait.
 fn parse_account_derives(ctx: &CrateContext) -> HashMap<String, AccountsStruct> {
     // TODO: parse manual implementations. Currently we only look
@@ -588,3 +728,171 @@ fn idl_accounts(
         })
         .collect::<Vec<_>>()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idl_events_of_structs() {
+        let structs: Vec<syn::ItemStruct> = vec![
+            syn::parse_quote! {
+                #[event(discriminator = [1, 2, 3, 4, 5, 6, 7, 8])]
+                pub struct Deposit {
+                    #[event_flatten]
+                    pub header: Header,
+                    /// Deposited amount.
+                    #[index(name = "depositor")]
+                    pub user: Pubkey,
+                    pub amounts: [u64; 2],
+                }
+            },
+            syn::parse_quote! {
+                pub struct Header {
+                    pub slot: u64,
+                }
+            },
+        ];
+
+        let events = parse_idl_events_json(&structs, false).unwrap();
+        assert_eq!(
+            events,
+            serde_json::json!([{
+                "name": "Deposit",
+                "discriminator": [1, 2, 3, 4, 5, 6, 7, 8],
+                "fields": [
+                    { "name": "slot", "type": "u64", "index": false },
+                    {
+                        "name": "user",
+                        "docs": ["Deposited amount."],
+                        "type": "publicKey",
+                        "index": true,
+                        "indexName": "depositor"
+                    },
+                    { "name": "amounts", "type": { "array": ["u64", 2] }, "index": false }
+                ]
+            }])
+        );
+
+        let events = parse_idl_events(&structs, true).unwrap();
+        assert_eq!(events[0].fields[1].docs, None);
+    }
+
+    #[test]
+    fn idl_event_via() {
+        let structs: Vec<syn::ItemStruct> = vec![
+            syn::parse_quote! {
+                #[event(via = "cpi")]
+                pub struct Large {
+                    pub data: [u8; 32],
+                }
+            },
+            syn::parse_quote! {
+                #[event(via = "log")]
+                pub struct Small {
+                    pub data: u8,
+                }
+            },
+        ];
+
+        let events = parse_idl_events_json(&structs, false).unwrap();
+        assert_eq!(events[0]["via"], "cpi");
+        // Logged events keep the IDL of events without `via`
+        assert!(events[1].get("via").is_none());
+
+        let events = parse_idl_events(&structs, false).unwrap();
+        assert_eq!(events[0].via, IdlEventVia::Cpi);
+        assert_eq!(events[1].via, IdlEventVia::Log);
+    }
+
+    #[test]
+    fn idl_event_correlated() {
+        let structs: Vec<syn::ItemStruct> = vec![
+            syn::parse_quote! {
+                #[event(correlated, version = 1)]
+                pub struct SwapStarted {
+                    pub amount: u64,
+                }
+            },
+            syn::parse_quote! {
+                #[event]
+                pub struct SwapCompleted {
+                    pub amount: u64,
+                }
+            },
+        ];
+
+        let events = parse_idl_events_json(&structs, false).unwrap();
+        assert_eq!(events[0]["correlated"], true);
+        assert_eq!(events[0]["version"], 1);
+        // Events without a correlation id slot keep their IDL
+        assert!(events[1].get("correlated").is_none());
+
+        let events = parse_idl_events(&structs, false).unwrap();
+        assert!(events[0].correlated);
+        assert!(!events[1].correlated);
+    }
+
+    #[test]
+    fn idl_event_fields_in_declaration_order() {
+        let structs: Vec<syn::ItemStruct> = vec![syn::parse_quote! {
+            #[event]
+            pub struct Interleaved {
+                pub first: u64,
+                #[index]
+                pub second: Pubkey,
+                pub third: u8,
+                #[index(name = "fourth_key")]
+                #[decimals(6)]
+                pub fourth: u32,
+                #[index]
+                pub fifth: bool,
+                pub sixth: String,
+            }
+        }];
+
+        let events = parse_idl_events(&structs, true).unwrap();
+        let fields = events[0]
+            .fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.index))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            [
+                ("first", false),
+                ("second", true),
+                ("third", false),
+                ("fourth", true),
+                ("fifth", true),
+                ("sixth", false),
+            ]
+        );
+        assert_eq!(events[0].fields[3].decimals, Some(6));
+        assert_eq!(events[0].fields[0].decimals, None);
+    }
+
+    #[test]
+    fn idl_events_without_hidden() {
+        let structs: Vec<syn::ItemStruct> = vec![
+            syn::parse_quote! {
+                #[event]
+                pub struct Public {
+                    pub data: u64,
+                }
+            },
+            syn::parse_quote! {
+                #[event(hidden)]
+                pub struct Debug {
+                    pub data: u64,
+                }
+            },
+        ];
+
+        let events = parse_idl_events(&structs, true).unwrap();
+        assert_eq!(
+            events.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(),
+            ["Public"]
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! SQL schema of the events of an IDL for indexers, e.g. Postgres databases that store the
+//! events of a program.
+
+use super::types::{Idl, IdlEvent, IdlEventField, IdlType};
+use heck::SnakeCase;
+
+/// Name of the column that the fields of an event that aren't indexed are stored in as JSON.
+pub const FIELDS_COLUMN: &str = "fields";
+
+/// Returns the `CREATE TABLE` statements of the events of `idl`, see [`event_sql`], separated
+/// by an empty line. Returns an empty string if the IDL has no events.
+pub fn events_sql(idl: &Idl) -> String {
+    idl.events
+        .iter()
+        .flatten()
+        .map(event_sql)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns a Postgres `CREATE TABLE` statement for `event`, followed by a `CREATE INDEX`
+/// statement per indexed field.
+///
+/// The table is named after the event in snake_case. Every field marked with `#[index]` gets a
+/// column, named after its [index key](IdlEventField::index_key) in snake_case, whose type is
+/// derived from the type of the field. Indexed fields of type `Option<T>` are nullable, and
+/// types without an SQL equivalent, e.g. vectors and defined types, are stored as `JSONB`. The
+/// fields that aren't indexed are stored together as a JSON object in the [`FIELDS_COLUMN`].
+pub fn event_sql(event: &IdlEvent) -> String {
+    let table = event.name.to_snake_case();
+    let indexed = event
+        .fields
+        .iter()
+        .filter_map(|field| field.index_key().map(|key| (key.to_snake_case(), field)))
+        .collect::<Vec<_>>();
+
+    let mut columns = indexed
+        .iter()
+        .map(|(column, field)| format!("    {} {}", quote_ident(column), column_type(field)))
+        .collect::<Vec<_>>();
+    if event.fields.iter().any(|field| !field.index) {
+        columns.push(format!("    {} JSONB NOT NULL", quote_ident(FIELDS_COLUMN)));
+    }
+
+    let mut sql = format!(
+        "CREATE TABLE IF NOT EXISTS {} (\n{}\n);\n",
+        quote_ident(&table),
+        columns.join(",\n")
+    );
+    for (column, _) in &indexed {
+        sql.push_str(&format!(
+            "CREATE INDEX IF NOT EXISTS {} ON {} ({});\n",
+            quote_ident(&format!("{table}_{column}_idx")),
+            quote_ident(&table),
+            quote_ident(column)
+        ));
+    }
+    sql
+}
+
+fn column_type(field: &IdlEventField) -> String {
+    match &field.ty {
+        IdlType::Option(ty) => sql_type(ty).to_owned(),
+        ty => format!("{} NOT NULL", sql_type(ty)),
+    }
+}
+
+fn sql_type(ty: &IdlType) -> &'static str {
+    match ty {
+        IdlType::Bool => "BOOLEAN",
+        IdlType::U8 | IdlType::I8 | IdlType::I16 => "SMALLINT",
+        IdlType::U16 | IdlType::I32 => "INTEGER",
+        IdlType::U32 | IdlType::I64 => "BIGINT",
+        IdlType::U64 => "NUMERIC(20, 0)",
+        IdlType::U128 | IdlType::I128 => "NUMERIC(39, 0)",
+        IdlType::U256 | IdlType::I256 => "NUMERIC(78, 0)",
+        IdlType::F32 => "REAL",
+        IdlType::F64 => "DOUBLE PRECISION",
+        IdlType::Bytes => "BYTEA",
+        // Public keys are stored base58 encoded
+        IdlType::String | IdlType::PublicKey => "TEXT",
+        IdlType::Option(_)
+        | IdlType::Vec(_)
+        | IdlType::Array(..)
+        | IdlType::GenericLenArray(..)
+        | IdlType::Defined(_)
+        | IdlType::Generic(_)
+        | IdlType::DefinedWithTypeArgs { .. } => "JSONB",
+    }
+}
+
+fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, ty: IdlType, index: bool) -> IdlEventField {
+        IdlEventField {
+            name: name.into(),
+            rust_name: None,
+            docs: None,
+            optional: IdlEventField::optional_marker(index, &ty),
+            ty,
+            index,
+            index_name: None,
+            decimals: None,
+        }
+    }
+
+    #[test]
+    fn event_sql() {
+        let mut owner = field("authority", IdlType::PublicKey, true);
+        owner.index_name = Some("owner".into());
+        let event = IdlEvent {
+            name: "TokenSwapped".into(),
+            discriminator: None,
+            version: None,
+            correlated: false,
+            via: Default::default(),
+            fields: vec![
+                owner,
+                field("amountIn", IdlType::U64, true),
+                field("referrer", IdlType::Option(Box::new(IdlType::String)), true),
+                field("route", IdlType::Vec(Box::new(IdlType::PublicKey)), false),
+            ],
+        };
+
+        assert_eq!(
+            super::event_sql(&event),
+            r#"CREATE TABLE IF NOT EXISTS "token_swapped" (
+    "owner" TEXT NOT NULL,
+    "amount_in" NUMERIC(20, 0) NOT NULL,
+    "referrer" TEXT,
+    "fields" JSONB NOT NULL
+);
+CREATE INDEX IF NOT EXISTS "token_swapped_owner_idx" ON "token_swapped" ("owner");
+CREATE INDEX IF NOT EXISTS "token_swapped_amount_in_idx" ON "token_swapped" ("amount_in");
+CREATE INDEX IF NOT EXISTS "token_swapped_referrer_idx" ON "token_swapped" ("referrer");
+"#
+        );
+    }
+
+    #[test]
+    fn event_sql_without_indexed_fields() {
+        let event = IdlEvent {
+            name: "Ping".into(),
+            discriminator: None,
+            version: None,
+            correlated: false,
+            via: Default::default(),
+            fields: vec![field("data", IdlType::U8, false)],
+        };
+
+        assert_eq!(
+            super::event_sql(&event),
+            "CREATE TABLE IF NOT EXISTS \"ping\" (\n    \"fields\" JSONB NOT NULL\n);\n"
+        );
+    }
+}
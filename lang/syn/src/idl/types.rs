@@ -133,15 +133,100 @@ pub struct IdlField {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct IdlEvent {
     pub name: String,
+    /// Discriminator of the event, only set if it isn't derived from the event name.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub discriminator: Option<Vec<u8>>,
+    /// Schema version serialized after the discriminator, only set for events declared with
+    /// `#[event(version = <N>)]`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub version: Option<u8>,
+    /// Whether an `Option<[u8; 16]>` correlation id slot follows the discriminator, only set for
+    /// events declared with `#[event(correlated)]`.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub correlated: bool,
+    /// Fields in declaration order, whether they are indexed or not, so that clients can
+    /// decode the event positionally.
     pub fields: Vec<IdlEventField>,
+    /// How the event is emitted, only set if it isn't logged.
+    #[serde(skip_serializing_if = "IdlEventVia::is_log", default)]
+    pub via: IdlEventVia,
+}
+
+/// How an event is emitted, i.e. where clients find it in transactions.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IdlEventVia {
+    /// `Program data:` logs of `emit!`.
+    Log,
+    /// Self-CPI inner instructions of `emit_cpi!`.
+    Cpi,
+}
+
+impl IdlEventVia {
+    pub fn is_log(&self) -> bool {
+        *self == Self::Log
+    }
+}
+
+impl Default for IdlEventVia {
+    fn default() -> Self {
+        Self::Log
+    }
+}
+
+impl From<crate::EventVia> for IdlEventVia {
+    fn from(via: crate::EventVia) -> Self {
+        match via {
+            crate::EventVia::Log => Self::Log,
+            crate::EventVia::Cpi => Self::Cpi,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
 pub struct IdlEventField {
+    /// camelCase name of the field, like the names of instruction arguments and type fields.
     pub name: String,
+    /// Name of the field in Rust, only set if it differs from `name`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub rust_name: Option<String>,
+    /// Doc comments of the field, one entry per line.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub docs: Option<Vec<String>>,
     #[serde(rename = "type")]
     pub ty: IdlType,
     pub index: bool,
+    /// Name given with `#[index(name = "..")]`, see [`IdlEventField::index_key`].
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub index_name: Option<String>,
+    /// Set for indexed fields of type `Option<T>`, so that indexers know the field can be null.
+    /// The inner type is the type of the `option`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub optional: Option<bool>,
+    /// Number of implied decimals given with `#[decimals(<N>)]`, so that clients can display
+    /// e.g. a `u64` amount of `1_500_000` with 6 decimals as `1.5`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub decimals: Option<u8>,
+}
+
+impl IdlEventField {
+    /// Returns the key indexers should use for this field, which is the index name if one was
+    /// given and the camelCase field name otherwise. The index name is used as is, i.e. it's not
+    /// converted to camelCase. Returns `None` if the field is not indexed.
+    pub fn index_key(&self) -> Option<&str> {
+        self.index
+            .then(|| self.index_name.as_deref().unwrap_or(&self.name))
+    }
+
+    /// Returns the `optional` marker of a field, which is only set for indexed `Option` fields.
+    pub fn optional_marker(index: bool, ty: &IdlType) -> Option<bool> {
+        if index && matches!(ty, IdlType::Option(_)) {
+            Some(true)
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
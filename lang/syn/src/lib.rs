@@ -38,6 +38,20 @@ pub struct Program {
     pub docs: Option<Vec<String>>,
     pub program_mod: ItemMod,
     pub fallback_fn: Option<FallbackFn>,
+    pub args: ProgramArgs,
+}
+
+/// Arguments of the `#[program]` attribute.
+#[derive(Debug, Default)]
+pub struct ProgramArgs {
+    /// Maximum number of events an instruction can emit with `emit!` and `emit_cpi!`.
+    pub max_events: Option<u32>,
+}
+
+impl Parse for ProgramArgs {
+    fn parse(stream: ParseStream) -> ParseResult<Self> {
+        program_parser::parse_args(stream)
+    }
 }
 
 impl Parse for Program {
@@ -251,6 +265,9 @@ impl Field {
             Ty::UncheckedAccount => quote! {
                 UncheckedAccount
             },
+            Ty::RingBuffer => quote! {
+                RingBuffer
+            },
             Ty::Signer => quote! {
                 Signer
             },
@@ -404,6 +421,7 @@ impl Field {
             Ty::InterfaceAccount(_) => {
                 quote! { anchor_lang::accounts::interface_account::InterfaceAccount }
             }
+            Ty::RingBuffer => quote! { anchor_lang::accounts::ring_buffer::RingBuffer },
             Ty::AccountInfo => quote! {},
             Ty::UncheckedAccount => quote! {},
             Ty::Signer => quote! {},
@@ -421,6 +439,9 @@ impl Field {
             Ty::UncheckedAccount => quote! {
                 UncheckedAccount
             },
+            Ty::RingBuffer => quote! {
+                RingBuffer
+            },
             Ty::Signer => quote! {
                 Signer
             },
@@ -497,6 +518,7 @@ pub enum Ty {
     Program(ProgramTy),
     Interface(InterfaceTy),
     InterfaceAccount(InterfaceAccountTy),
+    RingBuffer,
     Signer,
     SystemAccount,
     ProgramData,
@@ -578,6 +600,110 @@ impl Parse for ErrorArgs {
     }
 }
 
+#[derive(Debug)]
+pub struct EventArgs {
+    /// Length of the event discriminator in bytes, defaults to 8.
+    pub discriminator_len: usize,
+    /// Custom discriminator that overrides the hashed preimage.
+    pub discriminator: Option<Vec<u8>>,
+    /// Schema version that is serialized right after the discriminator.
+    pub version: Option<u8>,
+    /// Name of the event in the IDL, defaults to the name of the type.
+    pub idl_name: Option<String>,
+    /// Whether the event is serialized by casting it to bytes instead of with borsh.
+    pub zero_copy: bool,
+    /// Whether to implement `arbitrary::Arbitrary` for the event.
+    pub arbitrary: bool,
+    /// Maximum estimated size of the serialized event in bytes, exceeding it is an error.
+    pub max_size: Option<usize>,
+    /// Whether a `sequence: u64` field is added as the first field of the event.
+    pub sequence: bool,
+    /// Namespace that prefixes the hashed preimage, i.e. `<namespace>:event:<Name>`.
+    pub namespace: Option<String>,
+    /// Additional standard derives of the event, i.e. `derive(Clone, PartialEq, Debug)`.
+    pub derives: Vec<Ident>,
+    /// Whether the `AnchorSerialize` and `AnchorDeserialize` derives are skipped, i.e. the
+    /// serialization traits are implemented by the user.
+    pub no_serde: bool,
+    /// Whether to generate a `DISCRIMINATOR_HEX` constant with the discriminator as hex.
+    pub discriminator_hex: bool,
+    /// Whether the event is excluded from the IDL.
+    pub hidden: bool,
+    /// Whether an `emitted_at: i64` field is added as the last field of the event.
+    pub timestamp: bool,
+    /// How the event is emitted, which is stored in the IDL for clients.
+    pub via: EventVia,
+    /// Feature of the crate declaring the event without which the event is compiled out.
+    pub feature: Option<String>,
+    /// Whether an `Option<[u8; 16]>` correlation id slot follows the discriminator.
+    pub correlated: bool,
+}
+
+/// How an event is emitted, given with `#[event(via = "..")]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventVia {
+    /// Logged with `emit!`, i.e. `Program data:` logs.
+    Log,
+    /// Emitted with `emit_cpi!`, i.e. the data of self-CPI inner instructions.
+    Cpi,
+}
+
+impl EventVia {
+    /// Values of the `via` argument in the order of the variants.
+    pub const VALUES: &'static [&'static str] = &["log", "cpi"];
+
+    /// Returns the value of the `via` argument.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Log => "log",
+            Self::Cpi => "cpi",
+        }
+    }
+}
+
+impl Default for EventVia {
+    fn default() -> Self {
+        Self::Log
+    }
+}
+
+impl Default for EventArgs {
+    fn default() -> Self {
+        Self {
+            discriminator_len: 8,
+            discriminator: None,
+            version: None,
+            idl_name: None,
+            zero_copy: false,
+            arbitrary: false,
+            max_size: None,
+            sequence: false,
+            namespace: None,
+            derives: vec![],
+            no_serde: false,
+            discriminator_hex: false,
+            hidden: false,
+            timestamp: false,
+            via: EventVia::default(),
+            feature: None,
+            correlated: false,
+        }
+    }
+}
+
+impl Parse for EventArgs {
+    fn parse(stream: ParseStream) -> ParseResult<Self> {
+        parser::event::parse_args(stream)
+    }
+}
+
+/// Metadata of an event field marked with `#[index]`.
+#[derive(Debug, Default)]
+pub struct EventIndex {
+    /// Name given with `#[index(name = "..")]` that indexers use for the field.
+    pub name: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct ErrorCode {
     pub id: u32,
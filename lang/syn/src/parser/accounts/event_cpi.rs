@@ -1,4 +1,9 @@
 use quote::quote;
+use syn::parse::{Error as ParseError, Parse, ParseStream, Result as ParseResult};
+use syn::{Ident, LitByteStr, LitStr, Token};
+
+/// Maximum length of a PDA seed.
+const MAX_SEED_LEN: usize = 32;
 
 /// This struct is used to keep the authority account information in sync.
 pub struct EventAuthority {
@@ -17,6 +22,19 @@ impl EventAuthority {
         }
     }
 
+    /// Returns the seeds expression of the event authority with the given seed suffix, see
+    /// [`EventCpiArgs::seed`].
+    pub fn seeds_with_suffix(&self, suffix: Option<&LitStr>) -> proc_macro2::TokenStream {
+        let seeds = &self.seeds;
+        match suffix {
+            Some(suffix) => {
+                let suffix = LitByteStr::new(suffix.value().as_bytes(), suffix.span());
+                quote! {#seeds, #suffix}
+            }
+            None => quote! {#seeds},
+        }
+    }
+
     /// Returns the name without surrounding quotes.
     pub fn name_token_stream(&self) -> proc_macro2::TokenStream {
         let name_token_stream = syn::parse_str::<syn::Expr>(self.name).unwrap();
@@ -24,9 +42,106 @@ impl EventAuthority {
     }
 }
 
+/// Where the event CPI accounts are placed in the accounts struct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventCpiPosition {
+    /// Prepend the accounts before the user defined fields.
+    Front,
+    /// Append the accounts after the user defined fields.
+    Back,
+}
+
+impl Default for EventCpiPosition {
+    fn default() -> Self {
+        Self::Back
+    }
+}
+
+/// Arguments of the `#[event_cpi]` attribute.
+#[derive(Debug, Default)]
+pub struct EventCpiArgs {
+    /// Position of the event CPI accounts, defaults to the back.
+    pub position: EventCpiPosition,
+    /// Seed suffix of the event authority PDA, i.e. `seed = "vault"` derives the event authority
+    /// from `[b"__event_authority", b"vault"]`. `None` for the default event authority.
+    pub seed: Option<LitStr>,
+    /// Whether the event authority account numbers the events, i.e. `#[event_cpi(sequence)]`.
+    /// The event authority is then writable and must be initialized with `EventSequence::init`.
+    pub sequence: bool,
+    /// Expected address of the `program` account, i.e. `program_id = <expr>`, which self-CPI
+    /// events emitted with the accounts of `ctx` target. `None` for the current program, whose
+    /// address is not checked.
+    pub program_id: Option<syn::Expr>,
+}
+
+impl EventCpiArgs {
+    /// Parses the arguments of the given `#[event_cpi]` attribute, which can be empty.
+    pub fn from_attr(attr: &syn::Attribute) -> ParseResult<Self> {
+        if attr.tokens.is_empty() {
+            Ok(Self::default())
+        } else {
+            attr.parse_args()
+        }
+    }
+}
+
+impl Parse for EventCpiArgs {
+    fn parse(stream: ParseStream) -> ParseResult<Self> {
+        let mut args = Self::default();
+        while !stream.is_empty() {
+            let key = stream.parse::<Ident>()?;
+            if key == "sequence" {
+                args.sequence = true;
+                if !stream.is_empty() {
+                    stream.parse::<Token![,]>()?;
+                }
+                continue;
+            }
+
+            stream.parse::<Token![=]>()?;
+            match key.to_string().as_str() {
+                "position" => {
+                    let value = stream.parse::<LitStr>()?;
+                    args.position = match value.value().as_str() {
+                        "front" => EventCpiPosition::Front,
+                        "back" => EventCpiPosition::Back,
+                        _ => {
+                            return Err(ParseError::new(
+                                value.span(),
+                                "position must be either \"front\" or \"back\"",
+                            ))
+                        }
+                    };
+                }
+                "seed" => {
+                    let value = stream.parse::<LitStr>()?;
+                    let len = value.value().len();
+                    if len > MAX_SEED_LEN {
+                        return Err(ParseError::new(
+                            value.span(),
+                            format!("seed must be at most {MAX_SEED_LEN} bytes, found {len}"),
+                        ));
+                    }
+                    // An empty suffix doesn't change the address, use the default seeds
+                    args.seed = if len == 0 { None } else { Some(value) };
+                }
+                "program_id" => args.program_id = Some(stream.parse()?),
+                _ => return Err(ParseError::new(key.span(), "Invalid event_cpi argument")),
+            }
+
+            if !stream.is_empty() {
+                stream.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(args)
+    }
+}
+
 /// Add necessary event CPI accounts to the given accounts struct.
 pub fn add_event_cpi_accounts(
     accounts_struct: &syn::ItemStruct,
+    args: &EventCpiArgs,
 ) -> syn::parse::Result<syn::ItemStruct> {
     let syn::ItemStruct {
         attrs,
@@ -38,33 +153,123 @@ pub fn add_event_cpi_accounts(
         ..
     } = accounts_struct;
 
+    let authority = EventAuthority::get();
+    check_field_names(fields, &[authority.name, "program"])?;
     let fields = fields.into_iter().collect::<Vec<_>>();
 
-    let info_lifetime = generics
-        .lifetimes()
-        .next()
-        .map(|lifetime| quote! {#lifetime})
-        .unwrap_or(quote! {'info});
-    let generics = generics
-        .lt_token
-        .map(|_| quote! {#generics})
-        .unwrap_or(quote! {<'info>});
+    // Keep the original generics, only add the `'info` lifetime of the accounts if there is none
+    let mut generics = generics.clone();
+    let info_lifetime = match generics.lifetimes().next() {
+        Some(lifetime) => lifetime.lifetime.clone(),
+        None => {
+            let lifetime: syn::Lifetime = syn::parse_quote! {'info};
+            generics.params.insert(
+                0,
+                syn::GenericParam::Lifetime(syn::LifetimeDef::new(lifetime.clone())),
+            );
+            lifetime
+        }
+    };
+    let where_clause = &generics.where_clause;
 
-    let authority = EventAuthority::get();
     let authority_name = authority.name_token_stream();
-    let authority_seeds = authority.seeds;
+    let authority_seeds = authority.seeds_with_suffix(args.seed.as_ref());
 
-    let accounts_struct = quote! {
-        #(#attrs)*
-        #vis #struct_token #ident #generics {
-            #(#fields,)*
+    // The sequence number is stored in the event authority account
+    let authority_constraints = if args.sequence {
+        quote! { mut, seeds = [#authority_seeds], bump }
+    } else {
+        quote! { seeds = [#authority_seeds], bump }
+    };
 
-            /// CHECK: Only the event authority can invoke self-CPI
-            #[account(seeds = [#authority_seeds], bump)]
-            pub #authority_name: AccountInfo<#info_lifetime>,
+    let program_field = match &args.program_id {
+        Some(program_id) => quote! {
+            /// CHECK: Must be the program given with `program_id`
+            #[account(address = #program_id)]
+            pub program: AccountInfo<#info_lifetime>,
+        },
+        None => quote! {
             /// CHECK: Self-CPI will fail if the program is not the current program
             pub program: AccountInfo<#info_lifetime>,
+        },
+    };
+    let event_cpi_fields = quote! {
+        /// CHECK: Only the event authority can invoke self-CPI
+        #[account(#authority_constraints)]
+        pub #authority_name: AccountInfo<#info_lifetime>,
+        #program_field
+    };
+    let fields = match args.position {
+        EventCpiPosition::Front => quote! {
+            #event_cpi_fields
+            #(#fields,)*
+        },
+        EventCpiPosition::Back => quote! {
+            #(#fields,)*
+
+            #event_cpi_fields
+        },
+    };
+
+    let accounts_struct = quote! {
+        #(#attrs)*
+        #vis #struct_token #ident #generics #where_clause {
+            #fields
         }
     };
     syn::parse2(accounts_struct)
 }
+
+/// Returns an error for the first field that has one of the names of the accounts added by
+/// `#[event_cpi]`, which would otherwise be reported as a duplicate field.
+fn check_field_names(fields: &syn::Fields, names: &[&str]) -> syn::parse::Result<()> {
+    let conflict = fields
+        .iter()
+        .filter_map(|field| field.ident.as_ref())
+        .find(|ident| names.iter().any(|name| ident == name));
+    match conflict {
+        Some(ident) => Err(ParseError::new(
+            ident.span(),
+            format!(
+                "`{ident}` is an account added by `#[event_cpi]`, rename the field to \
+                avoid a duplicate field"
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conflicting_field_names() {
+        let accounts_struct: syn::ItemStruct = syn::parse_quote! {
+            pub struct Emit<'info> {
+                pub signer: Signer<'info>,
+            }
+        };
+        assert!(add_event_cpi_accounts(&accounts_struct, &EventCpiArgs::default()).is_ok());
+
+        for name in ["event_authority", "program"] {
+            let ident = Ident::new(name, proc_macro2::Span::call_site());
+            let accounts_struct: syn::ItemStruct = syn::parse_quote! {
+                pub struct Emit<'info> {
+                    pub signer: Signer<'info>,
+                    pub #ident: AccountInfo<'info>,
+                }
+            };
+            let err = add_event_cpi_accounts(&accounts_struct, &EventCpiArgs::default())
+                .unwrap_err()
+                .to_string();
+            assert_eq!(
+                err,
+                format!(
+                    "`{name}` is an account added by `#[event_cpi]`, rename the field to \
+                    avoid a duplicate field"
+                )
+            );
+        }
+    }
+}
@@ -25,15 +25,16 @@ pub fn parse(accounts_struct: &syn::ItemStruct) -> ParseResult<AccountsStruct> {
 
     #[cfg(feature = "event-cpi")]
     let accounts_struct = {
-        let is_event_cpi = accounts_struct
+        let event_cpi_attr = accounts_struct
             .attrs
             .iter()
-            .filter_map(|attr| attr.path.get_ident())
-            .any(|ident| *ident == "event_cpi");
-        if is_event_cpi {
-            event_cpi::add_event_cpi_accounts(accounts_struct)?
-        } else {
-            accounts_struct.clone()
+            .find(|attr| attr.path.is_ident("event_cpi"));
+        match event_cpi_attr {
+            Some(attr) => {
+                let args = event_cpi::EventCpiArgs::from_attr(attr)?;
+                event_cpi::add_event_cpi_accounts(accounts_struct, &args)?
+            }
+            None => accounts_struct.clone(),
         }
     };
     #[cfg(not(feature = "event-cpi"))]
@@ -339,6 +340,7 @@ fn is_field_primitive(f: &syn::Field) -> ParseResult<bool> {
             | "AccountLoader"
             | "Account"
             | "Program"
+            | "RingBuffer"
             | "Interface"
             | "InterfaceAccount"
             | "Signer"
@@ -359,6 +361,7 @@ fn parse_ty(f: &syn::Field) -> ParseResult<(Ty, bool)> {
         "Program" => Ty::Program(parse_program_ty(&path)?),
         "Interface" => Ty::Interface(parse_interface_ty(&path)?),
         "InterfaceAccount" => Ty::InterfaceAccount(parse_interface_account_ty(&path)?),
+        "RingBuffer" => Ty::RingBuffer,
         "Signer" => Ty::Signer,
         "SystemAccount" => Ty::SystemAccount,
         "ProgramData" => Ty::ProgramData,
@@ -48,6 +48,22 @@ impl CrateContext {
         })
     }
 
+    /// Creates the context of a crate with a single module made of the given items, e.g. to
+    /// parse items that aren't read from the files of a crate. Items of nested modules are not
+    /// part of the context.
+    pub fn from_items(items: Vec<syn::Item>) -> Self {
+        let root_mod = ParsedModule::new(
+            String::new(),
+            PathBuf::new(),
+            PathBuf::new(),
+            "crate".to_owned(),
+            items,
+        );
+        let mut modules = BTreeMap::new();
+        modules.insert(root_mod.name.clone(), root_mod);
+        CrateContext { modules }
+    }
+
     // Perform Anchor safety checks on the parsed create
     pub fn safety_checks(&self) -> Result<(), anyhow::Error> {
         // Check all structs for unsafe field types, i.e. AccountInfo and UncheckedAccount.
@@ -100,6 +116,8 @@ impl<'krate> ModuleContext<'krate> {
 struct ParsedModule {
     name: String,
     file: PathBuf,
+    /// Directory of the file modules declared within this module.
+    dir: PathBuf,
     path: String,
     items: Vec<syn::Item>,
 }
@@ -113,12 +131,14 @@ impl ParsedModule {
         let root_mod = Self::new(
             String::new(),
             root.to_owned(),
+            root.parent().unwrap().to_owned(),
             "crate".to_owned(),
             root_file.items,
         );
 
         struct UnparsedModule {
             file: PathBuf,
+            dir: PathBuf,
             path: String,
             name: String,
             item: syn::ItemMod,
@@ -128,6 +148,7 @@ impl ParsedModule {
             .submodules()
             .map(|item| UnparsedModule {
                 file: root_mod.file.clone(),
+                dir: root_mod.dir.clone(),
                 path: root_mod.path.clone(),
                 name: item.ident.to_string(),
                 item: item.clone(),
@@ -137,11 +158,12 @@ impl ParsedModule {
         while let Some(to_parse) = unparsed.pop() {
             let path = format!("{}::{}", to_parse.path, to_parse.name);
             let name = to_parse.name;
-            let module = Self::from_item_mod(&to_parse.file, &path, to_parse.item)?;
+            let module = Self::from_item_mod(&to_parse.file, &to_parse.dir, &path, to_parse.item)?;
 
             unparsed.extend(module.submodules().map(|item| UnparsedModule {
                 item: item.clone(),
                 file: module.file.clone(),
+                dir: module.dir.clone(),
                 path: module.path.clone(),
                 name: item.ident.to_string(),
             }));
@@ -155,15 +177,21 @@ impl ParsedModule {
 
     fn from_item_mod(
         parent_file: &Path,
+        parent_dir: &Path,
         parent_path: &str,
         item: syn::ItemMod,
     ) -> ParseResult<Self> {
+        // File modules declared within this module are located in a directory named after it,
+        // regardless of whether the module itself is inline or in its own file
+        let dir = parent_dir.join(item.ident.to_string());
+
         Ok(match item.content {
             Some((_, items)) => {
                 // The module content is within the parent file being parsed
                 Self::new(
                     parent_path.to_owned(),
                     parent_file.to_owned(),
+                    dir,
                     item.ident.to_string(),
                     items,
                 )
@@ -171,15 +199,9 @@ impl ParsedModule {
             None => {
                 // The module is referencing some other file, so we need to load that
                 // to parse the items it has.
-                let parent_dir = parent_file.parent().unwrap();
-                let parent_filename = parent_file.file_stem().unwrap().to_str().unwrap();
-                let parent_mod_dir = parent_dir.join(parent_filename);
-
                 let possible_file_paths = vec![
                     parent_dir.join(format!("{}.rs", item.ident)),
                     parent_dir.join(format!("{}/mod.rs", item.ident)),
-                    parent_mod_dir.join(format!("{}.rs", item.ident)),
-                    parent_mod_dir.join(format!("{}/mod.rs", item.ident)),
                 ];
 
                 let mod_file_path = possible_file_paths
@@ -193,6 +215,7 @@ impl ParsedModule {
                 Self::new(
                     parent_path.to_owned(),
                     mod_file_path,
+                    dir,
                     item.ident.to_string(),
                     mod_file.items,
                 )
@@ -200,10 +223,11 @@ impl ParsedModule {
         })
     }
 
-    fn new(path: String, file: PathBuf, name: String, items: Vec<syn::Item>) -> Self {
+    fn new(path: String, file: PathBuf, dir: PathBuf, name: String, items: Vec<syn::Item>) -> Self {
         Self {
             name,
             file,
+            dir,
             path,
             items,
         }
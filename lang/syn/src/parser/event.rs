@@ -0,0 +1,1226 @@
+use crate::{EventArgs, EventIndex, EventVia};
+use heck::{MixedCase, ShoutySnakeCase};
+use syn::ext::IdentExt;
+use syn::parse::{Error as ParseError, Parse, ParseStream, Result as ParseResult};
+use syn::spanned::Spanned;
+use syn::{Expr, ExprArray, ExprLit, Ident, Lit, LitInt, LitStr, Token};
+
+/// Maximum discriminator length, i.e. the length of the SHA256 hash.
+const MAX_DISCRIMINATOR_LEN: usize = 32;
+
+/// Standard library traits that can be derived with `#[event(derive(..))]`.
+pub const EVENT_DERIVES: &[&str] = &[
+    "Clone",
+    "Copy",
+    "Debug",
+    "Default",
+    "PartialEq",
+    "Eq",
+    "PartialOrd",
+    "Ord",
+    "Hash",
+];
+
+/// Estimated size in bytes above which events can't be logged with `sol_log_data`, since the
+/// base64 encoding of larger events exceeds the 10 KB log limit of a transaction.
+pub const MAX_LOGGED_EVENT_SIZE: usize = 7_500;
+
+pub fn parse_args(stream: ParseStream) -> ParseResult<EventArgs> {
+    let mut args = EventArgs::default();
+    let mut discriminator_span = None;
+    let mut namespace_span = None;
+    let mut timestamp_span = None;
+    while !stream.is_empty() {
+        let key = stream.call(Ident::parse_any)?;
+        match key.to_string().as_str() {
+            "discriminator_len" => {
+                stream.parse::<Token![=]>()?;
+                let lit = stream.parse::<LitInt>()?;
+                let len = lit.base10_parse::<usize>()?;
+                if len == 0 || len % 8 != 0 || len > MAX_DISCRIMINATOR_LEN {
+                    return Err(ParseError::new(
+                        lit.span(),
+                        format!(
+                            "discriminator_len must be a multiple of 8 between 8 and \
+                            {MAX_DISCRIMINATOR_LEN}, found {len}"
+                        ),
+                    ));
+                }
+                args.discriminator_len = len;
+            }
+            "discriminator" => {
+                stream.parse::<Token![=]>()?;
+                let array = stream.parse::<ExprArray>()?;
+                discriminator_span = Some(array.span());
+                args.discriminator = Some(parse_discriminator(&array)?);
+            }
+            "version" => {
+                stream.parse::<Token![=]>()?;
+                let lit = stream.parse::<LitInt>()?;
+                args.version = Some(lit.base10_parse::<u8>()?);
+            }
+            "idl_name" => {
+                stream.parse::<Token![=]>()?;
+                let lit = stream.parse::<LitStr>()?;
+                if lit.value().is_empty() {
+                    return Err(ParseError::new(lit.span(), "idl_name must not be empty"));
+                }
+                args.idl_name = Some(lit.value());
+            }
+            "namespace" => {
+                stream.parse::<Token![=]>()?;
+                let lit = stream.parse::<LitStr>()?;
+                let namespace = lit.value();
+                if namespace.is_empty() || namespace.contains(':') {
+                    return Err(ParseError::new(
+                        lit.span(),
+                        "namespace must be non-empty and must not contain `:`",
+                    ));
+                }
+                namespace_span = Some(lit.span());
+                args.namespace = Some(namespace);
+            }
+            "via" => {
+                stream.parse::<Token![=]>()?;
+                let lit = stream.parse::<LitStr>()?;
+                args.via = match lit.value().as_str() {
+                    "log" => EventVia::Log,
+                    "cpi" => EventVia::Cpi,
+                    via => {
+                        return Err(ParseError::new(
+                            lit.span(),
+                            format!(
+                                "unknown event emission `{via}`, expected one of: {}",
+                                EventVia::VALUES.join(", ")
+                            ),
+                        ))
+                    }
+                };
+            }
+            "feature" => {
+                stream.parse::<Token![=]>()?;
+                let lit = stream.parse::<LitStr>()?;
+                if lit.value().is_empty() {
+                    return Err(ParseError::new(lit.span(), "feature must not be empty"));
+                }
+                args.feature = Some(lit.value());
+            }
+            "max_size" => {
+                stream.parse::<Token![=]>()?;
+                let lit = stream.parse::<LitInt>()?;
+                args.max_size = Some(lit.base10_parse::<usize>()?);
+            }
+            "derive" => {
+                let content;
+                syn::parenthesized!(content in stream);
+                let derives = content.parse_terminated::<Ident, Token![,]>(Ident::parse)?;
+                for derive in derives {
+                    if !EVENT_DERIVES.contains(&derive.to_string().as_str()) {
+                        return Err(ParseError::new(
+                            derive.span(),
+                            format!(
+                                "unsupported event derive `{derive}`, expected one of: {}",
+                                EVENT_DERIVES.join(", ")
+                            ),
+                        ));
+                    }
+                    if args.derives.contains(&derive) {
+                        return Err(ParseError::new(
+                            derive.span(),
+                            format!("duplicate event derive `{derive}`"),
+                        ));
+                    }
+                    args.derives.push(derive);
+                }
+            }
+            "sequence" => args.sequence = true,
+            "zero_copy" => args.zero_copy = true,
+            "arbitrary" => args.arbitrary = true,
+            "no_serde" => args.no_serde = true,
+            "discriminator_hex" => args.discriminator_hex = true,
+            "hidden" => args.hidden = true,
+            "correlated" => args.correlated = true,
+            "timestamp" => {
+                timestamp_span = Some(key.span());
+                args.timestamp = true;
+            }
+            _ => return Err(ParseError::new(key.span(), "Invalid event argument")),
+        }
+
+        if !stream.is_empty() {
+            stream.parse::<Token![,]>()?;
+        }
+    }
+
+    if let (Some(discriminator), Some(span)) = (&args.discriminator, discriminator_span) {
+        if discriminator.len() != args.discriminator_len {
+            return Err(ParseError::new(
+                span,
+                format!(
+                    "discriminator must be exactly {} bytes, found {}",
+                    args.discriminator_len,
+                    discriminator.len()
+                ),
+            ));
+        }
+    }
+
+    if let (Some(_), Some(span)) = (&args.discriminator, namespace_span) {
+        return Err(ParseError::new(
+            span,
+            "namespace can't be combined with a custom discriminator",
+        ));
+    }
+
+    if let Some(span) = timestamp_span {
+        // The timestamp is written over the last 8 bytes of the borsh serialized event
+        if args.zero_copy || args.no_serde {
+            return Err(ParseError::new(
+                span,
+                "timestamp can't be combined with zero_copy or no_serde",
+            ));
+        }
+    }
+
+    Ok(args)
+}
+
+fn parse_discriminator(array: &ExprArray) -> ParseResult<Vec<u8>> {
+    array
+        .elems
+        .iter()
+        .map(|elem| match elem {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(int), ..
+            }) => int.base10_parse::<u8>(),
+            _ => Err(ParseError::new(
+                elem.span(),
+                "discriminator bytes must be u8 literals",
+            )),
+        })
+        .collect()
+}
+
+impl EventArgs {
+    /// Returns the discriminator of the event with the given name.
+    ///
+    /// The discriminator is either the custom `discriminator` argument, or the first
+    /// `discriminator_len` bytes of `Sha256("event:<name>")`, or of
+    /// `Sha256("<namespace>:event:<name>")` if a `namespace` is given.
+    pub fn discriminator(&self, name: &Ident) -> Vec<u8> {
+        if let Some(discriminator) = &self.discriminator {
+            return discriminator.clone();
+        }
+
+        match &self.namespace {
+            Some(namespace) => {
+                hash_preimage(&format!("{namespace}:event:{name}"), self.discriminator_len)
+            }
+            None => hashed_discriminator(&name.to_string(), self.discriminator_len),
+        }
+    }
+
+    /// Returns the name of the event with the given type name in the IDL.
+    pub fn idl_name(&self, name: &Ident) -> String {
+        self.idl_name.clone().unwrap_or_else(|| name.to_string())
+    }
+
+    /// Returns the discriminator to store in the IDL, which is only needed when it can't be
+    /// derived from the [IDL name](Self::idl_name) of the event, i.e. when either `idl_name`,
+    /// `discriminator` or `namespace` is given.
+    pub fn idl_discriminator(&self, name: &Ident) -> Option<Vec<u8>> {
+        let discriminator = self.discriminator(name);
+        let idl_name_discriminator =
+            hashed_discriminator(&self.idl_name(name), self.discriminator_len);
+        if discriminator == idl_name_discriminator {
+            None
+        } else {
+            Some(discriminator)
+        }
+    }
+
+    /// Returns the name of the IDL constant that holds the discriminator of the event, e.g.
+    /// `MY_EVENT_DISCRIMINATOR` for `MyEvent`.
+    pub fn idl_discriminator_const_name(&self, name: &Ident) -> String {
+        format!(
+            "{}_DISCRIMINATOR",
+            self.idl_name(name).to_shouty_snake_case()
+        )
+    }
+}
+
+/// Returns the first `len` bytes of `Sha256("event:<name>")`.
+fn hashed_discriminator(name: &str, len: usize) -> Vec<u8> {
+    hash_preimage(&format!("event:{name}"), len)
+}
+
+/// Returns the first `len` bytes of `Sha256(preimage)`.
+fn hash_preimage(preimage: &str, len: usize) -> Vec<u8> {
+    crate::hash::hash(preimage.as_bytes()).to_bytes()[..len].to_vec()
+}
+
+/// Estimated serialized size of an event, see [`estimate_size`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventSize {
+    /// Upper bound of the size in bytes, where dynamically-sized fields only count with the
+    /// size of their length prefix.
+    pub bytes: usize,
+    /// Whether the event has dynamically-sized fields, or fields of unknown size, i.e. whether
+    /// the event can be larger than `bytes`.
+    pub is_dynamic: bool,
+}
+
+impl EventSize {
+    fn fixed(bytes: usize) -> Self {
+        Self {
+            bytes,
+            is_dynamic: false,
+        }
+    }
+
+    fn dynamic(bytes: usize) -> Self {
+        Self {
+            bytes,
+            is_dynamic: true,
+        }
+    }
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            bytes: self.bytes.saturating_add(other.bytes),
+            is_dynamic: self.is_dynamic || other.is_dynamic,
+        }
+    }
+
+    fn max(self, other: Self) -> Self {
+        Self {
+            bytes: self.bytes.max(other.bytes),
+            is_dynamic: self.is_dynamic || other.is_dynamic,
+        }
+    }
+}
+
+/// Estimates the serialized size of the given event, including its discriminator, correlation id
+/// slot and version.
+///
+/// The estimate is a conservative upper bound derived from the field types, e.g. enums are as
+/// large as their largest variant, and `Option`s always count with their value.
+pub fn estimate_size(item: &syn::Item, args: &EventArgs) -> EventSize {
+    let correlation_id = if args.correlated { 17 } else { 0 };
+    let header = args.discriminator_len + correlation_id + usize::from(args.version.is_some());
+    let data = match item {
+        syn::Item::Struct(strct) => fields_size(&strct.fields),
+        syn::Item::Enum(enm) => enm
+            .variants
+            .iter()
+            .map(|variant| fields_size(&variant.fields))
+            .fold(EventSize::fixed(0), EventSize::max)
+            .add(EventSize::fixed(1)),
+        _ => EventSize::dynamic(0),
+    };
+    EventSize::fixed(header).add(data)
+}
+
+fn fields_size(fields: &syn::Fields) -> EventSize {
+    fields
+        .iter()
+        .map(|field| type_size(&field.ty))
+        .fold(EventSize::fixed(0), EventSize::add)
+}
+
+fn type_size(ty: &syn::Type) -> EventSize {
+    match ty {
+        syn::Type::Path(ty_path) => {
+            let segment = match ty_path.path.segments.last() {
+                Some(segment) => segment,
+                None => return EventSize::dynamic(0),
+            };
+            let generic = match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) => {
+                    args.args.iter().find_map(|arg| match arg {
+                        syn::GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    })
+                }
+                _ => None,
+            };
+            match (segment.ident.to_string().as_str(), generic) {
+                ("bool" | "u8" | "i8", _) => EventSize::fixed(1),
+                ("u16" | "i16", _) => EventSize::fixed(2),
+                ("u32" | "i32" | "f32", _) => EventSize::fixed(4),
+                ("u64" | "i64" | "f64", _) => EventSize::fixed(8),
+                ("u128" | "i128", _) => EventSize::fixed(16),
+                ("Pubkey", _) => EventSize::fixed(32),
+                ("Option", Some(ty)) => EventSize::fixed(1).add(type_size(ty)),
+                ("Box", Some(ty)) => type_size(ty),
+                (
+                    "String" | "Vec" | "VecDeque" | "HashMap" | "BTreeMap" | "HashSet" | "BTreeSet",
+                    _,
+                ) => EventSize::dynamic(4),
+                _ => EventSize::dynamic(0),
+            }
+        }
+        syn::Type::Array(array) => match &array.len {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(len), ..
+            }) => match len.base10_parse::<usize>() {
+                Ok(len) => {
+                    let elem = type_size(&array.elem);
+                    EventSize {
+                        bytes: elem.bytes.saturating_mul(len),
+                        is_dynamic: elem.is_dynamic,
+                    }
+                }
+                Err(_) => EventSize::dynamic(0),
+            },
+            _ => EventSize::dynamic(0),
+        },
+        syn::Type::Tuple(tuple) => tuple
+            .elems
+            .iter()
+            .map(type_size)
+            .fold(EventSize::fixed(0), EventSize::add),
+        syn::Type::Paren(paren) => type_size(&paren.elem),
+        syn::Type::Group(group) => type_size(&group.elem),
+        _ => EventSize::dynamic(0),
+    }
+}
+
+/// Checks that the fields of the event don't have types that obviously can't be serialized,
+/// e.g. `AccountInfo`, so that the error points at the field instead of the serialization
+/// derives. The check is best-effort, other types are still checked by the derives.
+pub fn check_field_types(item: &syn::Item) -> ParseResult<()> {
+    let fields: Vec<&syn::Field> = match item {
+        syn::Item::Struct(strct) => strct.fields.iter().collect(),
+        syn::Item::Enum(enm) => enm
+            .variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter())
+            .collect(),
+        _ => vec![],
+    };
+
+    fields
+        .into_iter()
+        .filter_map(|field| unserializable_type(&field.ty))
+        .map(|(ty, msg)| ParseError::new(ty.span(), msg))
+        .reduce(|mut errors, error| {
+            errors.combine(error);
+            errors
+        })
+        .map_or(Ok(()), Err)
+}
+
+/// Checks that the event has no lifetime parameters. Events are deserialized from the data of
+/// logs and instructions, which they can't borrow from, so they must own their data. Borrowed
+/// fields are reported by [`check_field_types`], which should run first.
+pub fn check_lifetimes(item: &syn::Item) -> ParseResult<()> {
+    let generics = match item {
+        syn::Item::Struct(strct) => &strct.generics,
+        syn::Item::Enum(enm) => &enm.generics,
+        _ => return Ok(()),
+    };
+
+    generics
+        .lifetimes()
+        .map(|param| {
+            ParseError::new(
+                param.lifetime.span(),
+                format!(
+                    "events must own their data to be deserialized, \
+                    remove the lifetime parameter `{}`",
+                    param.lifetime
+                ),
+            )
+        })
+        .reduce(|mut errors, error| {
+            errors.combine(error);
+            errors
+        })
+        .map_or(Ok(()), Err)
+}
+
+/// Returns the part of `ty` that can't be serialized, and why.
+fn unserializable_type(ty: &syn::Type) -> Option<(&syn::Type, String)> {
+    /// Account types of `Accounts` structs, which are only valid during an instruction. They're
+    /// only matched with a lifetime argument so that user-defined types with the same name, e.g.
+    /// `Account`, are not rejected.
+    const ACCOUNT_TYPES: &[&str] = &[
+        "Account",
+        "AccountInfo",
+        "AccountLoader",
+        "Context",
+        "Interface",
+        "InterfaceAccount",
+        "Program",
+        "RingBuffer",
+        "Signer",
+        "SystemAccount",
+        "Sysvar",
+        "UncheckedAccount",
+    ];
+
+    match ty {
+        syn::Type::Path(ty_path) => {
+            let segment = ty_path.path.segments.last()?;
+            let name = segment.ident.to_string();
+            let has_lifetime = match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) => args
+                    .args
+                    .iter()
+                    .any(|arg| matches!(arg, syn::GenericArgument::Lifetime(_))),
+                _ => false,
+            };
+            if has_lifetime && ACCOUNT_TYPES.contains(&name.as_str()) {
+                return Some((
+                    ty,
+                    format!(
+                        "`{name}` can't be serialized in an event, \
+                        use the key of the account, i.e. `Pubkey`, instead"
+                    ),
+                ));
+            }
+            match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) => {
+                    args.args.iter().find_map(|arg| match arg {
+                        syn::GenericArgument::Type(ty) => unserializable_type(ty),
+                        _ => None,
+                    })
+                }
+                _ => None,
+            }
+        }
+        syn::Type::Reference(reference) => {
+            let owned = match &*reference.elem {
+                syn::Type::Slice(slice) => {
+                    let elem = &slice.elem;
+                    format!("Vec<{}>", quote::quote! { #elem })
+                }
+                syn::Type::Path(path) if path.path.is_ident("str") => "String".into(),
+                elem => quote::quote! { #elem }.to_string(),
+            };
+            Some((
+                ty,
+                format!(
+                    "borrowed fields can't be deserialized from an event, \
+                    use the owned type `{owned}` instead"
+                ),
+            ))
+        }
+        syn::Type::Ptr(_)
+        | syn::Type::BareFn(_)
+        | syn::Type::TraitObject(_)
+        | syn::Type::ImplTrait(_) => Some((
+            ty,
+            format!(
+                "`{}` can't be serialized in an event",
+                quote::quote! { #ty }
+            ),
+        )),
+        syn::Type::Array(array) => unserializable_type(&array.elem),
+        syn::Type::Paren(paren) => unserializable_type(&paren.elem),
+        syn::Type::Tuple(tuple) => tuple.elems.iter().find_map(unserializable_type),
+        _ => None,
+    }
+}
+
+/// Name of the field added by `#[event(sequence)]`.
+pub const SEQUENCE_FIELD: &str = "sequence";
+
+/// Adds the `sequence: u64` field of `#[event(sequence)]` as the first field of the event, so
+/// that it's at a fixed offset right after the discriminator and version.
+pub fn add_sequence_field(strct: &mut syn::ItemStruct) -> ParseResult<()> {
+    let fields = match &mut strct.fields {
+        syn::Fields::Named(fields) => fields,
+        _ => {
+            return Err(ParseError::new(
+                strct.ident.span(),
+                "`sequence` events must have named fields",
+            ))
+        }
+    };
+    if let Some(field) = fields.named.iter().find(|field| {
+        field
+            .ident
+            .as_ref()
+            .map_or(false, |ident| ident == SEQUENCE_FIELD)
+    }) {
+        return Err(ParseError::new(
+            field.span(),
+            "`sequence` events can't have a field named `sequence`",
+        ));
+    }
+
+    let sequence = Ident::new(SEQUENCE_FIELD, strct.ident.span());
+    let field: syn::FieldsNamed = syn::parse_quote! {{
+        /// Sequence number of the event, set by the program emitting it.
+        pub #sequence: u64
+    }};
+    fields
+        .named
+        .insert(0, field.named.into_iter().next().unwrap());
+    Ok(())
+}
+
+/// Name of the field added by `#[event(timestamp)]`.
+pub const TIMESTAMP_FIELD: &str = "emitted_at";
+
+/// Adds the `emitted_at: i64` field of `#[event(timestamp)]` as the last field of the event, so
+/// that it's serialized as the last 8 bytes of the event, where it's written when the event is
+/// serialized on-chain.
+pub fn add_timestamp_field(strct: &mut syn::ItemStruct) -> ParseResult<()> {
+    let fields = match &mut strct.fields {
+        syn::Fields::Named(fields) => fields,
+        _ => {
+            return Err(ParseError::new(
+                strct.ident.span(),
+                "`timestamp` events must have named fields",
+            ))
+        }
+    };
+    if let Some(field) = fields.named.iter().find(|field| {
+        field
+            .ident
+            .as_ref()
+            .map_or(false, |ident| ident == TIMESTAMP_FIELD)
+    }) {
+        return Err(ParseError::new(
+            field.span(),
+            "`timestamp` events can't have a field named `emitted_at`",
+        ));
+    }
+
+    let emitted_at = Ident::new(TIMESTAMP_FIELD, strct.ident.span());
+    let field: syn::FieldsNamed = syn::parse_quote! {{
+        /// Unix timestamp of the `Clock` sysvar when the event was emitted.
+        pub #emitted_at: i64
+    }};
+    fields.named.push(field.named.into_iter().next().unwrap());
+    Ok(())
+}
+
+/// Parses the arguments of an `#[event]` attribute.
+pub fn parse_attr(attr: &syn::Attribute) -> ParseResult<EventArgs> {
+    if attr.tokens.is_empty() {
+        return Ok(EventArgs::default());
+    }
+    attr.parse_args_with(parse_args)
+}
+
+/// Returns the Rust name of the event field at `index`, i.e. its identifier, or `_<index>` for
+/// the unnamed fields of tuple struct events.
+pub fn field_name(field: &syn::Field, index: usize) -> String {
+    match &field.ident {
+        Some(ident) => ident.to_string(),
+        None => format!("_{index}"),
+    }
+}
+
+/// Returns the IDL name of the event field at `index` along with its Rust name, if the two
+/// differ.
+///
+/// Named fields are camelCased while the positional names of tuple struct fields are kept as is.
+pub fn idl_field_name(field: &syn::Field, index: usize) -> (String, Option<String>) {
+    let rust_name = field_name(field, index);
+    if field.ident.is_none() {
+        return (rust_name, None);
+    }
+
+    let name = rust_name.to_mixed_case();
+    if rust_name != name {
+        (name, Some(rust_name))
+    } else {
+        (name, None)
+    }
+}
+
+/// Parses the `#[index]` attribute of an event field.
+///
+/// Returns `None` if the field is not indexed.
+pub fn parse_index(field: &syn::Field) -> ParseResult<Option<EventIndex>> {
+    let attr = match field.attrs.iter().find(|attr| attr.path.is_ident("index")) {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+    if attr.tokens.is_empty() {
+        return Ok(Some(EventIndex::default()));
+    }
+
+    attr.parse_args_with(|stream: ParseStream| {
+        let mut index = EventIndex::default();
+        while !stream.is_empty() {
+            let key = stream.parse::<Ident>()?;
+            match key.to_string().as_str() {
+                "name" => {
+                    stream.parse::<Token![=]>()?;
+                    let name = stream.parse::<LitStr>()?;
+                    if name.value().is_empty() {
+                        return Err(ParseError::new(name.span(), "index name must not be empty"));
+                    }
+                    index.name = Some(name.value());
+                }
+                _ => return Err(ParseError::new(key.span(), "Invalid index argument")),
+            }
+
+            if !stream.is_empty() {
+                stream.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(index)
+    })
+    .map(Some)
+}
+
+/// Parses the `#[decimals(<N>)]` attribute of an event field.
+///
+/// Returns the number of implied decimals of the field, e.g. `6` for amounts in millionths, or
+/// `None` if the field doesn't have the attribute.
+pub fn parse_decimals(field: &syn::Field) -> ParseResult<Option<u8>> {
+    let attr = match field
+        .attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("decimals"))
+    {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+    if field
+        .attrs
+        .iter()
+        .any(|attr| attr.path.is_ident("event_flatten"))
+    {
+        return Err(ParseError::new(
+            attr.span(),
+            "`#[event_flatten]` fields can't have decimals",
+        ));
+    }
+
+    attr.parse_args_with(|stream: ParseStream| {
+        let decimals = stream.parse::<LitInt>()?;
+        decimals.base10_parse::<u8>().map(Some)
+    })
+}
+
+/// Returns the `#[cfg]` attributes of an event field, which the code generated for the field,
+/// e.g. its IDL, must be gated behind so that it only exists in the builds the field exists in.
+pub fn cfg_attrs(field: &syn::Field) -> Vec<&syn::Attribute> {
+    field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("cfg"))
+        .collect()
+}
+
+/// Name of the attribute of event fields that may be missing from older serialized events.
+pub const OPTIONAL_TRAILING_ATTR: &str = "optional_trailing";
+
+/// Returns the index of the first `#[optional_trailing]` field of an event, or `None` if it
+/// has none.
+///
+/// Fields are only optional at the end of the serialized data, so all the fields after the
+/// first `#[optional_trailing]` field must be `#[optional_trailing]` too, i.e. new fields can
+/// only be appended to the event.
+pub fn parse_optional_trailing(fields: &syn::Fields) -> ParseResult<Option<usize>> {
+    let is_optional = |field: &syn::Field| -> ParseResult<bool> {
+        match field
+            .attrs
+            .iter()
+            .find(|attr| attr.path.is_ident(OPTIONAL_TRAILING_ATTR))
+        {
+            Some(attr) if !attr.tokens.is_empty() => Err(ParseError::new(
+                attr.tokens.span(),
+                "`#[optional_trailing]` doesn't take arguments",
+            )),
+            Some(_) => Ok(true),
+            None => Ok(false),
+        }
+    };
+
+    let mut first = None;
+    for (i, field) in fields.iter().enumerate() {
+        match (is_optional(field)?, first) {
+            (true, None) => first = Some(i),
+            (false, Some(_)) => {
+                return Err(ParseError::new(
+                    field.span(),
+                    "fields after an `#[optional_trailing]` field must be \
+                    `#[optional_trailing]` too",
+                ))
+            }
+            _ => {}
+        }
+    }
+    Ok(first)
+}
+
+/// Parses the `#[event_flatten]` attribute of an event field.
+///
+/// Returns whether the fields of the field's type are inlined in the IDL of the event.
+pub fn parse_flatten(field: &syn::Field) -> ParseResult<bool> {
+    let attr = match field
+        .attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("event_flatten"))
+    {
+        Some(attr) => attr,
+        None => return Ok(false),
+    };
+    if !attr.tokens.is_empty() {
+        return Err(ParseError::new(
+            attr.tokens.span(),
+            "`#[event_flatten]` doesn't take arguments",
+        ));
+    }
+    if field.attrs.iter().any(|attr| attr.path.is_ident("index")) {
+        return Err(ParseError::new(
+            attr.span(),
+            "`#[event_flatten]` fields can't be indexed",
+        ));
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(field: syn::FieldsNamed) -> syn::Field {
+        field.named.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn index() {
+        let f = field(syn::parse_quote! {{ pub data: u64 }});
+        assert!(parse_index(&f).unwrap().is_none());
+
+        let f = field(syn::parse_quote! {{ #[index] pub data: u64 }});
+        assert_eq!(parse_index(&f).unwrap().unwrap().name, None);
+
+        let f = field(syn::parse_quote! {{
+            /// Docs before the index
+            #[index(name = "owner")]
+            pub data: u64
+        }});
+        assert_eq!(
+            parse_index(&f).unwrap().unwrap().name.as_deref(),
+            Some("owner")
+        );
+    }
+
+    #[test]
+    fn invalid_index() {
+        let f = field(syn::parse_quote! {{ #[index(key = "owner")] pub data: u64 }});
+        assert!(parse_index(&f).is_err());
+
+        let f = field(syn::parse_quote! {{ #[index(name = "")] pub data: u64 }});
+        assert!(parse_index(&f).is_err());
+    }
+
+    #[test]
+    fn decimals() {
+        let f = field(syn::parse_quote! {{ pub amount: u64 }});
+        assert_eq!(parse_decimals(&f).unwrap(), None);
+
+        let f = field(syn::parse_quote! {{ #[index] #[decimals(6)] pub amount: u64 }});
+        assert_eq!(parse_decimals(&f).unwrap(), Some(6));
+
+        let f = field(syn::parse_quote! {{ #[decimals] pub amount: u64 }});
+        assert!(parse_decimals(&f).is_err());
+
+        let f = field(syn::parse_quote! {{ #[decimals(256)] pub amount: u64 }});
+        assert!(parse_decimals(&f).is_err());
+
+        let f = field(syn::parse_quote! {{ #[event_flatten] #[decimals(6)] pub header: Header }});
+        assert!(parse_decimals(&f).is_err());
+    }
+
+    #[test]
+    fn idl_name() {
+        let name: Ident = syn::parse_quote!(Transfer);
+
+        let args: EventArgs = syn::parse_quote!();
+        assert_eq!(args.idl_name(&name), "Transfer");
+        assert_eq!(args.idl_discriminator(&name), None);
+
+        let args: EventArgs = syn::parse_quote!(idl_name = "TransferCompleted");
+        assert_eq!(args.idl_name(&name), "TransferCompleted");
+        assert_eq!(
+            args.discriminator(&name),
+            hashed_discriminator("Transfer", 8)
+        );
+        assert_eq!(
+            args.idl_discriminator(&name),
+            Some(hashed_discriminator("Transfer", 8))
+        );
+
+        // The discriminator can still be derived from the IDL name
+        let args: EventArgs = syn::parse_quote!(idl_name = "Transfer");
+        assert_eq!(args.idl_discriminator(&name), None);
+
+        let args: EventArgs = syn::parse_quote!(
+            idl_name = "TransferCompleted",
+            discriminator = [1, 2, 3, 4, 5, 6, 7, 8]
+        );
+        assert_eq!(
+            args.idl_discriminator(&name),
+            Some(vec![1, 2, 3, 4, 5, 6, 7, 8])
+        );
+
+        assert!(syn::parse_str::<EventArgs>(r#"idl_name = """#).is_err());
+    }
+
+    #[test]
+    fn derives() {
+        let args: EventArgs = syn::parse_quote!(derive(Clone, PartialEq, Debug), version = 1);
+        let derives = args
+            .derives
+            .iter()
+            .map(|derive| derive.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(derives, ["Clone", "PartialEq", "Debug"]);
+        assert_eq!(args.version, Some(1));
+
+        let args: EventArgs = syn::parse_quote!(derive());
+        assert!(args.derives.is_empty());
+
+        assert!(syn::parse_str::<EventArgs>("derive(Serialize)").is_err());
+        assert!(syn::parse_str::<EventArgs>("derive(Clone, Clone)").is_err());
+        assert!(syn::parse_str::<EventArgs>("derive = Clone").is_err());
+    }
+
+    #[test]
+    fn no_serde() {
+        let args: EventArgs = syn::parse_quote!(no_serde, version = 1);
+        assert!(args.no_serde);
+        assert_eq!(args.version, Some(1));
+
+        assert!(!EventArgs::default().no_serde);
+        assert!(syn::parse_str::<EventArgs>("no_serde = true").is_err());
+    }
+
+    #[test]
+    fn discriminator_hex() {
+        let args: EventArgs = syn::parse_quote!(discriminator_hex);
+        assert!(args.discriminator_hex);
+
+        assert!(!EventArgs::default().discriminator_hex);
+        assert!(syn::parse_str::<EventArgs>("discriminator_hex = true").is_err());
+    }
+
+    #[test]
+    fn correlated() {
+        let args: EventArgs = syn::parse_quote!(correlated, version = 1);
+        assert!(args.correlated);
+        assert_eq!(args.version, Some(1));
+
+        assert!(!EventArgs::default().correlated);
+        assert!(syn::parse_str::<EventArgs>("correlated = true").is_err());
+    }
+
+    #[test]
+    fn via() {
+        assert_eq!(EventArgs::default().via, EventVia::Log);
+
+        let args: EventArgs = syn::parse_quote!(via = "cpi", version = 1);
+        assert_eq!(args.via, EventVia::Cpi);
+        let args: EventArgs = syn::parse_quote!(via = "log");
+        assert_eq!(args.via, EventVia::Log);
+
+        assert!(syn::parse_str::<EventArgs>(r#"via = "logs""#).is_err());
+        assert!(syn::parse_str::<EventArgs>("via = cpi").is_err());
+    }
+
+    #[test]
+    fn hidden() {
+        let args: EventArgs = syn::parse_quote!(hidden, version = 1);
+        assert!(args.hidden);
+
+        assert!(!EventArgs::default().hidden);
+        assert!(syn::parse_str::<EventArgs>("hidden = true").is_err());
+    }
+
+    #[test]
+    fn namespace() {
+        let name: Ident = syn::parse_quote!(Transfer);
+
+        let args: EventArgs = syn::parse_quote!(namespace = "myproto", discriminator_len = 16);
+        assert_eq!(
+            args.discriminator(&name),
+            hash_preimage("myproto:event:Transfer", 16)
+        );
+        assert_eq!(
+            args.idl_discriminator(&name),
+            Some(hash_preimage("myproto:event:Transfer", 16))
+        );
+
+        assert!(syn::parse_str::<EventArgs>(r#"namespace = """#).is_err());
+        assert!(syn::parse_str::<EventArgs>(r#"namespace = "my:proto""#).is_err());
+        assert!(syn::parse_str::<EventArgs>(
+            r#"namespace = "myproto", discriminator = [1, 2, 3, 4, 5, 6, 7, 8]"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn field_types() {
+        let item: syn::Item = syn::parse_quote! {
+            pub struct MyEvent {
+                pub data: u64,
+                pub values: Option<Vec<[u8; 32]>>,
+                pub pair: (Pubkey, String),
+            }
+        };
+        assert!(check_field_types(&item).is_ok());
+
+        // User-defined types with the name of an account type
+        let item: syn::Item = syn::parse_quote! { pub struct MyEvent { pub account: Account } };
+        assert!(check_field_types(&item).is_ok());
+
+        for item in [
+            syn::parse_quote! { pub struct MyEvent<'info> { pub info: AccountInfo<'info> } },
+            syn::parse_quote! {
+                pub struct MyEvent<'info> { pub signers: Vec<Signer<'info>> }
+            },
+            syn::parse_quote! { pub struct MyEvent<'a> { pub label: &'a str } },
+            syn::parse_quote! { pub struct MyEvent { pub pair: (u64, Box<dyn Fn()>) } },
+            syn::parse_quote! {
+                pub enum MyEvent<'info> { Created { info: AccountInfo<'info> } }
+            },
+        ] {
+            assert!(check_field_types(&item).is_err());
+        }
+
+        let item: syn::Item = syn::parse_quote! {
+            pub struct MyEvent<'info> {
+                pub info: AccountInfo<'info>,
+                pub data: u64,
+                pub program: Program<'info, System>,
+            }
+        };
+        assert_eq!(check_field_types(&item).unwrap_err().into_iter().count(), 2);
+    }
+
+    #[test]
+    fn borrowed_field_types() {
+        for (item, owned) in [
+            (
+                syn::parse_quote! { pub struct MyEvent<'a> { pub data: &'a [u8] } },
+                "Vec<u8>",
+            ),
+            (
+                syn::parse_quote! { pub struct MyEvent<'a> { pub label: Option<&'a str> } },
+                "String",
+            ),
+            (
+                syn::parse_quote! { pub enum MyEvent<'a> { Moved { to: &'a Pubkey } } },
+                "Pubkey",
+            ),
+        ] {
+            let err = check_field_types(&item).unwrap_err().to_string();
+            assert!(err.contains("borrowed fields"), "{err}");
+            assert!(err.contains(&format!("`{owned}`")), "{err}");
+        }
+    }
+
+    #[test]
+    fn lifetimes() {
+        let item: syn::Item = syn::parse_quote! { pub struct MyEvent<const N: usize> {} };
+        assert!(check_lifetimes(&item).is_ok());
+
+        let item: syn::Item = syn::parse_quote! {
+            pub struct MyEvent<'a, 'b, T> { pub data: Cow<'a, [u8]>, pub other: Wrapper<'b, T> }
+        };
+        let errors = check_lifetimes(&item).unwrap_err();
+        assert_eq!(errors.into_iter().count(), 2);
+
+        let item: syn::Item = syn::parse_quote! { pub enum MyEvent<'a> { Created(Cow<'a, str>) } };
+        let err = check_lifetimes(&item).unwrap_err().to_string();
+        assert!(err.contains("remove the lifetime parameter `'a`"), "{err}");
+    }
+
+    #[test]
+    fn flatten() {
+        let f = field(syn::parse_quote! {{ pub header: Header }});
+        assert!(!parse_flatten(&f).unwrap());
+
+        let f = field(syn::parse_quote! {{ #[event_flatten] pub header: Header }});
+        assert!(parse_flatten(&f).unwrap());
+
+        let f = field(syn::parse_quote! {{ #[event_flatten(all)] pub header: Header }});
+        assert!(parse_flatten(&f).is_err());
+
+        let f = field(syn::parse_quote! {{ #[event_flatten] #[index] pub header: Header }});
+        assert!(parse_flatten(&f).is_err());
+    }
+
+    #[test]
+    fn sequence() {
+        let mut strct: syn::ItemStruct = syn::parse_quote! {
+            pub struct MyEvent {
+                pub data: u64,
+            }
+        };
+        add_sequence_field(&mut strct).unwrap();
+        let names = strct
+            .fields
+            .iter()
+            .map(|field| field.ident.as_ref().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["sequence", "data"]);
+
+        assert!(add_sequence_field(&mut strct).is_err());
+
+        let mut strct: syn::ItemStruct = syn::parse_quote!(
+            pub struct MyEvent(u64);
+        );
+        assert!(add_sequence_field(&mut strct).is_err());
+    }
+
+    #[test]
+    fn optional_trailing() {
+        let strct: syn::ItemStruct = syn::parse_quote! {
+            pub struct MyEvent {
+                pub data: u64,
+                #[optional_trailing]
+                pub fee: u64,
+                #[optional_trailing]
+                pub memo: Option<String>,
+            }
+        };
+        assert_eq!(parse_optional_trailing(&strct.fields).unwrap(), Some(1));
+
+        let strct: syn::ItemStruct = syn::parse_quote! {
+            pub struct MyEvent(u64, #[optional_trailing] u64);
+        };
+        assert_eq!(parse_optional_trailing(&strct.fields).unwrap(), Some(1));
+
+        let strct: syn::ItemStruct = syn::parse_quote! {
+            pub struct MyEvent {
+                pub data: u64,
+            }
+        };
+        assert_eq!(parse_optional_trailing(&strct.fields).unwrap(), None);
+
+        let strct: syn::ItemStruct = syn::parse_quote! {
+            pub struct MyEvent {
+                #[optional_trailing]
+                pub fee: u64,
+                pub data: u64,
+            }
+        };
+        assert!(parse_optional_trailing(&strct.fields).is_err());
+
+        let strct: syn::ItemStruct = syn::parse_quote! {
+            pub struct MyEvent {
+                #[optional_trailing(default = 1)]
+                pub fee: u64,
+            }
+        };
+        assert!(parse_optional_trailing(&strct.fields).is_err());
+    }
+
+    #[test]
+    fn timestamp() {
+        let mut strct: syn::ItemStruct = syn::parse_quote! {
+            pub struct MyEvent {
+                pub data: u64,
+            }
+        };
+        add_sequence_field(&mut strct).unwrap();
+        add_timestamp_field(&mut strct).unwrap();
+        let names = strct
+            .fields
+            .iter()
+            .map(|field| field.ident.as_ref().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["sequence", "data", "emitted_at"]);
+
+        assert!(add_timestamp_field(&mut strct).is_err());
+
+        assert!(syn::parse_str::<EventArgs>("timestamp").unwrap().timestamp);
+        assert!(syn::parse_str::<EventArgs>("timestamp, zero_copy").is_err());
+        assert!(syn::parse_str::<EventArgs>("no_serde, timestamp").is_err());
+    }
+
+    #[test]
+    fn size() {
+        let item: syn::Item = syn::parse_quote! {
+            pub struct MyEvent {
+                pub authority: Pubkey,
+                pub amount: u64,
+                pub flags: [u8; 16],
+                pub limit: Option<(u32, i16)>,
+            }
+        };
+        assert_eq!(
+            estimate_size(&item, &EventArgs::default()),
+            EventSize::fixed(8 + 32 + 8 + 16 + 7)
+        );
+
+        let args: EventArgs = syn::parse_quote!(discriminator_len = 16, version = 1);
+        assert_eq!(
+            estimate_size(&item, &args),
+            EventSize::fixed(16 + 1 + 32 + 8 + 16 + 7)
+        );
+
+        let args: EventArgs = syn::parse_quote!(correlated);
+        assert_eq!(
+            estimate_size(&item, &args),
+            EventSize::fixed(8 + 17 + 32 + 8 + 16 + 7)
+        );
+
+        let item: syn::Item = syn::parse_quote! {
+            pub struct MyEvent {
+                pub data: u64,
+                pub label: String,
+                pub values: Vec<[u8; 32]>,
+            }
+        };
+        assert_eq!(
+            estimate_size(&item, &EventArgs::default()),
+            EventSize::dynamic(8 + 8 + 4 + 4)
+        );
+
+        let item: syn::Item = syn::parse_quote! {
+            pub enum MyEvent {
+                Created { id: u64, authority: Pubkey },
+                Closed(u64),
+                Custom(MyData),
+            }
+        };
+        assert_eq!(
+            estimate_size(&item, &EventArgs::default()),
+            EventSize::dynamic(8 + 1 + 40)
+        );
+    }
+
+    #[test]
+    fn feature() {
+        assert_eq!(EventArgs::default().feature, None);
+
+        let args: EventArgs = syn::parse_quote!(feature = "telemetry", version = 1);
+        assert_eq!(args.feature.as_deref(), Some("telemetry"));
+
+        assert!(syn::parse_str::<EventArgs>("feature = \"\"").is_err());
+        assert!(syn::parse_str::<EventArgs>("feature = telemetry").is_err());
+    }
+
+    #[test]
+    fn idl_discriminator_const_name() {
+        let name: Ident = syn::parse_quote!(Transfer);
+
+        let args: EventArgs = syn::parse_quote!();
+        assert_eq!(
+            args.idl_discriminator_const_name(&name),
+            "TRANSFER_DISCRIMINATOR"
+        );
+
+        let args: EventArgs = syn::parse_quote!(idl_name = "TransferCompleted");
+        assert_eq!(
+            args.idl_discriminator_const_name(&name),
+            "TRANSFER_COMPLETED_DISCRIMINATOR"
+        );
+    }
+}
@@ -2,6 +2,7 @@ pub mod accounts;
 pub mod context;
 pub mod docs;
 pub mod error;
+pub mod event;
 pub mod program;
 pub mod spl_interface;
 
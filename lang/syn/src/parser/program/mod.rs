@@ -1,7 +1,9 @@
 use crate::parser::docs;
-use crate::Program;
-use syn::parse::{Error as ParseError, Result as ParseResult};
+use crate::{Program, ProgramArgs};
+use syn::ext::IdentExt;
+use syn::parse::{Error as ParseError, ParseStream, Result as ParseResult};
 use syn::spanned::Spanned;
+use syn::{Ident, LitInt, Token};
 
 mod instructions;
 
@@ -14,9 +16,38 @@ pub fn parse(program_mod: syn::ItemMod) -> ParseResult<Program> {
         docs,
         program_mod,
         fallback_fn,
+        args: ProgramArgs::default(),
     })
 }
 
+pub fn parse_args(stream: ParseStream) -> ParseResult<ProgramArgs> {
+    let mut args = ProgramArgs::default();
+    while !stream.is_empty() {
+        let key = stream.call(Ident::parse_any)?;
+        match key.to_string().as_str() {
+            "max_events" => {
+                stream.parse::<Token![=]>()?;
+                let lit = stream.parse::<LitInt>()?;
+                let max_events = lit.base10_parse::<u32>()?;
+                if max_events == 0 {
+                    return Err(ParseError::new(
+                        lit.span(),
+                        "max_events must be greater than 0",
+                    ));
+                }
+                args.max_events = Some(max_events);
+            }
+            _ => return Err(ParseError::new(key.span(), "Invalid program argument")),
+        }
+
+        if !stream.is_empty() {
+            stream.parse::<Token![,]>()?;
+        }
+    }
+
+    Ok(args)
+}
+
 fn ctx_accounts_ident(path_ty: &syn::PatType) -> ParseResult<proc_macro2::Ident> {
     let p = match &*path_ty.ty {
         syn::Type::Path(p) => &p.path,
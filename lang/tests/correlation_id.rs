@@ -0,0 +1,79 @@
+use anchor_lang::event::{split_correlation_id_ix_data, CORRELATION_ID_IX_TAG_LE};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::InstructionData;
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[derive(Accounts)]
+pub struct CheckCorrelationId {}
+
+#[program]
+pub mod correlation_id_program {
+    use super::*;
+
+    pub fn check_correlation_id(
+        ctx: Context<CheckCorrelationId>,
+        expected: Option<[u8; 16]>,
+    ) -> Result<()> {
+        require!(ctx.correlation_id == expected, ErrorCode::RequireViolated);
+        Ok(())
+    }
+}
+
+fn dispatch(data: &[u8]) -> ProgramResult {
+    entry(&ID, &[], data)
+}
+
+fn correlated(id: [u8; 16], ix: impl InstructionData) -> Vec<u8> {
+    [&CORRELATION_ID_IX_TAG_LE[..], &id, &ix.data()].concat()
+}
+
+#[test]
+fn test_dispatch_correlation_id() {
+    use correlation_id_program::instruction::CheckCorrelationId;
+
+    let ix = |expected| CheckCorrelationId { expected };
+    assert!(dispatch(&ix(None).data()).is_ok());
+    assert!(dispatch(&correlated([7; 16], ix(Some([7; 16])))).is_ok());
+    assert_eq!(
+        dispatch(&correlated([7; 16], ix(None))).unwrap_err(),
+        ProgramError::Custom(ErrorCode::RequireViolated.into())
+    );
+    // The id only applies to the instruction it prefixes
+    assert!(dispatch(&ix(None).data()).is_ok());
+
+    // Tags that aren't followed by a correlation id
+    assert_eq!(
+        dispatch(&[&CORRELATION_ID_IX_TAG_LE[..], &[7; 8]].concat()).unwrap_err(),
+        ProgramError::Custom(ErrorCode::InstructionDidNotDeserialize.into())
+    );
+    assert_eq!(
+        dispatch(&[&CORRELATION_ID_IX_TAG_LE[..], &[7; 16]].concat()).unwrap_err(),
+        ProgramError::Custom(ErrorCode::InstructionMissing.into())
+    );
+}
+
+#[test]
+fn test_split_correlation_id_ix_data() {
+    let data = [&CORRELATION_ID_IX_TAG_LE[..], &[7; 16], &[1, 2, 3]].concat();
+    assert_eq!(
+        split_correlation_id_ix_data(&data),
+        Some(([7; 16], &[1, 2, 3][..]))
+    );
+    assert_eq!(split_correlation_id_ix_data(&data[..20]), None);
+    assert_eq!(split_correlation_id_ix_data(&[1, 2, 3]), None);
+}
+
+#[test]
+fn test_cpi_context_correlation_id() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data: [u8; 0] = [];
+    let program = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &key, true, 0);
+
+    let ctx = CpiContext::new(program, CheckCorrelationId {});
+    assert_eq!(ctx.correlation_id, None);
+    let ctx = ctx.with_correlation_id([7; 16]);
+    assert_eq!(ctx.correlation_id, Some([7; 16]));
+}
@@ -0,0 +1,1553 @@
+use anchor_lang::event::{DiscriminatorMatcher, EventContext, EventDiscriminator, EventScratch};
+use anchor_lang::prelude::*;
+use anchor_lang::{Discriminator, Event};
+
+#[event]
+#[derive(Debug)]
+pub struct DefaultEvent {
+    pub data: u64,
+}
+
+#[event]
+pub struct IndexedEvent {
+    #[index]
+    pub data: u64,
+    /// The owner of the data
+    #[index(name = "owner")]
+    pub authority: Pubkey,
+}
+
+#[event(discriminator_len = 16)]
+pub struct WideEvent {
+    pub data: u64,
+}
+
+#[test]
+fn test_default_discriminator() {
+    let hash = solana_program::hash::hash(b"event:DefaultEvent").to_bytes();
+    assert_eq!(DefaultEvent::DISCRIMINATOR, hash[..8]);
+    assert_eq!(DefaultEvent::EVENT_DISCRIMINATOR, &hash[..8]);
+
+    let data = DefaultEvent { data: 1 }.data();
+    assert_eq!(data[..8], hash[..8]);
+    assert_eq!(data[8..], 1u64.to_le_bytes());
+}
+
+#[test]
+fn test_discriminator_len() {
+    let hash = solana_program::hash::hash(b"event:WideEvent").to_bytes();
+    assert_eq!(WideEvent::EVENT_DISCRIMINATOR, &hash[..16]);
+    assert_eq!(WideEvent::DISCRIMINATOR, hash[..8]);
+
+    let data = WideEvent { data: 2 }.data();
+    assert_eq!(data[..16], hash[..16]);
+    assert_eq!(data[16..], 2u64.to_le_bytes());
+}
+
+#[event(discriminator = [1, 2, 3, 4, 5, 6, 7, 8])]
+pub struct CustomDiscriminatorEvent {
+    pub data: u64,
+}
+
+#[test]
+fn test_custom_discriminator() {
+    assert_eq!(
+        CustomDiscriminatorEvent::DISCRIMINATOR,
+        [1, 2, 3, 4, 5, 6, 7, 8]
+    );
+
+    let data = CustomDiscriminatorEvent { data: 3 }.data();
+    assert_eq!(data[..8], [1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(data[8..], 3u64.to_le_bytes());
+}
+
+#[event(idl_name = "TransferCompleted")]
+pub struct Transfer {
+    pub amount: u64,
+}
+
+#[test]
+fn test_idl_name() {
+    let hash = solana_program::hash::hash(b"event:Transfer").to_bytes();
+    assert_eq!(Transfer::DISCRIMINATOR, hash[..8]);
+    assert_eq!(Transfer::EVENT_DISCRIMINATOR, &hash[..8]);
+}
+
+#[event(namespace = "myproto")]
+pub struct NamespacedEvent {
+    pub data: u64,
+}
+
+#[test]
+fn test_namespace() {
+    let hash = solana_program::hash::hash(b"myproto:event:NamespacedEvent").to_bytes();
+    assert_eq!(NamespacedEvent::DISCRIMINATOR, hash[..8]);
+    assert_eq!(NamespacedEvent::EVENT_DISCRIMINATOR, &hash[..8]);
+
+    let data = NamespacedEvent { data: 5 }.data();
+    assert_eq!(data[..8], hash[..8]);
+}
+
+#[event(zero_copy)]
+#[derive(Debug, PartialEq)]
+pub struct ZeroCopyEvent {
+    pub authority: Pubkey,
+    pub data: u64,
+    pub values: [u16; 4],
+}
+
+#[event(zero_copy, version = 1)]
+pub struct VersionedZeroCopyEvent {
+    pub data: u64,
+}
+
+mod imported_derives {
+    use super::*;
+    use bytemuck::{Pod, Zeroable};
+
+    // The derives are found by their last path segment, so they aren't derived twice
+    #[event(zero_copy)]
+    #[derive(Pod, Zeroable)]
+    pub struct ImportedDerivesEvent {
+        pub data: u64,
+    }
+}
+
+#[test]
+fn test_zero_copy_event() {
+    let event = ZeroCopyEvent {
+        authority: Pubkey::new_unique(),
+        data: 1,
+        values: [2, 3, 4, 5],
+    };
+    let data = event.data();
+    assert_eq!(data[..8], ZeroCopyEvent::DISCRIMINATOR);
+    assert_eq!(data[8..], event.try_to_vec().unwrap());
+    assert_eq!(ZeroCopyEvent::try_from_event_data(&data).unwrap(), event);
+
+    let data = VersionedZeroCopyEvent { data: 6 }.data();
+    assert_eq!(data[8], 1);
+    assert_eq!(data[9..], 6u64.to_le_bytes());
+
+    let data = imported_derives::ImportedDerivesEvent { data: 7 }.data();
+    assert_eq!(data[8..], 7u64.to_le_bytes());
+}
+
+#[event(max_size = 48)]
+pub struct SizedEvent {
+    pub authority: Pubkey,
+    pub data: u64,
+}
+
+#[test]
+fn test_max_size() {
+    let data = SizedEvent {
+        authority: Pubkey::new_unique(),
+        data: 1,
+    }
+    .data();
+    assert_eq!(data.len(), 48);
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct EventHeader {
+    pub slot: u64,
+    pub authority: Pubkey,
+}
+
+#[event]
+#[derive(Debug, PartialEq)]
+pub struct FlattenedEvent {
+    #[event_flatten]
+    pub header: EventHeader,
+    pub amount: u64,
+}
+
+#[test]
+fn test_event_flatten() {
+    let event = FlattenedEvent {
+        header: EventHeader {
+            slot: 1,
+            authority: Pubkey::new_unique(),
+        },
+        amount: 2,
+    };
+    let data = event.data();
+    // Only the IDL is flattened, the event is serialized with the nested struct
+    assert_eq!(
+        data[8..],
+        (event.header.clone(), event.amount).try_to_vec().unwrap()
+    );
+    assert_eq!(FlattenedEvent::try_from_event_data(&data).unwrap(), event);
+}
+
+#[event(sequence, version = 1)]
+pub struct SequencedEvent {
+    pub data: u64,
+}
+
+#[test]
+fn test_sequence() {
+    let data = SequencedEvent {
+        sequence: 3,
+        data: 4,
+    }
+    .data();
+    assert_eq!(data[8], 1);
+    assert_eq!(data[9..17], 3u64.to_le_bytes());
+    assert_eq!(data[17..], 4u64.to_le_bytes());
+}
+
+#[event(timestamp, sequence)]
+pub struct TimestampedEvent {
+    pub data: u64,
+}
+
+#[test]
+fn test_timestamp() {
+    // `emitted_at` is the last field, after the data
+    let event = TimestampedEvent {
+        sequence: 1,
+        data: 2,
+        emitted_at: 3,
+    };
+    let data = event.try_to_vec().unwrap();
+    assert_eq!(data[16..], 3i64.to_le_bytes());
+}
+
+#[cfg(feature = "idl-build")]
+#[test]
+fn test_timestamp_idl() {
+    use anchor_lang::anchor_syn::idl::types::IdlType;
+
+    let event = TimestampedEvent::__anchor_private_gen_idl_event(&mut Default::default()).unwrap();
+    let field = event.fields.last().unwrap();
+    assert_eq!(field.name, "emittedAt");
+    assert_eq!(field.ty, IdlType::I64);
+}
+
+#[event]
+pub struct DynamicEvent {
+    pub label: String,
+    pub values: Vec<u16>,
+}
+
+#[test]
+fn test_encoded_len() {
+    let event = DefaultEvent { data: 1 };
+    assert_eq!(event.encoded_len(), event.data().len());
+    let event = WideEvent { data: 2 };
+    assert_eq!(event.encoded_len(), 24);
+    let event = VersionedEvent { data: 3 };
+    assert_eq!(event.encoded_len(), 17);
+
+    let event = DynamicEvent {
+        label: "hello".into(),
+        values: vec![1, 2, 3],
+    };
+    assert_eq!(event.encoded_len(), 8 + 4 + 5 + 4 + 6);
+    assert_eq!(event.encoded_len(), event.data().len());
+
+    let event = ZeroCopyEvent {
+        authority: Pubkey::new_unique(),
+        data: 1,
+        values: [2, 3, 4, 5],
+    };
+    assert_eq!(event.encoded_len(), event.data().len());
+    assert_eq!(VersionedZeroCopyEvent { data: 6 }.encoded_len(), 17);
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LayoutHeader {
+    pub slot: u64,
+    pub flag: bool,
+}
+
+#[event]
+pub struct LayoutEvent {
+    pub header: LayoutHeader,
+    pub amount: i64,
+    pub authority: Pubkey,
+    pub values: [u16; 3],
+    pub items: Vec<u32>,
+    pub label: String,
+    pub memo: Option<u8>,
+}
+
+/// Snapshot of the serialized events, any change to the wire format must fail this test.
+#[test]
+fn test_event_data_layout() {
+    let data = LayoutEvent {
+        header: LayoutHeader {
+            slot: 0x0102030405060708,
+            flag: true,
+        },
+        amount: -2,
+        authority: Pubkey::new_from_array([7; 32]),
+        values: [1, 2, 0x0304],
+        items: vec![5, 6],
+        label: "hi".into(),
+        memo: Some(9),
+    }
+    .data();
+    let expected = [
+        // Discriminator, i.e. `Sha256("event:LayoutEvent")[..8]`
+        &[201, 121, 182, 135, 168, 216, 176, 109][..],
+        // header.slot
+        &[8, 7, 6, 5, 4, 3, 2, 1],
+        // header.flag
+        &[1],
+        // amount
+        &[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+        // authority
+        &[7; 32],
+        // values
+        &[1, 0, 2, 0, 4, 3],
+        // items, length prefix followed by the items
+        &[2, 0, 0, 0, 5, 0, 0, 0, 6, 0, 0, 0],
+        // label, length prefix followed by the UTF-8 bytes
+        &[2, 0, 0, 0, b'h', b'i'],
+        // memo, `Some` tag followed by the value
+        &[1, 9],
+    ]
+    .concat();
+    assert_eq!(data, expected);
+
+    let data = EnumEvent::Created { id: 3 }.data();
+    let expected = [
+        // Discriminator, i.e. `Sha256("event:EnumEvent")[..8]`
+        &[190, 9, 47, 204, 134, 180, 18, 41][..],
+        // Variant index
+        &[0],
+        // id
+        &[3, 0, 0, 0, 0, 0, 0, 0],
+    ]
+    .concat();
+    assert_eq!(data, expected);
+}
+
+#[test]
+fn test_try_from_event_data() {
+    let data = WideEvent { data: 4 }.data();
+    let event = WideEvent::try_from_event_data(&data).unwrap();
+    assert_eq!(event.data, 4);
+
+    let err = DefaultEvent::try_from_event_data(&data).unwrap_err();
+    assert_eq!(err, ErrorCode::InvalidEventDiscriminator.into());
+
+    let err = DefaultEvent::try_from_event_data(&[1, 2]).unwrap_err();
+    assert_eq!(err, ErrorCode::InvalidEventDiscriminator.into());
+}
+
+#[test]
+fn test_discriminator_matches() {
+    let data = DefaultEvent { data: 1 }.data();
+    assert!(DefaultEvent::discriminator_matches(&data));
+    assert!(DefaultEvent::discriminator_matches(&data[..8]));
+    assert!(!DefaultEvent::discriminator_matches(&data[..7]));
+    assert!(!DefaultEvent::discriminator_matches(&[]));
+    assert!(!IndexedEvent::discriminator_matches(&data));
+}
+
+#[test]
+fn test_discriminator_matcher() {
+    let mut matcher = DiscriminatorMatcher::new();
+    assert!(matcher.is_empty());
+    assert_eq!(matcher.insert_event::<DefaultEvent>("default"), None);
+    assert_eq!(matcher.insert_event::<WideEvent>("wide"), None);
+    assert_eq!(
+        matcher.insert_event::<DefaultEvent>("replaced"),
+        Some("default")
+    );
+    assert_eq!(matcher.len(), 2);
+
+    assert_eq!(
+        matcher.match_data(&DefaultEvent { data: 1 }.data()),
+        Some(&"replaced")
+    );
+    // Events with a longer discriminator are matched by their first 8 bytes
+    assert_eq!(
+        matcher.match_data(&WideEvent { data: 1 }.data()),
+        Some(&"wide")
+    );
+    assert_eq!(matcher.match_data(&IndexedEvent::DISCRIMINATOR), None);
+    assert_eq!(matcher.match_data(&DefaultEvent::DISCRIMINATOR[..7]), None);
+
+    // Many discriminators that only differ in a single byte
+    let matcher = (0..=255u8)
+        .flat_map(|byte| (0..8).map(move |i| (byte, i)))
+        .map(|(byte, i)| {
+            let mut discriminator = [0; 8];
+            discriminator[i] = byte;
+            (discriminator, (byte, i))
+        })
+        .collect::<DiscriminatorMatcher<_>>();
+    assert_eq!(matcher.len(), 255 * 8 + 1);
+    assert_eq!(matcher.get([0, 0, 0, 7, 0, 0, 0, 0]), Some(&(7, 3)));
+    assert_eq!(
+        matcher.get(EventDiscriminator([0, 0, 0, 7, 0, 0, 0, 1])),
+        None
+    );
+}
+
+#[test]
+fn test_discriminator_functions() {
+    assert_eq!(
+        anchor_lang::event_discriminator("DefaultEvent"),
+        DefaultEvent::DISCRIMINATOR
+    );
+    assert_eq!(
+        anchor_lang::event_discriminator("WideEvent"),
+        <WideEvent as Discriminator>::DISCRIMINATOR
+    );
+    assert_ne!(
+        anchor_lang::event_discriminator("DefaultEvent"),
+        anchor_lang::account_discriminator("DefaultEvent")
+    );
+}
+
+#[event]
+pub struct LabeledEvent {
+    pub data: u64,
+    pub label: String,
+}
+
+#[test]
+fn test_decode_logged_events() {
+    let logs = vec![
+        "Program log: Instruction: Example".to_string(),
+        program_data_log(&[DefaultEvent { data: 1 }.data()]),
+        program_data_log(&[WideEvent { data: 2 }.data()]),
+        program_data_log(&[DefaultEvent { data: 3 }.data()]),
+    ];
+
+    let events = anchor_lang::event::decode_logged_events::<DefaultEvent>(&logs);
+    assert_eq!(events.iter().map(|e| e.data).collect::<Vec<_>>(), [1, 3]);
+}
+
+#[test]
+fn test_encode_event_log() {
+    use anchor_lang::event::{decode_event_log, encode_event_log};
+
+    // The runtime logs the data with the padded standard base64 alphabet
+    let line = encode_event_log(&DefaultEvent { data: 5 });
+    assert_eq!(line, "Program data: nFhtdJ2xV40FAAAAAAAAAA==");
+    assert_eq!(line, program_data_log(&[DefaultEvent { data: 5 }.data()]));
+    assert_eq!(
+        encode_event_log(&DefaultEvent { data: u64::MAX }),
+        "Program data: nFhtdJ2xV43//////////w=="
+    );
+
+    for data in [0, 5, 0xfbff, u64::MAX] {
+        let line = encode_event_log(&DefaultEvent { data });
+        assert_eq!(decode_event_log::<DefaultEvent>(&line).unwrap().data, data);
+    }
+}
+
+#[test]
+fn test_decode_invalid_event_log() {
+    use anchor_lang::event::{decode_event_log, encode_event_log};
+
+    let event = DefaultEvent { data: 0xfbff };
+    for line in [
+        "Program log: nFhtdJ2xV43/+wAAAAAAAA==",
+        // URL-safe alphabet
+        "Program data: nFhtdJ2xV43_-wAAAAAAAA==",
+        // Missing padding
+        "Program data: nFhtdJ2xV43/+wAAAAAAAA",
+        "Program data: nFhtdJ2xV43/+wAAAAAAAA== ",
+        "Program data: ",
+    ] {
+        assert_eq!(
+            decode_event_log::<DefaultEvent>(line).unwrap_err(),
+            ErrorCode::InvalidEventLog.into(),
+            "{line}"
+        );
+    }
+
+    // Several events, e.g. logged with `emit_many!`
+    let line = program_data_log(&[event.data(), event.data()]);
+    assert_eq!(
+        decode_event_log::<DefaultEvent>(&line).unwrap_err(),
+        ErrorCode::InvalidEventLog.into()
+    );
+
+    assert_eq!(
+        decode_event_log::<WideEvent>(&encode_event_log(&event)).unwrap_err(),
+        ErrorCode::InvalidEventDiscriminator.into()
+    );
+}
+
+#[test]
+fn test_assert_event() {
+    let logs = vec![
+        program_data_log(&[DefaultEvent { data: 1 }.data()]),
+        program_data_log(&[LabeledEvent {
+            data: 5,
+            label: "five".to_string(),
+        }
+        .data()]),
+    ];
+
+    anchor_lang::assert_event!(&logs, DefaultEvent { data: 1 });
+    anchor_lang::assert_event!(
+        &logs,
+        LabeledEvent {
+            data: 5,
+            label: "five"
+        }
+    );
+    anchor_lang::assert_event!(&logs, LabeledEvent { data: 2 + 3, .. });
+    anchor_lang::assert_event!(&logs, LabeledEvent { .. });
+}
+
+#[test]
+#[should_panic(expected = "no logged `LabeledEvent` event matches")]
+fn test_assert_event_mismatch() {
+    let logs = vec![program_data_log(&[LabeledEvent {
+        data: 5,
+        label: "five".to_string(),
+    }
+    .data()])];
+
+    anchor_lang::assert_event!(&logs, LabeledEvent { data: 6, .. });
+}
+
+#[event]
+pub struct InterleavedEvent {
+    pub first: u64,
+    #[index]
+    pub second: Pubkey,
+    pub third: u8,
+    #[index(name = "fourth_key")]
+    #[decimals(6)]
+    pub fourth: u32,
+    #[index]
+    pub fifth: bool,
+    pub sixth: String,
+}
+
+#[event(version = 2)]
+pub struct LargeIndexedEvent {
+    pub kind: u8,
+    #[index]
+    pub owner: Pubkey,
+    pub memo: String,
+    /// Amount of the event
+    #[index]
+    pub amount: u64,
+    pub payload: Vec<u8>,
+}
+
+#[test]
+fn test_peek_indexed_fields() {
+    let owner = Pubkey::new_unique();
+    let event = LargeIndexedEvent {
+        kind: 1,
+        owner,
+        memo: "memo".to_string(),
+        amount: 7,
+        payload: vec![0; 4096],
+    };
+    let data = event.data();
+
+    let indexed = LargeIndexedEvent::peek_indexed_fields(&data).unwrap();
+    assert_eq!(indexed.owner, owner);
+    assert_eq!(indexed.amount, 7);
+
+    // The payload after the last indexed field isn't read
+    let truncated = &data[..data.len() - 1024];
+    assert!(LargeIndexedEvent::try_from_event_data(truncated).is_err());
+    let indexed = LargeIndexedEvent::peek_indexed_fields(truncated).unwrap();
+    assert_eq!(indexed.amount, 7);
+
+    assert!(LargeIndexedEvent::peek_indexed_fields(&DefaultEvent { data: 1 }.data()).is_err());
+    let indexed = IndexedEvent::peek_indexed_fields(
+        &IndexedEvent {
+            data: 3,
+            authority: owner,
+        }
+        .data(),
+    )
+    .unwrap();
+    assert_eq!((indexed.data, indexed.authority), (3, owner));
+}
+
+#[cfg(feature = "idl-build")]
+#[test]
+fn test_idl_field_order() {
+    let event = InterleavedEvent::__anchor_private_gen_idl_event(&mut Default::default()).unwrap();
+    let fields = event
+        .fields
+        .iter()
+        .map(|field| (field.name.as_str(), field.index))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        fields,
+        [
+            ("first", false),
+            ("second", true),
+            ("third", false),
+            ("fourth", true),
+            ("fifth", true),
+            ("sixth", false),
+        ]
+    );
+    assert_eq!(event.fields[3].decimals, Some(6));
+    assert_eq!(event.fields[0].decimals, None);
+}
+
+#[event]
+pub struct Batch<const N: usize> {
+    pub batch: u64,
+    pub items: [u64; N],
+}
+
+#[test]
+fn test_const_generic_event() {
+    let small = Batch::<2> {
+        batch: 1,
+        items: [1, 2],
+    };
+    let large = Batch::<4> {
+        batch: 2,
+        items: [1, 2, 3, 4],
+    };
+
+    // Instantiations share the discriminator of the type
+    assert_eq!(Batch::<2>::DISCRIMINATOR, Batch::<4>::DISCRIMINATOR);
+    assert_eq!(
+        Batch::<2>::DISCRIMINATOR,
+        anchor_lang::event_discriminator("Batch")
+    );
+
+    let data = small.data();
+    assert_eq!(data.len(), 8 + 8 + 2 * 8);
+    let decoded = Batch::<2>::try_from_event_data(&data).unwrap();
+    assert_eq!((decoded.batch, decoded.items), (1, [1, 2]));
+
+    let data = large.data();
+    assert_eq!(data.len(), 8 + 8 + 4 * 8);
+    let decoded = Batch::<4>::try_from_event_data(&data).unwrap();
+    assert_eq!((decoded.batch, decoded.items), (2, [1, 2, 3, 4]));
+    assert!(Batch::<8>::try_from_event_data(&data).is_err());
+}
+
+#[cfg(feature = "idl-build")]
+#[test]
+fn test_const_generic_event_idl() {
+    use anchor_lang::anchor_syn::idl::types::IdlType;
+
+    let event = Batch::<2>::__anchor_private_gen_idl_event(&mut Default::default()).unwrap();
+    assert_eq!(event.name, "Batch");
+    assert_eq!(
+        event.fields[1].ty,
+        IdlType::GenericLenArray(Box::new(IdlType::U64), "N".into())
+    );
+}
+
+#[event(hidden)]
+pub struct HiddenEvent {
+    pub data: u64,
+}
+
+#[test]
+fn test_hidden_event() {
+    let data = HiddenEvent { data: 4 }.data();
+    assert_eq!(&data[..8], &anchor_lang::event_discriminator("HiddenEvent"));
+    assert_eq!(HiddenEvent::try_from_event_data(&data).unwrap().data, 4);
+}
+
+#[event(discriminator_hex, namespace = "myproto")]
+pub struct HexEvent {
+    pub data: u64,
+}
+
+#[test]
+fn test_discriminator_hex() {
+    assert_eq!(
+        HexEvent::DISCRIMINATOR_HEX,
+        HexEvent::typed_discriminator().to_string()
+    );
+    assert_eq!(HexEvent::DISCRIMINATOR_HEX.len(), 16);
+    assert_eq!(
+        HexEvent::DISCRIMINATOR_HEX.parse::<EventDiscriminator>(),
+        Ok(EventDiscriminator(HexEvent::DISCRIMINATOR))
+    );
+}
+
+#[test]
+fn test_typed_discriminator() {
+    let discriminator = DefaultEvent::typed_discriminator();
+    assert_eq!(discriminator.to_bytes(), DefaultEvent::DISCRIMINATOR);
+    assert!(discriminator.matches(&DefaultEvent { data: 1 }.data()));
+    assert!(!discriminator.matches(&WideEvent { data: 1 }.data()));
+    assert!(!discriminator.matches(&discriminator.to_bytes()[..4]));
+
+    let hex = discriminator.to_string();
+    assert_eq!(hex.len(), 16);
+    assert_eq!(hex.parse::<EventDiscriminator>().unwrap(), discriminator);
+    assert_eq!(
+        format!("0x{hex}").parse::<EventDiscriminator>().unwrap(),
+        discriminator
+    );
+
+    let custom: EventDiscriminator = "0102030405060708".parse().unwrap();
+    assert_eq!(custom, CustomDiscriminatorEvent::typed_discriminator());
+    assert!("01020304".parse::<EventDiscriminator>().is_err());
+    assert!("01020304050607zz".parse::<EventDiscriminator>().is_err());
+    assert!("0102030405060\u{e9}0"
+        .parse::<EventDiscriminator>()
+        .is_err());
+}
+
+#[event(derive(Clone, PartialEq, Debug))]
+pub struct DerivedEvent {
+    pub data: u64,
+    pub label: String,
+}
+
+#[event(zero_copy, derive(Clone, Copy, PartialEq, Debug))]
+pub struct DerivedZeroCopyEvent {
+    pub data: u64,
+}
+
+#[test]
+fn test_event_derives() {
+    let event = DerivedEvent {
+        data: 1,
+        label: "label".into(),
+    };
+    let decoded = DerivedEvent::try_from_event_data(&event.data()).unwrap();
+    assert_eq!(decoded, event.clone());
+    assert_eq!(
+        format!("{event:?}"),
+        r#"DerivedEvent { data: 1, label: "label" }"#
+    );
+
+    let event = DerivedZeroCopyEvent { data: 2 };
+    let copy = event;
+    assert_eq!(copy, event);
+}
+
+/// Event with a legacy wire format that stores `amount` as a big-endian integer.
+#[event(derive(Debug, PartialEq), version = 2)]
+pub struct TrailingEvent {
+    pub amount: u64,
+    #[optional_trailing]
+    pub fee: u64,
+    #[optional_trailing]
+    pub memo: Option<String>,
+}
+
+#[test]
+fn test_optional_trailing() {
+    let event = TrailingEvent {
+        amount: 1,
+        fee: 2,
+        memo: Some("memo".into()),
+    };
+    let data = event.data();
+    assert_eq!(TrailingEvent::try_from_event_data(&data).unwrap(), event);
+
+    // Data of an older version of the event, without the fee and the memo
+    let mut data = TrailingEvent::DISCRIMINATOR.to_vec();
+    data.push(2);
+    data.extend_from_slice(&1u64.to_le_bytes());
+    let event = TrailingEvent::try_from_event_data(&data).unwrap();
+    assert_eq!(
+        event,
+        TrailingEvent {
+            amount: 1,
+            fee: 0,
+            memo: None,
+        }
+    );
+
+    // Without the memo
+    data.extend_from_slice(&2u64.to_le_bytes());
+    assert_eq!(TrailingEvent::try_from_event_data(&data).unwrap().fee, 2);
+    let mut reader = &data[9..];
+    let event = TrailingEvent::deserialize_reader(&mut reader).unwrap();
+    assert_eq!((event.fee, event.memo), (2, None));
+    assert!(TrailingEvent::deserialize_reader(&mut &data[9..13]).is_err());
+
+    // Fields that are cut off and missing required fields are still errors
+    data.pop();
+    assert!(TrailingEvent::try_from_event_data(&data).is_err());
+    assert!(TrailingEvent::try_from_event_data(&data[..12]).is_err());
+}
+
+#[event(no_serde)]
+#[derive(Debug, PartialEq)]
+pub struct LegacyEvent {
+    pub amount: u64,
+}
+
+impl AnchorSerialize for LegacyEvent {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.amount.to_be_bytes())
+    }
+}
+
+impl AnchorDeserialize for LegacyEvent {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let mut amount = [0; 8];
+        reader.read_exact(&mut amount)?;
+        Ok(Self {
+            amount: u64::from_be_bytes(amount),
+        })
+    }
+}
+
+#[test]
+fn test_no_serde_event() {
+    let event = LegacyEvent { amount: 10 };
+    let data = event.data();
+    assert_eq!(data[..8], LegacyEvent::DISCRIMINATOR);
+    assert_eq!(data[8..], 10u64.to_be_bytes());
+    assert_eq!(event.encoded_len(), 16);
+    assert_eq!(LegacyEvent::try_from_event_data(&data).unwrap(), event);
+}
+
+#[event]
+pub struct CfgEvent {
+    pub data: u64,
+    #[cfg(feature = "event-cpi")]
+    pub cpi_data: u64,
+}
+
+#[test]
+fn test_cfg_field_event() {
+    #[cfg(feature = "event-cpi")]
+    let (event, len) = (
+        CfgEvent {
+            data: 1,
+            cpi_data: 2,
+        },
+        8 + 8 + 8,
+    );
+    #[cfg(not(feature = "event-cpi"))]
+    let (event, len) = (CfgEvent { data: 1 }, 8 + 8);
+
+    let data = event.data();
+    assert_eq!(data.len(), len);
+    assert_eq!(event.encoded_len(), len);
+    let decoded = CfgEvent::try_from_event_data(&data).unwrap();
+    assert_eq!(decoded.data, 1);
+    #[cfg(feature = "event-cpi")]
+    assert_eq!(decoded.cpi_data, 2);
+}
+
+#[event]
+pub struct TupleEvent(pub u64, pub Pubkey);
+
+#[test]
+fn test_tuple_event() {
+    let key = Pubkey::new_unique();
+    let data = TupleEvent(7, key).data();
+    assert_eq!(&data[..8], TupleEvent::DISCRIMINATOR);
+    assert_eq!(data.len(), 8 + 8 + 32);
+
+    let event = TupleEvent::try_from_event_data(&data).unwrap();
+    assert_eq!(event.0, 7);
+    assert_eq!(event.1, key);
+}
+
+#[event]
+#[derive(Debug, PartialEq)]
+pub enum EnumEvent {
+    Created { id: u64 },
+    Closed(u64),
+}
+
+#[test]
+fn test_enum_event() {
+    let hash = solana_program::hash::hash(b"event:EnumEvent").to_bytes();
+    assert_eq!(EnumEvent::DISCRIMINATOR, hash[..8]);
+
+    let data = EnumEvent::Closed(5).data();
+    assert_eq!(data[..8], hash[..8]);
+    assert_eq!(data[8], 1);
+    assert_eq!(data[9..], 5u64.to_le_bytes());
+    assert_eq!(
+        EnumEvent::try_from_event_data(&data).unwrap(),
+        EnumEvent::Closed(5)
+    );
+}
+
+#[test]
+fn test_emit_many() {
+    let buf = 1;
+    emit_many!(DefaultEvent { data: buf }, WideEvent { data: 2 });
+    emit_many!([DefaultEvent { data: 3 }, DefaultEvent { data: 4 }]);
+}
+
+#[test]
+fn test_data_into() {
+    let mut buf = vec![0xff];
+    DefaultEvent { data: 1 }.data_into(&mut buf);
+    VersionedEvent { data: 2 }.data_into(&mut buf);
+    assert_eq!(
+        buf,
+        [
+            &[0xff][..],
+            &DefaultEvent { data: 1 }.data(),
+            &VersionedEvent { data: 2 }.data()
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn test_encode_event() {
+    fn encode_all<E: Event>(events: &[E]) -> Vec<Vec<u8>> {
+        events
+            .iter()
+            .map(anchor_lang::event::encode_event)
+            .collect()
+    }
+
+    assert_eq!(
+        encode_all(&[DefaultEvent { data: 1 }, DefaultEvent { data: 2 }]),
+        vec![
+            DefaultEvent { data: 1 }.data(),
+            DefaultEvent { data: 2 }.data()
+        ]
+    );
+    assert_eq!(
+        anchor_lang::event::encode_event(&VersionedEvent { data: 3 }),
+        VersionedEvent { data: 3 }.data()
+    );
+}
+
+#[test]
+fn test_event_ix_tag() {
+    let hash = solana_program::hash::hash(b"anchor:event").to_bytes();
+    assert_eq!(anchor_lang::event::EVENT_IX_TAG_BE, hash[..8]);
+    assert_eq!(
+        anchor_lang::event::EVENT_IX_TAG_LE,
+        [0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d]
+    );
+}
+
+#[event(version = 2)]
+#[derive(Debug)]
+pub struct VersionedEvent {
+    pub data: u64,
+}
+
+#[test]
+fn test_versioned_event() {
+    assert_eq!(VersionedEvent::VERSION, 2);
+    assert_eq!(VersionedEvent::EVENT_VERSION, Some(2));
+    assert_eq!(DefaultEvent::EVENT_VERSION, None);
+
+    let data = VersionedEvent { data: 6 }.data();
+    assert_eq!(data[..8], VersionedEvent::DISCRIMINATOR);
+    assert_eq!(data[8], 2);
+    assert_eq!(data[9..], 6u64.to_le_bytes());
+    assert_eq!(
+        VersionedEvent::try_version_from_event_data(&data).unwrap(),
+        Some(2)
+    );
+    assert_eq!(VersionedEvent::try_from_event_data(&data).unwrap().data, 6);
+
+    let mut other_version = data.clone();
+    other_version[8] = 1;
+    assert_eq!(
+        VersionedEvent::try_version_from_event_data(&other_version).unwrap(),
+        Some(1)
+    );
+    let err = VersionedEvent::try_from_event_data(&other_version).unwrap_err();
+    assert_eq!(err, ErrorCode::InvalidEventVersion.into());
+
+    let data = DefaultEvent { data: 7 }.data();
+    assert_eq!(
+        DefaultEvent::try_version_from_event_data(&data).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_decode_versioned() {
+    let data = VersionedEvent { data: 8 }.data();
+    let (version, event) = VersionedEvent::decode_versioned(&data).unwrap();
+    assert_eq!((version, event.data), (2, 8));
+
+    let mut other_version = data.clone();
+    other_version[8] = 3;
+    let err = VersionedEvent::decode_versioned(&other_version).unwrap_err();
+    assert_eq!(err, ErrorCode::InvalidEventVersion.into());
+    let err = VersionedEvent::decode_versioned(&data[..8]).unwrap_err();
+    assert_eq!(err, ErrorCode::InvalidEventVersion.into());
+
+    // Unversioned events have no version to return
+    let err = DefaultEvent::decode_versioned(&DefaultEvent { data: 9 }.data()).unwrap_err();
+    assert_eq!(err, ErrorCode::InvalidEventVersion.into());
+}
+
+#[test]
+fn test_emit_with() {
+    let mut logs = vec![];
+    DefaultEvent { data: 8 }.emit_with(|data| logs.push(data.concat()));
+    VersionedEvent { data: 9 }.emit_with(|data| logs.push(data.concat()));
+    assert_eq!(
+        logs,
+        vec![
+            DefaultEvent { data: 8 }.data(),
+            VersionedEvent { data: 9 }.data()
+        ]
+    );
+}
+
+#[event(correlated, version = 2)]
+#[derive(Debug)]
+pub struct CorrelatedEvent {
+    pub data: u64,
+}
+
+#[event(correlated, zero_copy)]
+pub struct CorrelatedZeroCopyEvent {
+    pub data: u64,
+}
+
+#[test]
+fn test_correlation_id() {
+    use anchor_lang::error::ErrorCode;
+    use anchor_lang::event::{EventSink, InstructionEvents};
+
+    assert!(CorrelatedEvent::EVENT_CORRELATED);
+    assert!(!DefaultEvent::EVENT_CORRELATED);
+
+    // Without an id, the slot is `None`
+    let data = CorrelatedEvent { data: 9 }.data();
+    assert_eq!(
+        data,
+        [
+            &CorrelatedEvent::DISCRIMINATOR[..],
+            &[0, 2],
+            &9u64.to_le_bytes()
+        ]
+        .concat()
+    );
+    assert_eq!(
+        CorrelatedEvent::correlation_id_from_event_data(&data).unwrap(),
+        None
+    );
+    assert_eq!(CorrelatedEvent::try_from_event_data(&data).unwrap().data, 9);
+
+    // The slot follows the discriminator, and precedes the version
+    let mut data = vec![];
+    CorrelatedEvent { data: 9 }.correlated_data_into(&mut data, Some([1; 16]));
+    assert_eq!(
+        data,
+        [
+            &CorrelatedEvent::DISCRIMINATOR[..],
+            &[1],
+            &[1; 16],
+            &[2],
+            &9u64.to_le_bytes()
+        ]
+        .concat()
+    );
+    assert_eq!(
+        CorrelatedEvent::correlation_id_from_event_data(&data).unwrap(),
+        Some([1; 16])
+    );
+    assert_eq!(CorrelatedEvent::try_from_event_data(&data).unwrap().data, 9);
+    assert_eq!(
+        CorrelatedEvent::try_version_from_event_data(&data).unwrap(),
+        Some(2)
+    );
+
+    // Events that are not correlated are not affected
+    let mut data = vec![];
+    DefaultEvent { data: 8 }.correlated_data_into(&mut data, Some([1; 16]));
+    assert_eq!(data, DefaultEvent { data: 8 }.data());
+
+    // The emit paths that are given an id serialize it
+    let event = CorrelatedEvent { data: 7 };
+    let mut correlated = vec![];
+    event.correlated_data_into(&mut correlated, Some([3; 16]));
+    let mut logs = vec![];
+    event.emit_with_correlation_id(Some([3; 16]), |data| logs.push(data.concat()));
+    assert_eq!(logs, vec![correlated.clone()]);
+    assert_eq!(event.encoded_len(), event.data().len());
+
+    let event = CorrelatedZeroCopyEvent { data: 7 };
+    let mut data = vec![];
+    event.correlated_data_into(&mut data, Some([3; 16]));
+    assert_eq!(data[8..25], [&[1], &[3; 16][..]].concat());
+    assert_eq!(data[25..], 7u64.to_le_bytes());
+    assert_eq!(event.encoded_len(), event.data().len());
+
+    // The macros that take the context serialize its id
+    let program_id = Pubkey::new_unique();
+    let mut accounts = EmitOnSuccess {};
+    let events = InstructionEvents::default();
+    let ctx = Context::new(&program_id, &mut accounts, &[], EmitOnSuccessBumps {})
+        .with_events(&events)
+        .with_correlation_id([3; 16]);
+    assert_eq!(EventSink::correlation_id(&ctx), Some([3; 16]));
+    emit_on_success!(ctx, CorrelatedEvent { data: 7 });
+    assert_eq!(events.deferred(), vec![correlated]);
+
+    // Events that are not correlated never have a slot
+    let data = DefaultEvent { data: 1 }.data();
+    assert_eq!(
+        DefaultEvent::correlation_id_from_event_data(&data).unwrap(),
+        None
+    );
+
+    // Invalid and truncated slots
+    for slot in [&[2][..], &[1; 8], &[]] {
+        let data = [&CorrelatedEvent::DISCRIMINATOR[..], slot].concat();
+        assert_eq!(
+            CorrelatedEvent::correlation_id_from_event_data(&data).unwrap_err(),
+            ErrorCode::InvalidEventCorrelationId.into()
+        );
+        assert_eq!(
+            CorrelatedEvent::try_from_event_data(&data).unwrap_err(),
+            ErrorCode::InvalidEventCorrelationId.into()
+        );
+    }
+}
+
+#[test]
+fn test_event_emitter() {
+    use anchor_lang::event::{EventEmitter, EventRecorder, EventSink, LogEmitter};
+
+    fn shared_logic(events: &dyn EventSink, data: u64) {
+        events.emit(&DefaultEvent { data });
+        emit!(events, VersionedEvent { data: data + 1 });
+    }
+
+    let recorder = EventRecorder::default();
+    shared_logic(&recorder, 10);
+    recorder.emit(&DefaultEvent { data: 12 });
+    emit!(recorder, DefaultEvent { data: 13 });
+    assert_eq!(
+        recorder.events(),
+        vec![
+            DefaultEvent { data: 10 }.data(),
+            VersionedEvent { data: 11 }.data(),
+            DefaultEvent { data: 12 }.data(),
+            DefaultEvent { data: 13 }.data(),
+        ]
+    );
+    assert_eq!(
+        recorder
+            .decode::<DefaultEvent>()
+            .iter()
+            .map(|event| event.data)
+            .collect::<Vec<_>>(),
+        vec![10, 12, 13]
+    );
+
+    shared_logic(&LogEmitter, 14);
+}
+
+#[derive(Accounts)]
+pub struct EmitOnSuccess {}
+
+/// Records the data logged with `sol_log_data`, which is shared by all tests of this file.
+static LOGGED_DATA: std::sync::Mutex<Vec<Vec<Vec<u8>>>> = std::sync::Mutex::new(Vec::new());
+
+struct LogDataStubs;
+
+/// Unix timestamp of the `Clock` sysvar of [`LogDataStubs`].
+const STUB_UNIX_TIMESTAMP: i64 = 1_700_000_000;
+
+impl anchor_lang::solana_program::program_stubs::SyscallStubs for LogDataStubs {
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let fields = fields.iter().map(|field| field.to_vec()).collect();
+        LOGGED_DATA.lock().unwrap().push(fields);
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: STUB_UNIX_TIMESTAMP,
+            ..Default::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        anchor_lang::solana_program::entrypoint::SUCCESS
+    }
+}
+
+#[test]
+fn test_emit_timestamp() {
+    anchor_lang::solana_program::program_stubs::set_syscall_stubs(Box::new(LogDataStubs));
+
+    emit!(TimestampedEvent {
+        sequence: 7,
+        data: 8,
+        emitted_at: 0,
+    });
+
+    let logged = LOGGED_DATA.lock().unwrap().clone();
+    let event = logged
+        .iter()
+        .filter_map(|fields| TimestampedEvent::try_from_event_data(&fields[0]).ok())
+        .find(|event| event.sequence == 7)
+        .unwrap();
+    assert_eq!(event.emitted_at, STUB_UNIX_TIMESTAMP);
+    assert_eq!(event.data, 8);
+
+    // Only the emit macros set the timestamp, serializing the event keeps it as is
+    let event = TimestampedEvent {
+        sequence: 9,
+        data: 10,
+        emitted_at: 11,
+    };
+    assert_eq!(event.data()[24..], 11i64.to_le_bytes());
+    let mut data = vec![];
+    event.emit_data_into(&mut data, None);
+    assert_eq!(data[24..], STUB_UNIX_TIMESTAMP.to_le_bytes());
+}
+
+#[test]
+fn test_emit_for() {
+    anchor_lang::solana_program::program_stubs::set_syscall_stubs(Box::new(LogDataStubs));
+
+    let key = Pubkey::new_unique();
+    let (mut lamports, mut data) = (0, vec![]);
+    let owner = Pubkey::default();
+    let account = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+
+    emit_for!(account, DefaultEvent { data: 1 });
+    emit_for!(&account, DefaultEvent { data: 2 });
+    emit_for!(key, DefaultEvent { data: 3 });
+
+    let logged = LOGGED_DATA
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|fields| fields.first() == Some(&key.to_bytes().to_vec()))
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        logged,
+        (1..=3)
+            .map(|data| vec![key.to_bytes().to_vec(), DefaultEvent { data }.data()])
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_emit_borrowed() {
+    use anchor_lang::event::EventRecorder;
+
+    anchor_lang::solana_program::program_stubs::set_syscall_stubs(Box::new(LogDataStubs));
+
+    // `DefaultEvent` isn't `Clone`, so the event must not be moved by any of the macros
+    let event = DefaultEvent { data: 0xe0_0001 };
+    let event_ref = &event;
+    emit!(event);
+    emit!(&event);
+    emit!(event_ref);
+    emit_many!(event, &event);
+
+    let logged = LOGGED_DATA
+        .lock()
+        .unwrap()
+        .iter()
+        .flatten()
+        .filter(|data| **data == event.data())
+        .count();
+    assert_eq!(logged, 5);
+
+    let recorder = EventRecorder::default();
+    emit!(recorder, event);
+    emit!(&recorder, &event);
+    assert_eq!(recorder.events(), vec![event.data(), event.data()]);
+}
+
+#[test]
+fn test_emit_raw() {
+    anchor_lang::solana_program::program_stubs::set_syscall_stubs(Box::new(LogDataStubs));
+
+    let payload = DefaultEvent { data: 1 }.try_to_vec().unwrap();
+    emit_raw!(DefaultEvent::DISCRIMINATOR, payload);
+    emit_raw!(b"rawevent", &payload[..]);
+    emit_raw!([0xff, 0xfe, 0xfd, 0xfc, 0xfb, 0xfa, 0xf9, 0xf8], [1, 2, 3]);
+
+    let logged = LOGGED_DATA.lock().unwrap().clone();
+    assert!(logged.contains(&vec![DefaultEvent { data: 1 }.data()]));
+    assert!(logged.contains(&vec![[&b"rawevent"[..], &payload].concat()]));
+    assert!(logged.contains(&vec![vec![
+        0xff, 0xfe, 0xfd, 0xfc, 0xfb, 0xfa, 0xf9, 0xf8, 1, 2, 3
+    ]]));
+}
+
+#[test]
+fn test_emit_on_success() {
+    anchor_lang::solana_program::program_stubs::set_syscall_stubs(Box::new(LogDataStubs));
+
+    fn handler(ctx: Context<EmitOnSuccess>, data: u64) -> Result<()> {
+        emit_on_success!(DefaultEvent { data });
+        helper(&ctx, data + 1);
+        Ok(())
+    }
+
+    fn helper(ctx: &Context<EmitOnSuccess>, data: u64) {
+        emit_on_success!(ctx, VersionedEvent { data });
+    }
+
+    let program_id = Pubkey::new_unique();
+    let mut accounts = EmitOnSuccess {};
+    let events = anchor_lang::event::InstructionEvents::default();
+    let ctx =
+        Context::new(&program_id, &mut accounts, &[], EmitOnSuccessBumps {}).with_events(&events);
+    handler(ctx, 31).unwrap();
+
+    let deferred = vec![
+        DefaultEvent { data: 31 }.data(),
+        VersionedEvent { data: 32 }.data(),
+    ];
+    assert_eq!(events.deferred(), deferred);
+    let is_logged = |data: &Vec<u8>| LOGGED_DATA.lock().unwrap().contains(&vec![data.clone()]);
+    assert!(!deferred.iter().any(is_logged));
+
+    events.finish().unwrap();
+    assert!(deferred.iter().all(is_logged));
+}
+
+declare_events!(
+    DefaultEvent,
+    IndexedEvent,
+    WideEvent,
+    CustomDiscriminatorEvent,
+    EnumEvent,
+    VersionedEvent,
+);
+
+#[test]
+fn test_discriminators_collide() {
+    use anchor_lang::__private::discriminators_collide;
+
+    assert!(discriminators_collide(&[1, 2, 3], &[1, 2, 3]));
+    assert!(discriminators_collide(&[1, 2], &[1, 2, 3]));
+    assert!(!discriminators_collide(&[1, 2, 3], &[1, 2, 4]));
+    assert!(!discriminators_collide(
+        DefaultEvent::EVENT_DISCRIMINATOR,
+        WideEvent::EVENT_DISCRIMINATOR
+    ));
+}
+
+mod other {
+    use anchor_lang::prelude::*;
+
+    #[event]
+    #[derive(Debug, PartialEq)]
+    pub struct Event {
+        pub label: String,
+    }
+}
+
+generate_event_enum! {
+    #[derive(Debug)]
+    pub enum ProgramEvent {
+        /// Variants can be documented
+        DefaultEvent,
+        Versioned(VersionedEvent),
+        Enum(EnumEvent),
+        Other(other::Event),
+    }
+}
+
+#[test]
+fn test_generate_event_enum() {
+    let data = DefaultEvent { data: 10 }.data();
+    assert!(matches!(
+        ProgramEvent::decode(&data),
+        Some(ProgramEvent::DefaultEvent(DefaultEvent { data: 10 }))
+    ));
+
+    let data = VersionedEvent { data: 11 }.data();
+    assert!(matches!(
+        ProgramEvent::decode(&data),
+        Some(ProgramEvent::Versioned(VersionedEvent { data: 11 }))
+    ));
+
+    let data = EnumEvent::Closed(12).data();
+    assert!(matches!(
+        ProgramEvent::decode(&data),
+        Some(ProgramEvent::Enum(EnumEvent::Closed(12)))
+    ));
+
+    let data = other::Event {
+        label: "hello".into(),
+    }
+    .data();
+    match ProgramEvent::decode(&data) {
+        Some(ProgramEvent::Other(event)) => assert_eq!(event.label, "hello"),
+        event => panic!("Unexpected event: {event:?}"),
+    }
+
+    assert!(ProgramEvent::decode(&WideEvent { data: 13 }.data()).is_none());
+    assert!(ProgramEvent::decode(&DefaultEvent::DISCRIMINATOR).is_none());
+}
+
+#[test]
+fn test_event_enum_from() {
+    let events: Vec<ProgramEvent> = vec![
+        DefaultEvent { data: 14 }.into(),
+        VersionedEvent { data: 15 }.into(),
+        ProgramEvent::from(EnumEvent::Closed(16)),
+    ];
+    assert!(matches!(
+        events.as_slice(),
+        [
+            ProgramEvent::DefaultEvent(DefaultEvent { data: 14 }),
+            ProgramEvent::Versioned(VersionedEvent { data: 15 }),
+            ProgramEvent::Enum(EnumEvent::Closed(16)),
+        ]
+    ));
+}
+
+fn program_data_log(events: &[Vec<u8>]) -> String {
+    use anchor_lang::__private::base64::{engine::general_purpose::STANDARD, Engine};
+
+    let segments = events
+        .iter()
+        .map(|event| STANDARD.encode(event))
+        .collect::<Vec<_>>();
+    format!("Program data: {}", segments.join(" "))
+}
+
+/// Records the events passed to the handler as `(slot, event)`.
+#[derive(Default)]
+struct Indexer {
+    events: Vec<(u64, String)>,
+}
+
+impl ProgramEventHandler for Indexer {
+    fn on_default_event(&mut self, event: DefaultEvent, ctx: &EventContext) {
+        self.events
+            .push((ctx.slot, format!("default {}", event.data)));
+    }
+
+    fn on_versioned(&mut self, event: VersionedEvent, ctx: &EventContext) {
+        self.events
+            .push((ctx.slot, format!("versioned {}", event.data)));
+    }
+
+    fn on_enum(&mut self, event: EnumEvent, ctx: &EventContext) {
+        self.events.push((ctx.slot, format!("enum {event:?}")));
+    }
+
+    fn on_other(&mut self, event: other::Event, ctx: &EventContext) {
+        self.events
+            .push((ctx.slot, format!("other {} {}", event.label, ctx.signature)));
+    }
+}
+
+#[test]
+fn test_event_enum_dispatch() {
+    let logs = vec![
+        "Program 11111111111111111111111111111111 invoke [1]".to_string(),
+        program_data_log(&[DefaultEvent { data: 1 }.data()]),
+        "Program log: Instruction: Emit".to_string(),
+        program_data_log(&[
+            VersionedEvent { data: 2 }.data(),
+            WideEvent { data: 3 }.data(),
+            EnumEvent::Closed(4).data(),
+        ]),
+        program_data_log(&[other::Event {
+            label: "hello".into(),
+        }
+        .data()]),
+        "Program data: not base64!".to_string(),
+    ];
+
+    assert!(matches!(
+        ProgramEvent::from_log_line(&logs[1]),
+        Some(ProgramEvent::DefaultEvent(DefaultEvent { data: 1 }))
+    ));
+    assert!(matches!(
+        ProgramEvent::from_log_line(&logs[3]),
+        Some(ProgramEvent::Versioned(VersionedEvent { data: 2 }))
+    ));
+    assert!(ProgramEvent::from_log_line(&logs[2]).is_none());
+    assert!(ProgramEvent::from_log_line(&logs[5]).is_none());
+
+    let ctx = EventContext {
+        slot: 7,
+        signature: "sig",
+    };
+    let mut indexer = Indexer::default();
+    ProgramEvent::dispatch(&ctx, &logs, &mut indexer);
+    assert_eq!(
+        indexer.events,
+        vec![
+            (7, "default 1".to_string()),
+            (7, "versioned 2".to_string()),
+            (7, "enum Closed(4)".to_string()),
+            (7, "other hello sig".to_string()),
+        ]
+    );
+
+    // Decoding into a reused scratch yields the same events
+    let mut scratch = EventScratch::new();
+    assert!(matches!(
+        ProgramEvent::from_log_line_with(&logs[3], &mut scratch),
+        Some(ProgramEvent::Versioned(VersionedEvent { data: 2 }))
+    ));
+    assert!(ProgramEvent::from_log_line_with(&logs[5], &mut scratch).is_none());
+    let mut scratch_indexer = Indexer::default();
+    ProgramEvent::dispatch_with(&ctx, &logs, &mut scratch_indexer, &mut scratch);
+    assert_eq!(scratch_indexer.events, indexer.events);
+}
+
+// Adding `WideEvent` to the events adds the required `on_wide` method to the handler
+generate_event_enum! {
+    pub enum ExtendedProgramEvent {
+        DefaultEvent,
+        Wide(WideEvent),
+    }
+}
+
+impl ExtendedProgramEventHandler for Indexer {
+    fn on_default_event(&mut self, event: DefaultEvent, ctx: &EventContext) {
+        self.events
+            .push((ctx.slot, format!("default {}", event.data)));
+    }
+
+    fn on_wide(&mut self, event: WideEvent, ctx: &EventContext) {
+        self.events.push((ctx.slot, format!("wide {}", event.data)));
+    }
+}
+
+#[test]
+fn test_event_enum_dispatch_new_event() {
+    let logs = vec![program_data_log(&[
+        DefaultEvent { data: 1 }.data(),
+        WideEvent { data: 2 }.data(),
+    ])];
+    let ctx = EventContext {
+        slot: 8,
+        signature: "sig",
+    };
+
+    let mut indexer = Indexer::default();
+    ExtendedProgramEvent::dispatch(&ctx, &logs, &mut indexer);
+    assert_eq!(
+        indexer.events,
+        vec![(8, "default 1".to_string()), (8, "wide 2".to_string())]
+    );
+
+    // The handler of the original events skips the new event
+    let mut indexer = Indexer::default();
+    ProgramEvent::dispatch(&ctx, &logs, &mut indexer);
+    assert_eq!(indexer.events, vec![(8, "default 1".to_string())]);
+}
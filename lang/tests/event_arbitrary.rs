@@ -0,0 +1,53 @@
+#![cfg(feature = "arbitrary")]
+
+use anchor_lang::__private::arbitrary::{Arbitrary, Unstructured};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::Event;
+
+#[event(arbitrary)]
+#[derive(Debug, PartialEq)]
+pub struct ArbitraryEvent {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub label: String,
+    pub values: Vec<i16>,
+    pub id: Option<[u8; 4]>,
+    #[cfg(not(feature = "event-cpi"))]
+    pub non_cpi_data: u64,
+}
+
+#[event(arbitrary, version = 1)]
+#[derive(Debug, PartialEq)]
+pub enum ArbitraryEnumEvent {
+    Created { authority: Pubkey },
+    Updated(u32, Option<u64>),
+    Closed,
+}
+
+/// Returns deterministic pseudo-random bytes for the given seed.
+fn random_bytes(seed: u64) -> Vec<u8> {
+    (0..8u64)
+        .flat_map(|i| hashv(&[&seed.to_le_bytes(), &i.to_le_bytes()]).to_bytes())
+        .collect()
+}
+
+#[test]
+fn test_arbitrary_event_round_trip() {
+    for seed in 0..256 {
+        let bytes = random_bytes(seed);
+        let mut u = Unstructured::new(&bytes);
+
+        let event = ArbitraryEvent::arbitrary(&mut u).unwrap();
+        assert_eq!(
+            ArbitraryEvent::try_from_event_data(&event.data()).unwrap(),
+            event
+        );
+
+        let event = ArbitraryEnumEvent::arbitrary(&mut u).unwrap();
+        assert_eq!(
+            ArbitraryEnumEvent::try_from_event_data(&event.data()).unwrap(),
+            event
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use anchor_lang::error::ErrorCode;
+use anchor_lang::event::{
+    compress_event_data, decompress_event_data, decompress_event_data_with, EventCodec,
+    IdentityCodec, ZeroRunCodec,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::Event;
+
+#[event]
+pub struct Swapped {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub referrer: Option<Pubkey>,
+    pub route: Vec<u16>,
+}
+
+#[event(version = 2, discriminator_len = 16)]
+pub struct VersionedSwapped {
+    pub amount: u64,
+}
+
+/// Codec that flips the bits of every byte, to check that custom codecs are supported.
+struct NotCodec;
+
+impl EventCodec for NotCodec {
+    const ID: u8 = 128;
+
+    fn encode(&self, data: &[u8], out: &mut Vec<u8>) {
+        out.extend(data.iter().map(|byte| !byte));
+    }
+
+    fn decode(&self, data: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        out.extend(data.iter().map(|byte| !byte));
+        Ok(())
+    }
+}
+
+fn swapped() -> Swapped {
+    Swapped {
+        authority: Pubkey::new_from_array([7; 32]),
+        amount: 250,
+        fee: 0,
+        referrer: None,
+        route: vec![1, 2, 3],
+    }
+}
+
+#[test]
+fn test_zero_run_codec() {
+    let data = [[1, 0, 0, 2, 0].as_slice(), &[0; 300], &[3]].concat();
+    let mut compressed = vec![];
+    ZeroRunCodec.encode(&data, &mut compressed);
+    assert_eq!(compressed, [1, 0, 2, 2, 0, 255, 0, 46, 3]);
+
+    let mut decompressed = vec![];
+    ZeroRunCodec.decode(&compressed, &mut decompressed).unwrap();
+    assert_eq!(decompressed, data);
+
+    // A zero byte must be followed by the length of its run
+    for invalid in [&[1, 0][..], &[0, 0]] {
+        assert_eq!(
+            ZeroRunCodec.decode(invalid, &mut vec![]).unwrap_err(),
+            ErrorCode::InvalidCompressedEvent.into()
+        );
+    }
+}
+
+#[test]
+fn test_compress_event_data() {
+    let event = swapped();
+    let data = compress_event_data(&event, &ZeroRunCodec);
+    assert_eq!(&data[..8], &Swapped::DISCRIMINATOR);
+    assert_eq!(data[8], ZeroRunCodec::ID);
+
+    // The zeros of the amount, the fee, the `None` referrer and the route length shrink, while
+    // the single zeros of the route grow
+    assert_eq!(event.encoded_len(), 8 + 32 + 8 + 8 + 1 + 4 + 6);
+    assert_eq!(data.len(), 8 + 1 + 32 + (1 + 2) + (1 + 2) + 9);
+
+    let decompressed = decompress_event_data::<Swapped>(&data).unwrap();
+    assert_eq!(decompressed, event.data());
+    let decoded = Swapped::try_from_event_data(&decompressed).unwrap();
+    assert_eq!((decoded.amount, decoded.route), (250, vec![1, 2, 3]));
+
+    let data = compress_event_data(&event, &IdentityCodec);
+    assert_eq!(data.len(), event.encoded_len() + 1);
+    assert_eq!(
+        decompress_event_data::<Swapped>(&data).unwrap(),
+        event.data()
+    );
+}
+
+#[test]
+fn test_compress_versioned_event_data() {
+    let event = VersionedSwapped { amount: 1 };
+    let data = compress_event_data(&event, &ZeroRunCodec);
+    assert_eq!(&data[..16], VersionedSwapped::EVENT_DISCRIMINATOR);
+    assert_eq!(data[16..], [ZeroRunCodec::ID, 2, 1, 0, 7]);
+
+    let decompressed = decompress_event_data::<VersionedSwapped>(&data).unwrap();
+    assert_eq!(
+        VersionedSwapped::decode_versioned(&decompressed).unwrap().0,
+        2
+    );
+}
+
+#[test]
+fn test_custom_codec() {
+    let event = swapped();
+    let data = compress_event_data(&event, &NotCodec);
+    assert_eq!(data[8], NotCodec::ID);
+    assert_eq!(
+        decompress_event_data_with::<Swapped, _>(&data, &NotCodec).unwrap(),
+        event.data()
+    );
+
+    // Only the codecs of Anchor are decompressed without passing the codec
+    assert_eq!(
+        decompress_event_data::<Swapped>(&data).unwrap_err(),
+        ErrorCode::InvalidEventCodec.into()
+    );
+    assert_eq!(
+        decompress_event_data_with::<Swapped, _>(&data, &ZeroRunCodec).unwrap_err(),
+        ErrorCode::InvalidEventCodec.into()
+    );
+}
+
+#[test]
+fn test_decompress_invalid_event_data() {
+    let data = compress_event_data(&swapped(), &ZeroRunCodec);
+    assert_eq!(
+        decompress_event_data::<VersionedSwapped>(&data).unwrap_err(),
+        ErrorCode::InvalidEventDiscriminator.into()
+    );
+    assert_eq!(
+        decompress_event_data::<Swapped>(&Swapped::DISCRIMINATOR).unwrap_err(),
+        ErrorCode::InvalidEventCodec.into()
+    );
+    assert_eq!(
+        decompress_event_data::<Swapped>(&data[..data.len() - 1]).unwrap_err(),
+        ErrorCode::InvalidCompressedEvent.into()
+    );
+}
+
+#[test]
+fn test_emit_compressed() {
+    emit_compressed!(swapped());
+    emit_compressed!(IdentityCodec, swapped());
+    emit_compressed!(NotCodec, swapped());
+}
@@ -0,0 +1,1032 @@
+#![cfg(feature = "event-cpi")]
+
+use anchor_lang::__private::EventCpiAccounts;
+use anchor_lang::event::{
+    record_cpi_events, split_event_ix_data, split_event_ix_data_with, EventCpiAccountInfos,
+    EventCpiInstruction, EventIxTagOrder, EventSequence, EVENT_AUTHORITY_BUMP_IX_TAG_LE,
+    EVENT_IX_TAG_BE, EVENT_IX_TAG_LE, EVENT_SEQUENCE_IX_TAG_LE,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::{Discriminator, Event};
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct EmitEvent<'info> {
+    pub signer: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct EmitEventNoLifetime {}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct EmitGenericEvent<'info, T>
+where
+    T: AccountSerialize + AccountDeserialize + Owner + Clone,
+{
+    pub generic: Account<'info, T>,
+}
+
+#[account]
+pub struct GenericData {
+    pub data: u64,
+}
+
+#[event_cpi(position = "front")]
+#[derive(Accounts)]
+pub struct EmitEventFront<'info> {
+    pub signer: Signer<'info>,
+}
+
+#[event_cpi(seed = "vault")]
+#[derive(Accounts)]
+pub struct EmitVaultEvent<'info> {
+    pub signer: Signer<'info>,
+}
+
+#[event_cpi(sequence)]
+#[derive(Accounts)]
+pub struct EmitSequencedEvent<'info> {
+    pub signer: Signer<'info>,
+}
+
+fn proxy_program_id() -> Pubkey {
+    Pubkey::new_from_array([7; 32])
+}
+
+#[event_cpi(program_id = proxy_program_id())]
+#[derive(Accounts)]
+pub struct EmitProxiedEvent<'info> {
+    pub signer: Signer<'info>,
+}
+
+#[program]
+pub mod event_cpi_program {
+    use super::*;
+
+    pub fn emit_event(ctx: Context<EmitEvent>) -> Result<()> {
+        emit_cpi!(MyEvent { data: 42 });
+        Ok(())
+    }
+
+    pub fn emit_event_twice(ctx: Context<EmitEvent>) -> Result<()> {
+        let event = MyEvent { data: 43 };
+        emit_cpi!(event);
+        emit_cpi!(&event);
+        Ok(())
+    }
+
+    pub fn emit_vault_event(ctx: Context<EmitVaultEvent>) -> Result<()> {
+        emit_cpi!(MyEvent { data: 44 });
+        Ok(())
+    }
+
+    pub fn emit_sequenced_event(ctx: Context<EmitSequencedEvent>) -> Result<()> {
+        emit_cpi!(MyEvent { data: 47 });
+        Ok(())
+    }
+
+    pub fn emit_correlated_event(ctx: Context<EmitEvent>, correlation_id: [u8; 16]) -> Result<()> {
+        emit_cpi!(CorrelatedEvent { data: 53 });
+        let ctx = ctx.with_correlation_id(correlation_id);
+        emit_cpi!(CorrelatedEvent { data: 54 });
+        emit_cpi!(MyEvent { data: 55 });
+        Ok(())
+    }
+
+    pub fn emit_auto_event(ctx: Context<EmitEvent>) -> Result<()> {
+        emit_auto!(MyEvent { data: 49 });
+        emit_auto!(MyEvent { data: 50 }, threshold = 0);
+        Ok(())
+    }
+}
+
+#[event]
+pub struct MyEvent {
+    pub data: u64,
+}
+
+#[event(correlated)]
+pub struct CorrelatedEvent {
+    pub data: u64,
+}
+
+fn assert_event_cpi_accounts<T: EventCpiAccounts>() {}
+
+fn build<'info>(ctx: Context<'_, '_, '_, 'info, EmitEvent<'info>>) -> EventCpiInstruction<'info> {
+    build_cpi_event_ix!(MyEvent { data: 42 })
+}
+
+fn build_vault<'info>(
+    ctx: Context<'_, '_, '_, 'info, EmitVaultEvent<'info>>,
+) -> EventCpiInstruction<'info> {
+    build_cpi_event_ix!(MyEvent { data: 44 })
+}
+
+fn build_sequenced<'info>(
+    ctx: Context<'_, '_, '_, 'info, EmitSequencedEvent<'info>>,
+) -> EventCpiInstruction<'info> {
+    build_cpi_event_ix!(MyEvent { data: 47 })
+}
+
+fn build_proxied<'info>(
+    ctx: Context<'_, '_, '_, 'info, EmitProxiedEvent<'info>>,
+) -> EventCpiInstruction<'info> {
+    build_cpi_event_ix!(MyEvent { data: 51 })
+}
+
+fn build_explicit_vault<'info>(
+    program: &AccountInfo<'info>,
+    event_authority: &AccountInfo<'info>,
+    bump: u8,
+) -> EventCpiInstruction<'info> {
+    build_cpi_event_ix!(
+        program,
+        event_authority,
+        bump,
+        b"vault",
+        MyEvent { data: 45 }
+    )
+}
+
+fn build_explicit<'info>(
+    program: &AccountInfo<'info>,
+    event_authority: &AccountInfo<'info>,
+    bump: u8,
+) -> EventCpiInstruction<'info> {
+    build_cpi_event_ix!(program, event_authority, bump, MyEvent { data: 43 })
+}
+
+fn emit_explicit<'info>(
+    program: &AccountInfo<'info>,
+    event_authority: &AccountInfo<'info>,
+    bump: u8,
+) -> Result<()> {
+    emit_cpi!(program, event_authority, bump, MyEvent { data: 51 });
+    Ok(())
+}
+
+fn build_forward<'info>(
+    target: &AccountInfo<'info>,
+    ctx: Context<'_, '_, '_, 'info, EmitEvent<'info>>,
+) -> EventCpiInstruction<'info> {
+    forward_cpi_event!(target, MyEvent { data: 46 })
+}
+
+#[test]
+fn test_event_cpi_accounts_impl() {
+    assert_event_cpi_accounts::<EmitEvent>();
+    assert_event_cpi_accounts::<EmitEventNoLifetime>();
+    assert_event_cpi_accounts::<EmitVaultEvent>();
+    assert_event_cpi_accounts::<EmitGenericEvent<GenericData>>();
+
+    assert_eq!(EmitEvent::EVENT_AUTHORITY_SEEDS, &[b"__event_authority"]);
+    assert_eq!(
+        EmitVaultEvent::EVENT_AUTHORITY_SEEDS,
+        &[&b"__event_authority"[..], b"vault"]
+    );
+
+    // The bump is read from the bumps of the accounts struct
+    let bumps = EmitEventBumps {
+        event_authority: 254,
+    };
+    assert_eq!(EmitEvent::event_authority_bump(&bumps), 254);
+}
+
+struct TestAccounts {
+    signer_key: Pubkey,
+    authority_key: Pubkey,
+    authority_bump: u8,
+    lamports: [u64; 3],
+    data: [Vec<u8>; 3],
+    owner: Pubkey,
+}
+
+impl TestAccounts {
+    fn new() -> Self {
+        let (authority_key, authority_bump) = event_authority_pda(&ID);
+        Self {
+            signer_key: Pubkey::new_unique(),
+            authority_key,
+            authority_bump,
+            lamports: [0; 3],
+            data: Default::default(),
+            owner: Pubkey::default(),
+        }
+    }
+
+    /// Returns the signer, event authority and program account infos.
+    fn infos(&mut self) -> [AccountInfo<'_>; 3] {
+        let [signer_lamports, authority_lamports, program_lamports] = &mut self.lamports;
+        let [signer_data, authority_data, program_data] = &mut self.data;
+        [
+            AccountInfo::new(
+                &self.signer_key,
+                true,
+                false,
+                signer_lamports,
+                signer_data,
+                &self.owner,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &self.authority_key,
+                false,
+                false,
+                authority_lamports,
+                authority_data,
+                &self.owner,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &ID,
+                false,
+                false,
+                program_lamports,
+                program_data,
+                &self.owner,
+                true,
+                0,
+            ),
+        ]
+    }
+}
+
+#[test]
+fn test_build_cpi_event_ix() {
+    let mut test_accounts = TestAccounts::new();
+    let (authority_key, authority_bump) =
+        (test_accounts.authority_key, test_accounts.authority_bump);
+    let [signer, event_authority, program] = test_accounts.infos();
+
+    let mut accounts = EmitEvent {
+        signer: Signer::try_from(&signer).unwrap(),
+        event_authority,
+        program,
+    };
+    let ctx = Context::new(
+        &ID,
+        &mut accounts,
+        &[],
+        EmitEventBumps {
+            event_authority: authority_bump,
+        },
+    );
+
+    let event_ix = build(ctx);
+    assert_eq!(event_ix.instruction.program_id, ID);
+    assert_eq!(
+        event_ix.instruction.data,
+        [&EVENT_IX_TAG_LE[..], &MyEvent { data: 42 }.data()].concat()
+    );
+    assert_eq!(event_ix.instruction.accounts.len(), 1);
+    assert_eq!(event_ix.instruction.accounts[0].pubkey, authority_key);
+    assert!(event_ix.instruction.accounts[0].is_signer);
+    assert!(!event_ix.instruction.accounts[0].is_writable);
+    assert_eq!(event_ix.account_infos[0].key, &authority_key);
+    assert_eq!(event_ix.account_infos[1].key, &ID);
+    assert_eq!(event_ix.authority_seeds, &[b"__event_authority"]);
+    assert_eq!(event_ix.authority_bump, authority_bump);
+    assert!(!event_ix.sequenced);
+}
+
+#[test]
+fn test_record_cpi_events() {
+    let mut test_accounts = TestAccounts::new();
+    let (authority_key, authority_bump) =
+        (test_accounts.authority_key, test_accounts.authority_bump);
+    let [signer, event_authority, program] = test_accounts.infos();
+
+    let mut accounts = EmitEvent {
+        signer: Signer::try_from(&signer).unwrap(),
+        event_authority,
+        program,
+    };
+    let ctx = Context::new(
+        &ID,
+        &mut accounts,
+        &[],
+        EmitEventBumps {
+            event_authority: authority_bump,
+        },
+    );
+
+    let (result, events) = record_cpi_events(|| event_cpi_program::emit_event(ctx));
+    result.unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].program_id, ID);
+    assert_eq!(
+        events[0].data,
+        [&EVENT_IX_TAG_LE[..], &MyEvent { data: 42 }.data()].concat()
+    );
+    assert_eq!(
+        events[0].accounts,
+        [AccountMeta::new_readonly(authority_key, true)]
+    );
+}
+
+#[test]
+fn test_emit_cpi_correlation_id() {
+    let mut test_accounts = TestAccounts::new();
+    let authority_bump = test_accounts.authority_bump;
+    let [signer, event_authority, program] = test_accounts.infos();
+
+    let mut accounts = EmitEvent {
+        signer: Signer::try_from(&signer).unwrap(),
+        event_authority,
+        program,
+    };
+    let ctx = Context::new(
+        &ID,
+        &mut accounts,
+        &[],
+        EmitEventBumps {
+            event_authority: authority_bump,
+        },
+    );
+
+    let (result, events) =
+        record_cpi_events(|| event_cpi_program::emit_correlated_event(ctx, [7; 16]));
+    result.unwrap();
+    assert_eq!(events.len(), 3);
+    let (_, unscoped) = split_event_ix_data(&events[0].data).unwrap();
+    assert_eq!(
+        unscoped,
+        [
+            &CorrelatedEvent::DISCRIMINATOR[..],
+            &[0],
+            &53u64.to_le_bytes()
+        ]
+        .concat()
+    );
+    assert_eq!(
+        CorrelatedEvent::correlation_id_from_event_data(unscoped).unwrap(),
+        None
+    );
+
+    let (_, correlated) = split_event_ix_data(&events[1].data).unwrap();
+    assert_eq!(
+        correlated,
+        [
+            &CorrelatedEvent::DISCRIMINATOR[..],
+            &[1],
+            &[7; 16],
+            &54u64.to_le_bytes()
+        ]
+        .concat()
+    );
+    assert_eq!(
+        CorrelatedEvent::correlation_id_from_event_data(correlated).unwrap(),
+        Some([7; 16])
+    );
+    assert_eq!(
+        CorrelatedEvent::try_from_event_data(correlated)
+            .unwrap()
+            .data,
+        54
+    );
+
+    // Events that are not correlated are not affected by the id
+    let (_, uncorrelated) = split_event_ix_data(&events[2].data).unwrap();
+    assert_eq!(uncorrelated, MyEvent { data: 55 }.data());
+}
+
+#[test]
+fn test_emit_cpi_borrowed() {
+    let mut test_accounts = TestAccounts::new();
+    let (authority_key, authority_bump) =
+        (test_accounts.authority_key, test_accounts.authority_bump);
+    let [signer, event_authority, program] = test_accounts.infos();
+
+    let mut accounts = EmitEvent {
+        signer: Signer::try_from(&signer).unwrap(),
+        event_authority,
+        program,
+    };
+    let ctx = Context::new(
+        &ID,
+        &mut accounts,
+        &[],
+        EmitEventBumps {
+            event_authority: authority_bump,
+        },
+    );
+
+    let (result, events) = record_cpi_events(|| event_cpi_program::emit_event_twice(ctx));
+    result.unwrap();
+
+    let ix = MyEvent { data: 43 }.cpi_event_instruction(ID, authority_key);
+    assert_eq!(events, [ix.clone(), ix]);
+}
+
+#[test]
+fn test_cpi_event_instruction() {
+    let mut test_accounts = TestAccounts::new();
+    let (authority_key, authority_bump) =
+        (test_accounts.authority_key, test_accounts.authority_bump);
+    let [signer, event_authority, program] = test_accounts.infos();
+
+    let mut accounts = EmitEvent {
+        signer: Signer::try_from(&signer).unwrap(),
+        event_authority,
+        program,
+    };
+    let ctx = Context::new(
+        &ID,
+        &mut accounts,
+        &[],
+        EmitEventBumps {
+            event_authority: authority_bump,
+        },
+    );
+
+    let (result, events) = record_cpi_events(|| event_cpi_program::emit_event(ctx));
+    result.unwrap();
+
+    let ix = MyEvent { data: 42 }.cpi_event_instruction(ID, authority_key);
+    assert_eq!(events, [ix.clone()]);
+    assert_eq!(
+        anchor_lang::event::cpi_event_instruction(&MyEvent { data: 42 }, ID, authority_key),
+        ix
+    );
+}
+
+// Handler that is generic over its accounts, like the default methods of traits that programs
+// implement for their instructions
+trait Notify {
+    fn notify<'info, T: EventCpiAccountInfos<'info>>(
+        ctx: &Context<'_, '_, '_, '_, T>,
+        data: u64,
+    ) -> Result<()> {
+        emit_cpi!(MyEvent { data });
+        Ok(())
+    }
+}
+
+struct Notifier;
+
+impl Notify for Notifier {}
+
+#[test]
+fn test_emit_cpi_generic_accounts() {
+    let mut test_accounts = TestAccounts::new();
+    let (authority_key, authority_bump) = event_authority_pda_with_seed(&ID, b"vault");
+    test_accounts.authority_key = authority_key;
+    let [signer, event_authority, program] = test_accounts.infos();
+
+    let mut accounts = EmitVaultEvent {
+        signer: Signer::try_from(&signer).unwrap(),
+        event_authority,
+        program,
+    };
+    assert_eq!(accounts.event_authority_info().key, &authority_key);
+    assert_eq!(accounts.event_program_info().key, &ID);
+    let ctx = Context::new(
+        &ID,
+        &mut accounts,
+        &[],
+        EmitVaultEventBumps {
+            event_authority: authority_bump,
+        },
+    );
+
+    let (result, events) = record_cpi_events(|| Notifier::notify(&ctx, 48));
+    result.unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0].data,
+        [
+            &EVENT_AUTHORITY_BUMP_IX_TAG_LE[..],
+            &[authority_bump],
+            &EVENT_IX_TAG_LE,
+            &MyEvent { data: 48 }.data()
+        ]
+        .concat()
+    );
+    assert_eq!(
+        events[0].accounts,
+        [AccountMeta::new_readonly(authority_key, true)]
+    );
+}
+
+#[test]
+fn test_event_cpi_program_id() {
+    assert_eq!(EmitEvent::event_program_id(), None);
+    assert_eq!(
+        EmitProxiedEvent::event_program_id(),
+        Some(proxy_program_id())
+    );
+
+    let mut test_accounts = TestAccounts::new();
+    let (authority_key, authority_bump) =
+        (test_accounts.authority_key, test_accounts.authority_bump);
+    let [signer, event_authority, program] = test_accounts.infos();
+
+    // The `program` account must be the given program
+    let infos = [signer.clone(), event_authority.clone(), program];
+    let err = EmitProxiedEvent::try_accounts(
+        &ID,
+        &mut &infos[..],
+        &[],
+        &mut EmitProxiedEventBumps::default(),
+        &mut Default::default(),
+    )
+    .err()
+    .unwrap();
+    assert_eq!(err, ErrorCode::ConstraintAddress.into());
+
+    let proxy_key = proxy_program_id();
+    let (mut lamports, mut data) = (0, vec![]);
+    let proxy = AccountInfo::new(
+        &proxy_key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &proxy_key,
+        true,
+        0,
+    );
+    let infos = [signer, event_authority, proxy];
+    let mut bumps = EmitProxiedEventBumps::default();
+    let mut accounts = EmitProxiedEvent::try_accounts(
+        &ID,
+        &mut &infos[..],
+        &[],
+        &mut bumps,
+        &mut Default::default(),
+    )
+    .unwrap();
+    assert_eq!(bumps.event_authority, authority_bump);
+
+    let ctx = Context::new(&ID, &mut accounts, &[], bumps);
+    let event_ix = build_proxied(ctx);
+    assert_eq!(event_ix.instruction.program_id, proxy_key);
+    assert_eq!(event_ix.instruction.accounts[0].pubkey, authority_key);
+    assert_eq!(event_ix.account_infos[1].key, &proxy_key);
+    assert_eq!(event_ix.authority_seeds, &[b"__event_authority"]);
+}
+
+#[test]
+fn test_build_cpi_event_ix_explicit() {
+    let mut test_accounts = TestAccounts::new();
+    let (authority_key, authority_bump) =
+        (test_accounts.authority_key, test_accounts.authority_bump);
+    let [_, event_authority, program] = test_accounts.infos();
+
+    let event_ix = build_explicit(&program, &event_authority, authority_bump);
+    assert_eq!(event_ix.instruction.program_id, ID);
+    assert_eq!(
+        event_ix.instruction.data,
+        [&EVENT_IX_TAG_LE[..], &MyEvent { data: 43 }.data()].concat()
+    );
+    assert_eq!(event_ix.instruction.accounts[0].pubkey, authority_key);
+    assert_eq!(event_ix.account_infos[0].key, &authority_key);
+    assert_eq!(event_ix.account_infos[1].key, &ID);
+    assert_eq!(event_ix.authority_bump, authority_bump);
+}
+
+#[test]
+fn test_forward_cpi_event() {
+    let mut test_accounts = TestAccounts::new();
+    let (authority_key, authority_bump) =
+        (test_accounts.authority_key, test_accounts.authority_bump);
+    let [signer, event_authority, program] = test_accounts.infos();
+
+    let target_key = Pubkey::new_unique();
+    let (mut target_lamports, mut target_data) = (0, vec![]);
+    let target = AccountInfo::new(
+        &target_key,
+        false,
+        false,
+        &mut target_lamports,
+        &mut target_data,
+        &target_key,
+        true,
+        0,
+    );
+
+    let mut accounts = EmitEvent {
+        signer: Signer::try_from(&signer).unwrap(),
+        event_authority,
+        program,
+    };
+    let ctx = Context::new(
+        &ID,
+        &mut accounts,
+        &[],
+        EmitEventBumps {
+            event_authority: authority_bump,
+        },
+    );
+
+    let event_ix = build_forward(&target, ctx);
+    assert_eq!(event_ix.instruction.program_id, target_key);
+    assert_eq!(
+        event_ix.instruction.data,
+        [&EVENT_IX_TAG_LE[..], &MyEvent { data: 46 }.data()].concat()
+    );
+    assert_eq!(
+        event_ix.instruction.accounts,
+        vec![
+            AccountMeta::new_readonly(authority_key, true),
+            AccountMeta::new_readonly(ID, false),
+        ]
+    );
+    assert_eq!(event_ix.account_infos[0].key, &authority_key);
+    assert_eq!(event_ix.account_infos[1].key, &ID);
+    assert_eq!(event_ix.authority_bump, authority_bump);
+}
+
+#[test]
+fn test_event_cpi_position() {
+    let mut test_accounts = TestAccounts::new();
+    let [signer, event_authority, program] = test_accounts.infos();
+    let keys = [*signer.key, *event_authority.key, *program.key];
+
+    let back = EmitEvent {
+        signer: Signer::try_from(&signer).unwrap(),
+        event_authority: event_authority.clone(),
+        program: program.clone(),
+    };
+    let metas = back.to_account_metas(None);
+    assert_eq!(
+        metas.iter().map(|meta| meta.pubkey).collect::<Vec<_>>(),
+        [keys[0], keys[1], keys[2]]
+    );
+
+    let front = EmitEventFront {
+        event_authority,
+        program,
+        signer: Signer::try_from(&signer).unwrap(),
+    };
+    let metas = front.to_account_metas(None);
+    assert_eq!(
+        metas.iter().map(|meta| meta.pubkey).collect::<Vec<_>>(),
+        [keys[1], keys[2], keys[0]]
+    );
+}
+
+#[test]
+fn test_event_authority_pda() {
+    let (authority_key, authority_bump) = event_authority_pda(&ID);
+    assert_eq!(
+        (authority_key, authority_bump),
+        Pubkey::find_program_address(&[b"__event_authority"], &ID)
+    );
+
+    let mut test_accounts = TestAccounts::new();
+    let [signer, event_authority, program] = test_accounts.infos();
+    let mut accounts = EmitEvent {
+        signer: Signer::try_from(&signer).unwrap(),
+        event_authority,
+        program,
+    };
+    let ctx = Context::new(
+        &ID,
+        &mut accounts,
+        &[],
+        EmitEventBumps {
+            event_authority: authority_bump,
+        },
+    );
+
+    // The seeds `emit_cpi!` signs with must derive the same address
+    let event_ix = build(ctx);
+    let mut seeds = event_ix.authority_seeds.to_vec();
+    let bump = [event_ix.authority_bump];
+    seeds.push(&bump);
+    assert_eq!(
+        Pubkey::create_program_address(&seeds, &ID).unwrap(),
+        authority_key
+    );
+}
+
+struct FailingInvokeStubs;
+
+impl solana_program::program_stubs::SyscallStubs for FailingInvokeStubs {
+    fn sol_invoke_signed(
+        &self,
+        _instruction: &solana_program::instruction::Instruction,
+        _account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> std::result::Result<(), ProgramError> {
+        Err(ProgramError::Custom(7))
+    }
+}
+
+#[test]
+fn test_event_cpi_failed() {
+    let mut test_accounts = TestAccounts::new();
+    let authority_bump = test_accounts.authority_bump;
+    let [_, event_authority, program] = test_accounts.infos();
+
+    solana_program::program_stubs::set_syscall_stubs(Box::new(FailingInvokeStubs));
+    let err = build_explicit(&program, &event_authority, authority_bump)
+        .invoke()
+        .unwrap_err();
+    match err {
+        Error::AnchorError(err) => {
+            assert_eq!(err.error_code_number, ErrorCode::EventCpiFailed as u32);
+            assert_eq!(err.error_name, "EventCpiFailed");
+            assert!(err
+                .error_msg
+                .ends_with(&ProgramError::Custom(7).to_string()));
+        }
+        err => panic!("Unexpected error: {err:?}"),
+    }
+}
+
+#[test]
+fn test_wrong_event_authority() {
+    let assert_mismatch = |result: Result<()>| match result.unwrap_err() {
+        Error::AnchorError(err) => assert_eq!(
+            err.error_code_number,
+            ErrorCode::EventAuthorityMismatch as u32
+        ),
+        err => panic!("Unexpected error: {err:?}"),
+    };
+
+    let mut test_accounts = TestAccounts::new();
+    let authority_bump = test_accounts.authority_bump;
+    let [_, event_authority, program] = test_accounts.infos();
+    let (result, events) =
+        record_cpi_events(|| emit_explicit(&program, &event_authority, authority_bump));
+    result.unwrap();
+    assert_eq!(events.len(), 1);
+
+    // Wrong bump of the right account
+    assert_mismatch(emit_explicit(
+        &program,
+        &event_authority,
+        authority_bump.wrapping_sub(1),
+    ));
+
+    // Account that isn't the event authority
+    let mut test_accounts = TestAccounts::new();
+    test_accounts.authority_key = Pubkey::new_unique();
+    let [_, event_authority, program] = test_accounts.infos();
+    let (result, events) =
+        record_cpi_events(|| emit_explicit(&program, &event_authority, authority_bump));
+    assert_mismatch(result);
+    assert!(events.is_empty());
+}
+
+#[test]
+fn test_event_authority_with_seed() {
+    let (authority_key, authority_bump) = event_authority_pda_with_seed(&ID, b"vault");
+    assert_eq!(
+        (authority_key, authority_bump),
+        Pubkey::find_program_address(&[b"__event_authority", b"vault"], &ID)
+    );
+    assert_ne!(authority_key, event_authority_pda(&ID).0);
+    // An empty suffix doesn't change the address
+    assert_eq!(
+        event_authority_pda_with_seed(&ID, b""),
+        event_authority_pda(&ID)
+    );
+
+    let mut test_accounts = TestAccounts::new();
+    test_accounts.authority_key = authority_key;
+    let [signer, event_authority, program] = test_accounts.infos();
+    let mut accounts = EmitVaultEvent {
+        signer: Signer::try_from(&signer).unwrap(),
+        event_authority: event_authority.clone(),
+        program: program.clone(),
+    };
+    let ctx = Context::new(
+        &ID,
+        &mut accounts,
+        &[],
+        EmitVaultEventBumps {
+            event_authority: authority_bump,
+        },
+    );
+
+    let event_ix = build_vault(ctx);
+    assert_eq!(event_ix.instruction.accounts[0].pubkey, authority_key);
+    assert_eq!(
+        event_ix.authority_seeds,
+        &[&b"__event_authority"[..], b"vault"]
+    );
+    assert_eq!(event_ix.authority_bump, authority_bump);
+
+    let event_ix = build_explicit_vault(&program, &event_authority, authority_bump);
+    assert_eq!(
+        event_ix.authority_seeds,
+        &[&b"__event_authority"[..], b"vault"]
+    );
+}
+
+#[test]
+fn test_event_dispatch_with_seed() {
+    let event_ix_data = [&EVENT_IX_TAG_LE[..], &MyEvent { data: 46 }.data()].concat();
+    let dispatch = |authority_key: Pubkey, is_signer: bool, ix_data: &[u8]| {
+        let (mut lamports, mut data) = (0, vec![]);
+        let owner = Pubkey::default();
+        let authority = AccountInfo::new(
+            &authority_key,
+            is_signer,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        entry(&ID, &[authority], ix_data)
+    };
+    let seeded_ix_data =
+        |bump: u8| [&EVENT_AUTHORITY_BUMP_IX_TAG_LE[..], &[bump], &event_ix_data].concat();
+
+    assert!(dispatch(event_authority_pda(&ID).0, true, &event_ix_data).is_ok());
+    let (vault_key, vault_bump) = event_authority_pda_with_seed(&ID, b"vault");
+    assert!(dispatch(vault_key, true, &seeded_ix_data(vault_bump)).is_ok());
+    assert!(dispatch(vault_key, false, &seeded_ix_data(vault_bump)).is_err());
+    // The bump must be passed and match the event authority
+    assert!(dispatch(vault_key, true, &event_ix_data).is_err());
+    assert!(dispatch(vault_key, true, &seeded_ix_data(vault_bump.wrapping_sub(1))).is_err());
+    assert!(dispatch(vault_key, true, &EVENT_AUTHORITY_BUMP_IX_TAG_LE).is_err());
+    let (other_key, other_bump) = event_authority_pda_with_seed(&ID, b"other");
+    assert!(dispatch(other_key, true, &seeded_ix_data(other_bump)).is_err());
+}
+
+#[test]
+fn test_event_sequence() {
+    assert_eq!(
+        [
+            EmitEvent::EVENT_SEQUENCE,
+            EmitSequencedEvent::EVENT_SEQUENCE
+        ],
+        [false, true]
+    );
+
+    let mut test_accounts = TestAccounts::new();
+    let authority_bump = test_accounts.authority_bump;
+    let [signer, event_authority, program] = test_accounts.infos();
+    let mut accounts = EmitSequencedEvent {
+        signer: Signer::try_from(&signer).unwrap(),
+        event_authority: event_authority.clone(),
+        program,
+    };
+
+    // The sequence number is stored in the event authority
+    let metas = crate::accounts::EmitSequencedEvent {
+        signer: *signer.key,
+        event_authority: *event_authority.key,
+        program: ID,
+    }
+    .to_account_metas(None);
+    assert_eq!(metas[1], AccountMeta::new(*event_authority.key, false));
+
+    let ctx = Context::new(
+        &ID,
+        &mut accounts,
+        &[],
+        EmitSequencedEventBumps {
+            event_authority: authority_bump,
+        },
+    );
+    let event_ix = build_sequenced(ctx);
+    assert!(event_ix.sequenced);
+    // The sequence number is only written when the instruction is invoked
+    assert_eq!(
+        event_ix.instruction.data,
+        [
+            &EVENT_SEQUENCE_IX_TAG_LE[..],
+            &[0; 8],
+            &MyEvent { data: 47 }.data()
+        ]
+        .concat()
+    );
+
+    match EventSequence::advance(&event_authority).unwrap_err() {
+        Error::AnchorError(err) => assert_eq!(
+            err.error_code_number,
+            ErrorCode::EventSequenceNotInitialized as u32
+        ),
+        err => panic!("Unexpected error: {err:?}"),
+    }
+
+    let mut test_accounts = TestAccounts::new();
+    test_accounts.data[1] = [&EventSequence::DISCRIMINATOR[..], &5u64.to_le_bytes()].concat();
+    let [_, event_authority, _] = test_accounts.infos();
+    assert_eq!(EventSequence::advance(&event_authority).unwrap(), 5);
+    assert_eq!(EventSequence::advance(&event_authority).unwrap(), 6);
+    assert_eq!(
+        EventSequence::try_from_account_info(&event_authority).unwrap(),
+        EventSequence { next: 7 }
+    );
+}
+
+#[test]
+fn test_split_event_ix_data() {
+    let event = MyEvent { data: 48 }.data();
+    assert_eq!(
+        split_event_ix_data(&[&EVENT_IX_TAG_LE[..], &event].concat()),
+        Some((None, &event[..]))
+    );
+    assert_eq!(
+        split_event_ix_data(&[&EVENT_SEQUENCE_IX_TAG_LE[..], &3u64.to_le_bytes(), &event].concat()),
+        Some((Some(3), &event[..]))
+    );
+    assert_eq!(split_event_ix_data(&EVENT_SEQUENCE_IX_TAG_LE), None);
+    assert_eq!(split_event_ix_data(&event), None);
+
+    // The bump of event authorities with a seed suffix is skipped
+    let data = [
+        &EVENT_AUTHORITY_BUMP_IX_TAG_LE[..],
+        &[254],
+        &EVENT_IX_TAG_LE,
+        &event,
+    ]
+    .concat();
+    assert_eq!(split_event_ix_data(&data), Some((None, &event[..])));
+    assert_eq!(split_event_ix_data(&EVENT_AUTHORITY_BUMP_IX_TAG_LE), None);
+
+    // Sequenced events are dispatched like other events
+    let (mut lamports, mut data) = (0, vec![]);
+    let (authority_key, owner) = (event_authority_pda(&ID).0, Pubkey::default());
+    let authority = AccountInfo::new(
+        &authority_key,
+        true,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    let ix_data = [&EVENT_SEQUENCE_IX_TAG_LE[..], &3u64.to_le_bytes(), &event].concat();
+    assert!(entry(&ID, &[authority], &ix_data).is_ok());
+}
+
+#[test]
+fn test_split_event_ix_data_with() {
+    let event = MyEvent { data: 49 }.data();
+    let le_data = [&EVENT_IX_TAG_LE[..], &event].concat();
+    let be_data = [&EVENT_IX_TAG_BE[..], &event].concat();
+
+    assert_eq!(split_event_ix_data(&be_data), None);
+    assert_eq!(
+        split_event_ix_data_with(&le_data, EventIxTagOrder::default()),
+        Some((None, &event[..]))
+    );
+    assert_eq!(
+        split_event_ix_data_with(&le_data, EventIxTagOrder::BigEndian),
+        None
+    );
+    assert_eq!(
+        split_event_ix_data_with(&be_data, EventIxTagOrder::BigEndian),
+        Some((None, &event[..]))
+    );
+    for data in [&le_data, &be_data] {
+        assert_eq!(
+            split_event_ix_data_with(data, EventIxTagOrder::Either),
+            Some((None, &event[..]))
+        );
+    }
+
+    // The sequence tag is always little-endian
+    let sequenced = [&EVENT_SEQUENCE_IX_TAG_LE[..], &4u64.to_le_bytes(), &event].concat();
+    assert_eq!(
+        split_event_ix_data_with(&sequenced, EventIxTagOrder::BigEndian),
+        Some((Some(4), &event[..]))
+    );
+}
+
+#[test]
+fn test_emit_auto() {
+    assert_eq!(anchor_lang::event::EMIT_AUTO_THRESHOLD, 1024);
+
+    fn emit_logged(ctx: Context<EmitEvent>) -> Result<()> {
+        // Small enough to be logged, i.e. without self-CPI
+        emit_auto!(MyEvent { data: 51 });
+        emit_auto!(MyEvent { data: 52 }, threshold = 16);
+        Ok(())
+    }
+
+    let mut test_accounts = TestAccounts::new();
+    let authority_bump = test_accounts.authority_bump;
+    let [signer, event_authority, program] = test_accounts.infos();
+    let mut accounts = EmitEvent {
+        signer: Signer::try_from(&signer).unwrap(),
+        event_authority,
+        program,
+    };
+    let ctx = Context::new(
+        &ID,
+        &mut accounts,
+        &[],
+        EmitEventBumps {
+            event_authority: authority_bump,
+        },
+    );
+    assert_eq!(MyEvent { data: 52 }.encoded_len(), 16);
+    assert!(emit_logged(ctx).is_ok());
+}
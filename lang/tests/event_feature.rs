@@ -0,0 +1,115 @@
+use anchor_lang::event::EventRecorder;
+use anchor_lang::prelude::*;
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+// The events are gated with features of `anchor-lang` that are only enabled in some of the test
+// runs, so that both the events and their stubs are tested
+
+#[event(feature = "derive", derive(Debug, PartialEq))]
+pub struct Telemetry {
+    #[index]
+    pub units: u64,
+    pub label: String,
+}
+
+#[event(feature = "event-cpi", version = 1)]
+pub enum Debugged {
+    Checkpoint { step: u8 },
+}
+
+fn telemetry() -> Telemetry {
+    Telemetry {
+        units: 1_000,
+        label: "swap".into(),
+    }
+}
+
+#[test]
+fn test_emit_feature_events() {
+    let recorder = EventRecorder::default();
+    let event = telemetry();
+    emit!(recorder, event);
+    emit!(recorder, Debugged::Checkpoint { step: 1 });
+    emit!(event);
+
+    #[allow(unused_mut)]
+    let mut expected: Vec<Vec<u8>> = vec![];
+    #[cfg(feature = "derive")]
+    expected.push(anchor_lang::Event::data(&telemetry()));
+    #[cfg(feature = "event-cpi")]
+    expected.push(anchor_lang::Event::data(&Debugged::Checkpoint { step: 1 }));
+    assert_eq!(recorder.events(), expected);
+}
+
+#[test]
+fn test_feature_event_derives() {
+    // Stubs keep the derives of the event
+    assert_eq!(
+        format!("{:?}", telemetry()),
+        r#"Telemetry { units: 1000, label: "swap" }"#
+    );
+    assert_eq!(telemetry(), telemetry());
+}
+
+#[cfg(feature = "event-cpi")]
+#[test]
+fn test_enabled_feature_event() {
+    use anchor_lang::Event;
+
+    assert_eq!(Debugged::VERSION, 1);
+    let data = Debugged::Checkpoint { step: 2 }.data();
+    assert!(matches!(
+        Debugged::try_from_event_data(&data).unwrap(),
+        Debugged::Checkpoint { step: 2 }
+    ));
+}
+
+#[cfg(not(feature = "derive"))]
+#[test]
+fn test_emit_macros_of_disabled_feature_events() -> Result<()> {
+    // Stubs serialize to no data, so every macro compiles and emits nothing
+    assert!(telemetry().data().is_empty());
+    assert_eq!(telemetry().encoded_len(), 0);
+
+    emit_many!(telemetry(), telemetry());
+    emit_for!(Pubkey::new_unique(), telemetry());
+    emit_compressed!(telemetry());
+    emit_compressed!(anchor_lang::event::IdentityCodec, telemetry());
+
+    let info = Box::leak(Box::new(AccountInfo::new(
+        Box::leak(Box::new(Pubkey::new_unique())),
+        false,
+        true,
+        Box::leak(Box::new(0)),
+        Box::leak(vec![0; RingBuffer::space(2, 64)].into_boxed_slice()),
+        &ID,
+        false,
+        0,
+    )));
+    let events = RingBuffer::try_from_unchecked(&ID, info)?;
+    events.initialize(64)?;
+    assert_eq!(emit_to_account!(events, telemetry()), 0);
+    assert!(events.is_empty());
+
+    #[cfg(feature = "event-cpi")]
+    {
+        use anchor_lang::event::record_cpi_events;
+
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data: [u8; 0] = [];
+        let program = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &key, true, 0);
+        let event_authority = program.clone();
+
+        assert!(build_cpi_event_ix!(program, event_authority, 255, telemetry()).is_empty());
+        let (result, events) = record_cpi_events(|| -> Result<()> {
+            emit_cpi!(program, event_authority, 255, telemetry());
+            Ok(())
+        });
+        result?;
+        assert!(events.is_empty());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "json-schema")]
+
+use anchor_lang::anchor_syn::idl::build::serde_json::json;
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Leg {
+    pub side: Side,
+    pub price: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum Side {
+    Bid,
+    Ask { limit: u32 },
+}
+
+#[event]
+pub struct OrderFilled {
+    /// Owner of the order
+    pub authority: Pubkey,
+    pub legs: [Leg; 2],
+    pub fills: Vec<Leg>,
+    pub memo: Option<String>,
+}
+
+#[event]
+pub struct Batch<const N: usize> {
+    pub items: [u16; N],
+}
+
+#[test]
+fn test_json_schema() {
+    let schema = OrderFilled::json_schema();
+    assert_eq!(schema["title"], "OrderFilled");
+    assert_eq!(
+        schema["required"],
+        json!(["authority", "legs", "fills", "memo"])
+    );
+    assert_eq!(
+        schema["properties"]["authority"]["description"],
+        "Owner of the order"
+    );
+    assert_eq!(
+        schema["properties"]["legs"],
+        json!({
+            "type": "array",
+            "items": { "$ref": "#/$defs/Leg" },
+            "minItems": 2,
+            "maxItems": 2,
+        })
+    );
+    assert_eq!(
+        schema["properties"]["fills"],
+        json!({ "type": "array", "items": { "$ref": "#/$defs/Leg" } })
+    );
+    assert_eq!(
+        schema["properties"]["memo"],
+        json!({ "anyOf": [{ "type": "string" }, { "type": "null" }] })
+    );
+
+    // Nested types are described once, and refer to each other
+    let defs = schema["$defs"].as_object().unwrap();
+    assert_eq!(defs.keys().collect::<Vec<_>>(), ["Leg", "Side"]);
+    assert_eq!(
+        defs["Leg"]["properties"]["side"],
+        json!({ "$ref": "#/$defs/Side" })
+    );
+    assert_eq!(
+        defs["Side"]["oneOf"][1]["properties"]["Ask"]["properties"]["limit"],
+        json!({ "type": "integer", "minimum": 0, "maximum": u32::MAX })
+    );
+}
+
+#[test]
+fn test_json_schema_generic_len_array() {
+    let schema = Batch::<4>::json_schema();
+    assert_eq!(
+        schema["properties"]["items"],
+        json!({
+            "type": "array",
+            "items": { "type": "integer", "minimum": 0, "maximum": u16::MAX },
+        })
+    );
+    assert!(schema.get("$defs").is_none());
+}
@@ -0,0 +1,64 @@
+use anchor_lang::event::EventMeta;
+use anchor_lang::prelude::*;
+use anchor_lang::Event;
+
+#[event]
+pub struct Deposited {
+    pub amount: u64,
+}
+
+#[event]
+pub enum Withdrawn {
+    Partial { amount: u64 },
+    All,
+}
+
+pub mod admin {
+    use super::*;
+
+    #[event]
+    pub struct ConfigUpdated {
+        pub fee: u16,
+    }
+}
+
+declare_events!(pub const EVENTS = [Deposited, admin::ConfigUpdated, Withdrawn]);
+
+// Events are listed in the given order, named after the last segment of their path
+#[test]
+fn test_events() {
+    assert_eq!(
+        EVENTS,
+        [
+            EventMeta {
+                name: "Deposited",
+                discriminator: Deposited::EVENT_DISCRIMINATOR,
+            },
+            EventMeta {
+                name: "ConfigUpdated",
+                discriminator: admin::ConfigUpdated::EVENT_DISCRIMINATOR,
+            },
+            EventMeta {
+                name: "Withdrawn",
+                discriminator: Withdrawn::EVENT_DISCRIMINATOR,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_event_meta_matches() {
+    let deposited = EVENTS
+        .iter()
+        .find(|event| event.name == "Deposited")
+        .unwrap();
+    assert!(deposited.matches(&Deposited { amount: 1 }.data()));
+    assert!(!deposited.matches(&Withdrawn::All.data()));
+    assert_eq!(
+        EVENTS
+            .iter()
+            .filter(|event| event.matches(&Withdrawn::All.data()))
+            .collect::<Vec<&EventMeta>>(),
+        [&EVENTS[2]]
+    );
+}
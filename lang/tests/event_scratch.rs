@@ -0,0 +1,169 @@
+//! Allocations of decoding events with and without an `EventScratch`. The allocations are
+//! counted per thread by the global allocator of this test binary.
+
+use anchor_lang::__private::base64::{engine::general_purpose::STANDARD, Engine};
+use anchor_lang::event::{EventContext, EventScratch};
+use anchor_lang::prelude::*;
+use anchor_lang::Event;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::time::Instant;
+
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Returns the number of allocations of the current thread made by `f`.
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[event]
+pub struct TransferEvent {
+    pub amount: u64,
+    pub memo: Vec<u8>,
+}
+
+generate_event_enum! {
+    pub enum TransferProgramEvent {
+        TransferEvent,
+    }
+}
+
+/// Sums the amounts of the events, without allocating.
+#[derive(Default)]
+struct Totals {
+    amount: u64,
+    events: usize,
+}
+
+impl TransferProgramEventHandler for Totals {
+    fn on_transfer_event(&mut self, event: TransferEvent, _ctx: &EventContext) {
+        self.amount += event.amount;
+        self.events += 1;
+    }
+}
+
+fn transfer_logs(events: u64) -> Vec<String> {
+    (0..events)
+        .map(|amount| {
+            let event = TransferEvent {
+                amount,
+                memo: vec![amount as u8; 32],
+            };
+            format!("Program data: {}", STANDARD.encode(event.data()))
+        })
+        .collect()
+}
+
+const CTX: EventContext<'static> = EventContext {
+    slot: 1,
+    signature: "sig",
+};
+
+#[test]
+fn test_dispatch_with_reused_scratch_allocations() {
+    let logs = transfer_logs(100);
+    let mut scratch = EventScratch::with_capacity(64);
+    let mut totals = Totals::default();
+
+    // Only the `memo` of each event is allocated
+    let allocations = count_allocations(|| {
+        TransferProgramEvent::dispatch_with(&CTX, &logs, &mut totals, &mut scratch)
+    });
+    assert_eq!(totals.events, 100);
+    assert_eq!(totals.amount, (0..100).sum::<u64>());
+    assert_eq!(allocations, 100);
+
+    // Without a scratch, the segments of every line and their buffers are allocated as well
+    let allocations = count_allocations(|| {
+        for line in &logs {
+            assert!(TransferProgramEvent::from_log_line(line).is_some());
+        }
+    });
+    assert_eq!(allocations, 300);
+
+    let allocations = count_allocations(|| {
+        for line in &logs {
+            assert!(TransferProgramEvent::from_log_line_with(line, &mut scratch).is_some());
+        }
+    });
+    assert_eq!(allocations, 100);
+}
+
+#[test]
+fn test_scratch_grows_once() {
+    let logs = transfer_logs(10);
+    let mut scratch = EventScratch::new();
+    assert_eq!(scratch.capacity(), 0);
+
+    let first = count_allocations(|| {
+        TransferProgramEvent::dispatch_with(&CTX, &logs, &mut Totals::default(), &mut scratch)
+    });
+    let capacity = scratch.capacity();
+    assert!(capacity > 0);
+    assert!(first > 10);
+
+    let second = count_allocations(|| {
+        TransferProgramEvent::dispatch_with(&CTX, &logs, &mut Totals::default(), &mut scratch)
+    });
+    assert_eq!(second, 10);
+    assert_eq!(scratch.capacity(), capacity);
+
+    scratch.reserve(capacity + 1);
+    assert!(scratch.capacity() > capacity);
+}
+
+/// Compares the throughput of decoding with and without a reused scratch. Run with
+/// `cargo test --release --test event_scratch -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn bench_dispatch_with_scratch() {
+    let logs = transfer_logs(100_000);
+
+    let start = Instant::now();
+    let mut totals = Totals::default();
+    for line in &logs {
+        if let Some(event) = TransferProgramEvent::from_log_line(line) {
+            event.handle(&CTX, &mut totals);
+        }
+    }
+    let without_scratch = start.elapsed();
+
+    let start = Instant::now();
+    let mut scratch = EventScratch::new();
+    let mut totals_with_scratch = Totals::default();
+    TransferProgramEvent::dispatch_with(&CTX, &logs, &mut totals_with_scratch, &mut scratch);
+    let with_scratch = start.elapsed();
+
+    assert_eq!(totals.amount, totals_with_scratch.amount);
+    println!(
+        "{} events: {:?} without scratch, {:?} with scratch",
+        logs.len(),
+        without_scratch,
+        with_scratch
+    );
+}
@@ -0,0 +1,94 @@
+use anchor_lang::event::InstructionEvents;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::InstructionData;
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[derive(Accounts)]
+pub struct EmitEvents {}
+
+#[program(max_events = 3)]
+pub mod max_events_program {
+    use super::*;
+
+    pub fn emit_events(ctx: Context<EmitEvents>, count: u64) -> Result<()> {
+        for data in 0..count {
+            emit!(ctx, MyEvent { data });
+        }
+        Ok(())
+    }
+
+    pub fn emit_from_helpers(ctx: Context<EmitEvents>, count: u64) -> Result<()> {
+        (0..count).for_each(|data| emit_event(&ctx, data));
+        Ok(())
+    }
+
+    pub fn emit_many_events(ctx: Context<EmitEvents>, count: u64) -> Result<()> {
+        emit!(ctx, MyEvent { data: 0 });
+        emit!(ctx, MyEvent { data: 1 });
+        for data in 2..count {
+            emit_on_success!(MyEvent { data });
+        }
+        Ok(())
+    }
+
+    pub fn emit_uncounted_events(_ctx: Context<EmitEvents>, count: u64) -> Result<()> {
+        for data in 0..count {
+            emit!(MyEvent { data });
+        }
+        Ok(())
+    }
+}
+
+fn emit_event(ctx: &Context<EmitEvents>, data: u64) {
+    emit!(ctx, MyEvent { data });
+}
+
+#[event]
+pub struct MyEvent {
+    pub data: u64,
+}
+
+fn dispatch(ix: impl InstructionData) -> ProgramResult {
+    entry(&ID, &[], &ix.data())
+}
+
+#[test]
+fn test_max_events() {
+    use max_events_program::instruction;
+
+    for count in [3, 4] {
+        let results = [
+            dispatch(instruction::EmitEvents { count }),
+            dispatch(instruction::EmitFromHelpers { count }),
+            dispatch(instruction::EmitManyEvents { count }),
+        ];
+        for result in results {
+            match count {
+                3 => assert!(result.is_ok()),
+                _ => assert_eq!(
+                    result.unwrap_err(),
+                    ProgramError::Custom(ErrorCode::TooManyEvents.into())
+                ),
+            }
+        }
+        assert!(dispatch(instruction::EmitUncountedEvents { count }).is_ok());
+    }
+}
+
+#[test]
+fn test_max_events_outside_of_dispatch() {
+    let mut accounts = EmitEvents {};
+    let ctx = Context::new(&ID, &mut accounts, &[], EmitEventsBumps {});
+    assert!(max_events_program::emit_events(ctx, 5).is_ok());
+
+    let events = InstructionEvents::new(Some(3));
+    let ctx = Context::new(&ID, &mut accounts, &[], EmitEventsBumps {}).with_events(&events);
+    assert!(max_events_program::emit_events(ctx, 5).is_ok());
+    assert_eq!(events.count(), 5);
+    assert_eq!(
+        ProgramError::from(events.finish().unwrap_err()),
+        ProgramError::Custom(ErrorCode::TooManyEvents.into())
+    );
+}
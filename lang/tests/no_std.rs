@@ -0,0 +1,93 @@
+//! Events declared in a `#![no_std]` crate. The code generated by `#[event]` must only refer
+//! to `std` through `anchor_lang`, and discriminators are computed when the macro is expanded,
+//! so events don't hash anything at runtime.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec;
+use anchor_lang::prelude::*;
+use anchor_lang::{Discriminator, Event};
+
+#[event]
+pub struct NoStdEvent {
+    pub amount: u64,
+    #[index]
+    pub owner: Pubkey,
+}
+
+#[event(version = 1, sequence, timestamp)]
+pub struct NoStdVersioned {
+    pub data: u8,
+}
+
+#[event(discriminator_hex, derive(Clone, PartialEq, Debug))]
+pub struct NoStdTrailing {
+    pub data: alloc::vec::Vec<u8>,
+    #[optional_trailing]
+    pub extra: u8,
+}
+
+#[event(zero_copy)]
+pub struct NoStdZeroCopy {
+    pub data: u64,
+}
+
+#[event]
+pub enum NoStdEnum {
+    A { data: u8 },
+    B,
+}
+
+// Discriminators are constants, computed without hashing at runtime
+const DISCRIMINATORS: [[u8; 8]; 5] = [
+    NoStdEvent::DISCRIMINATOR,
+    NoStdVersioned::DISCRIMINATOR,
+    NoStdTrailing::DISCRIMINATOR,
+    NoStdZeroCopy::DISCRIMINATOR,
+    NoStdEnum::DISCRIMINATOR,
+];
+
+#[test]
+fn test_no_std_event() {
+    assert_eq!(
+        DISCRIMINATORS[0],
+        anchor_lang::event_discriminator("NoStdEvent")
+    );
+
+    let event = NoStdEvent {
+        amount: 1,
+        owner: Pubkey::default(),
+    };
+    let data = event.data();
+    assert_eq!(&data[..8], &DISCRIMINATORS[0]);
+    assert_eq!(NoStdEvent::try_from_event_data(&data).unwrap().amount, 1);
+    assert_eq!(
+        NoStdEvent::peek_indexed_fields(&data).unwrap().owner,
+        Pubkey::default()
+    );
+
+    let data = NoStdTrailing {
+        data: vec![1, 2],
+        extra: 3,
+    }
+    .data();
+    assert_eq!(
+        NoStdTrailing::try_from_event_data(&data[..14]).unwrap(),
+        NoStdTrailing {
+            data: vec![1, 2],
+            extra: 0,
+        }
+    );
+
+    let data = NoStdZeroCopy { data: 4 }.data();
+    assert_eq!(data[8..], 4u64.to_le_bytes());
+
+    emit!(NoStdVersioned {
+        sequence: 0,
+        data: 5,
+        emitted_at: 0,
+    });
+    emit_many!(NoStdEnum::A { data: 6 }, NoStdEnum::B);
+}
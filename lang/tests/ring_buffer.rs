@@ -0,0 +1,193 @@
+use anchor_lang::accounts::ring_buffer::RING_BUFFER_DISCRIMINATOR;
+use anchor_lang::prelude::*;
+use anchor_lang::Event;
+use std::collections::BTreeSet;
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[derive(Accounts)]
+pub struct CreateEvents<'info> {
+    #[account(init, payer = payer, space = RingBuffer::space(16, 64))]
+    pub events: RingBuffer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitEvents<'info> {
+    #[account(zero, rent_exempt = skip)]
+    pub events: RingBuffer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EmitEvent<'info> {
+    #[account(mut)]
+    pub events: RingBuffer<'info>,
+}
+
+#[program]
+pub mod ring_buffer_program {
+    use super::*;
+
+    pub fn init_events(ctx: Context<InitEvents>, slot_len: u32) -> Result<()> {
+        ctx.accounts.events.initialize(slot_len)
+    }
+
+    pub fn emit_event(ctx: Context<EmitEvent>, data: u64) -> Result<()> {
+        let sequence = emit_to_account!(ctx.accounts.events, MyEvent { data });
+        assert_eq!(sequence + 1, ctx.accounts.events.head());
+        Ok(())
+    }
+
+    pub fn emit_other_event(ctx: Context<EmitEvent>, data: String) -> Result<()> {
+        let event = MyOtherEvent { data };
+        emit_to_account!(&ctx.accounts.events, &event);
+        Ok(())
+    }
+}
+
+#[event]
+#[derive(Debug, PartialEq)]
+pub struct MyEvent {
+    pub data: u64,
+}
+
+#[event]
+#[derive(Debug, PartialEq)]
+pub struct MyOtherEvent {
+    pub data: String,
+}
+
+fn account_info(owner: &'static Pubkey, data: Vec<u8>) -> &'static AccountInfo<'static> {
+    Box::leak(Box::new(AccountInfo::new(
+        Box::leak(Box::new(Pubkey::new_unique())),
+        false,
+        true,
+        Box::leak(Box::new(0)),
+        Box::leak(data.into_boxed_slice()),
+        owner,
+        false,
+        0,
+    )))
+}
+
+fn init(capacity: u32, slot_len: u32) -> &'static AccountInfo<'static> {
+    let info = account_info(&ID, vec![0; RingBuffer::space(capacity, slot_len)]);
+    let mut accounts = InitEvents::try_accounts(
+        &ID,
+        &mut std::slice::from_ref(info),
+        &[],
+        &mut InitEventsBumps {},
+        &mut BTreeSet::new(),
+    )
+    .unwrap();
+    ring_buffer_program::init_events(
+        Context::new(&ID, &mut accounts, &[], InitEventsBumps {}),
+        slot_len,
+    )
+    .unwrap();
+    info
+}
+
+fn emit<T>(
+    info: &'static AccountInfo<'static>,
+    handler: fn(Context<EmitEvent>, T) -> Result<()>,
+    arg: T,
+) -> Result<()> {
+    let mut accounts = EmitEvent::try_accounts(
+        &ID,
+        &mut std::slice::from_ref(info),
+        &[],
+        &mut EmitEventBumps {},
+        &mut BTreeSet::new(),
+    )?;
+    handler(
+        Context::new(&ID, &mut accounts, &[], EmitEventBumps {}),
+        arg,
+    )
+}
+
+#[test]
+fn test_emit_to_account() {
+    let info = init(3, 32);
+    let events = RingBuffer::try_from(&ID, info).unwrap();
+    assert_eq!(events.capacity(), 3);
+    assert_eq!(events.slot_len(), 32);
+    assert!(events.is_empty());
+
+    for data in 0..2 {
+        emit(info, ring_buffer_program::emit_event, data).unwrap();
+    }
+    emit(info, ring_buffer_program::emit_other_event, "hi".into()).unwrap();
+    assert_eq!(events.len(), 3);
+    assert_eq!(
+        events.decode::<MyEvent>(),
+        [MyEvent { data: 0 }, MyEvent { data: 1 }]
+    );
+
+    // The oldest events are overwritten once the buffer is full
+    for data in 2..4 {
+        emit(info, ring_buffer_program::emit_event, data).unwrap();
+    }
+    assert_eq!(events.head(), 5);
+    assert_eq!(events.len(), 3);
+    assert_eq!(events.get(1), None);
+    assert_eq!(events.get(5), None);
+    assert_eq!(events.get(4), Some(MyEvent { data: 3 }.data()));
+    assert_eq!(
+        events.decode::<MyOtherEvent>(),
+        [MyOtherEvent { data: "hi".into() }]
+    );
+    assert_eq!(
+        events.decode::<MyEvent>(),
+        [MyEvent { data: 2 }, MyEvent { data: 3 }]
+    );
+    assert_eq!(events.events().len(), 3);
+}
+
+#[test]
+fn test_emit_to_account_errors() {
+    let info = init(2, 16);
+    assert_eq!(
+        emit(info, ring_buffer_program::emit_other_event, "a".repeat(32)).unwrap_err(),
+        ErrorCode::EventTooLargeForRingBuffer.into()
+    );
+    assert!(RingBuffer::try_from(&ID, info).unwrap().is_empty());
+
+    // Uninitialized buffers are rejected
+    let info = account_info(&ID, vec![0; RingBuffer::space(2, 16)]);
+    assert_eq!(
+        emit(info, ring_buffer_program::emit_event, 0).unwrap_err(),
+        ErrorCode::AccountDiscriminatorMismatch.into()
+    );
+    let events = RingBuffer::try_from_unchecked(&ID, info).unwrap();
+    assert_eq!(
+        events.push(&[]).unwrap_err(),
+        ErrorCode::AccountNotInitialized.into()
+    );
+
+    // Buffers can only be initialized once, with room for at least one event
+    events.initialize(16).unwrap();
+    assert_eq!(&info.data.borrow()[..8], RING_BUFFER_DISCRIMINATOR);
+    assert_eq!(
+        events.initialize(16).unwrap_err(),
+        ErrorCode::AccountDiscriminatorAlreadySet.into()
+    );
+    let info = account_info(&ID, vec![0; RingBuffer::space(1, 16) - 1]);
+    assert_eq!(
+        RingBuffer::try_from_unchecked(&ID, info)
+            .unwrap()
+            .initialize(16)
+            .unwrap_err(),
+        ErrorCode::RingBufferTooSmall.into()
+    );
+
+    // Buffers must be owned by the program
+    let owner = Box::leak(Box::new(Pubkey::new_unique()));
+    let info = account_info(owner, vec![0; RingBuffer::space(2, 16)]);
+    assert_eq!(
+        RingBuffer::try_from_unchecked(&ID, info).unwrap_err(),
+        ErrorCode::AccountOwnedByWrongProgram.into()
+    );
+}
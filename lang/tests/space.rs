@@ -147,3 +147,11 @@ fn test_full_path() {
 fn test_const() {
     assert_eq!(TestConst::INIT_SPACE, (4 + 10) + 10)
 }
+
+#[test]
+fn test_account_discriminator() {
+    assert_eq!(
+        anchor_lang::account_discriminator("TestBasicVarAccount"),
+        <TestBasicVarAccount as anchor_lang::Discriminator>::DISCRIMINATOR
+    )
+}
@@ -366,6 +366,114 @@ pub mod bench {
     pub fn unchecked_account8(_ctx: Context<UncheckedAccount8>) -> Result<()> {
         Ok(())
     }
+
+    pub fn emit1(_ctx: Context<Emit>) -> Result<()> {
+        emit!(BenchEvent { data: 1 });
+        Ok(())
+    }
+
+    pub fn emit2(_ctx: Context<Emit>) -> Result<()> {
+        emit_many!([BenchEvent { data: 1 }, BenchEvent { data: 2 }]);
+        Ok(())
+    }
+
+    pub fn emit4(_ctx: Context<Emit>) -> Result<()> {
+        emit_many!([BenchEvent { data: 1 }, BenchEvent { data: 2 }, BenchEvent { data: 3 }, BenchEvent { data: 4 }]);
+        Ok(())
+    }
+
+    pub fn emit8(_ctx: Context<Emit>) -> Result<()> {
+        emit_many!([BenchEvent { data: 1 }, BenchEvent { data: 2 }, BenchEvent { data: 3 }, BenchEvent { data: 4 }, BenchEvent { data: 5 }, BenchEvent { data: 6 }, BenchEvent { data: 7 }, BenchEvent { data: 8 }]);
+        Ok(())
+    }
+
+    pub fn emit_sized8(_ctx: Context<Emit>) -> Result<()> {
+        emit!(SizedEvent8 { data: [1; 8] });
+        Ok(())
+    }
+
+    pub fn emit_sized64(_ctx: Context<Emit>) -> Result<()> {
+        emit!(SizedEvent64 { data: [1; 64] });
+        Ok(())
+    }
+
+    pub fn emit_sized256(_ctx: Context<Emit>) -> Result<()> {
+        emit!(SizedEvent256 { data: [1; 256] });
+        Ok(())
+    }
+
+    pub fn emit_sized1024(_ctx: Context<Emit>) -> Result<()> {
+        emit!(SizedEvent1024 { data: [1; 1024] });
+        Ok(())
+    }
+
+    // Zeroed events compress best with the default codec of `emit_compressed!`, the compute
+    // units of `emit!` don't depend on the content of the event
+    pub fn emit_compressed_sized8(_ctx: Context<Emit>) -> Result<()> {
+        emit_compressed!(SizedEvent8 { data: [0; 8] });
+        Ok(())
+    }
+
+    pub fn emit_compressed_sized64(_ctx: Context<Emit>) -> Result<()> {
+        emit_compressed!(SizedEvent64 { data: [0; 64] });
+        Ok(())
+    }
+
+    pub fn emit_compressed_sized256(_ctx: Context<Emit>) -> Result<()> {
+        emit_compressed!(SizedEvent256 { data: [0; 256] });
+        Ok(())
+    }
+
+    pub fn emit_compressed_sized1024(_ctx: Context<Emit>) -> Result<()> {
+        emit_compressed!(SizedEvent1024 { data: [0; 1024] });
+        Ok(())
+    }
+
+    pub fn emit_cpi_sized8(ctx: Context<EmitCpi>) -> Result<()> {
+        emit_cpi!(SizedEvent8 { data: [1; 8] });
+        Ok(())
+    }
+
+    pub fn emit_cpi_sized64(ctx: Context<EmitCpi>) -> Result<()> {
+        emit_cpi!(SizedEvent64 { data: [1; 64] });
+        Ok(())
+    }
+
+    pub fn emit_cpi_sized256(ctx: Context<EmitCpi>) -> Result<()> {
+        emit_cpi!(SizedEvent256 { data: [1; 256] });
+        Ok(())
+    }
+
+    pub fn emit_cpi_sized1024(ctx: Context<EmitCpi>) -> Result<()> {
+        emit_cpi!(SizedEvent1024 { data: [1; 1024] });
+        Ok(())
+    }
+}
+
+#[event]
+pub struct BenchEvent {
+    pub data: u64,
+}
+
+// Events with `N` bytes of data to compare the cost of the emit macros by event size
+#[event]
+pub struct SizedEvent8 {
+    pub data: [u8; 8],
+}
+
+#[event]
+pub struct SizedEvent64 {
+    pub data: [u8; 64],
+}
+
+#[event]
+pub struct SizedEvent256 {
+    pub data: [u8; 256],
+}
+
+#[event]
+pub struct SizedEvent1024 {
+    pub data: [u8; 1024],
 }
 
 #[account]
@@ -1212,3 +1320,10 @@ pub struct UncheckedAccount8<'info> {
     pub account7: UncheckedAccount<'info>,
     pub account8: UncheckedAccount<'info>,
 }
+
+#[derive(Accounts)]
+pub struct Emit {}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct EmitCpi {}
@@ -38,10 +38,13 @@ import {
 
     // Update the anchor dependency versions
     for (const dependency of ["lang", "spl"]) {
+      // `emit_cpi!` benchmarks require the `event-cpi` feature
+      const features =
+        dependency === "lang" ? `, features = ["event-cpi"]` : "";
       cargoToml.replaceValue(`anchor-${dependency}`, () => {
         return isUnreleased
-          ? `{ path = "../../../../${dependency}" }`
-          : `"${version}"`;
+          ? `{ path = "../../../../${dependency}"${features} }`
+          : `{ version = "${version}"${features} }`;
       });
     }
 
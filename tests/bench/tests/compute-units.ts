@@ -221,6 +221,27 @@ describe("Compute units", () => {
     await measureComputeUnits("uncheckedAccount");
   });
 
+  it("Emit", async () => {
+    await measureComputeUnits("emit");
+  });
+
+  // The suffixes of the sized instructions are the sizes of the event data in bytes
+  const EVENT_SIZES = [8, 64, 256, 1024];
+
+  it("Emit Sized", async () => {
+    await measureComputeUnits("emitSized", { accountCounts: EVENT_SIZES });
+  });
+
+  it("Emit Compressed Sized", async () => {
+    await measureComputeUnits("emitCompressedSized", {
+      accountCounts: EVENT_SIZES,
+    });
+  });
+
+  it("Emit CPI Sized", async () => {
+    await measureComputeUnits("emitCpiSized", { accountCounts: EVENT_SIZES });
+  });
+
   after(async () => {
     const bench = await BenchData.open();
     await bench.update({ computeUnits });
@@ -24,6 +24,22 @@ pub mod events {
         Ok(())
     }
 
+    pub fn test_event_order(_ctx: Context<TestEvent>) -> Result<()> {
+        emit!(MyEvent {
+            data: 1,
+            label: "first".to_string(),
+        });
+        emit!(MyOtherEvent {
+            data: 2,
+            label: "second".to_string(),
+        });
+        emit!(MyEvent {
+            data: 3,
+            label: "third".to_string(),
+        });
+        Ok(())
+    }
+
     pub fn test_event_cpi(ctx: Context<TestEventCpi>) -> Result<()> {
         emit_cpi!(MyOtherEvent {
             data: 7,
@@ -31,8 +47,30 @@ pub mod events {
         });
         Ok(())
     }
+
+    pub fn test_event_cpi_trait(ctx: Context<TestEventCpi>) -> Result<()> {
+        Notifier::notify(&ctx)
+    }
 }
 
+/// Handlers that are shared between instructions can emit events through CPI by being generic
+/// over accounts that implement `EventCpiAccountInfos`.
+pub trait Notify {
+    fn notify<'info, T: anchor_lang::event::EventCpiAccountInfos<'info>>(
+        ctx: &Context<'_, '_, '_, '_, T>,
+    ) -> Result<()> {
+        emit_cpi!(MyOtherEvent {
+            data: 8,
+            label: "cpi trait".to_string(),
+        });
+        Ok(())
+    }
+}
+
+pub struct Notifier;
+
+impl Notify for Notifier {}
+
 #[derive(Accounts)]
 pub struct Initialize {}
 
@@ -0,0 +1,62 @@
+#![cfg(feature = "test-sbf")]
+
+use {
+    anchor_client::{
+        anchor_lang::{Discriminator, Event, InstructionData, ToAccountMetas},
+        decode_events_from_logs,
+        solana_sdk::{instruction::Instruction, signature::Signer, transaction::Transaction},
+        EventParser,
+    },
+    events::{MyEvent, MyOtherEvent},
+    solana_program_test::{tokio, ProgramTest},
+};
+
+#[tokio::test]
+async fn events_are_logged_in_emission_order() {
+    let pt = ProgramTest::new("events", events::id(), None);
+    let (mut banks_client, payer, recent_blockhash) = pt.start().await;
+
+    let ix = Instruction {
+        program_id: events::id(),
+        accounts: events::accounts::TestEvent {}.to_account_metas(None),
+        data: events::instruction::TestEventOrder {}.data(),
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+
+    let result = banks_client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    result.result.unwrap();
+    let logs = result.metadata.unwrap().log_messages;
+
+    // All three events are logged by the program, in the order they were emitted
+    let events = EventParser.parse_logs(&logs);
+    assert_eq!(events.len(), 3);
+    assert!(events
+        .iter()
+        .all(|(program_id, _)| *program_id == events::id()));
+
+    let labels = events
+        .iter()
+        .map(|(_, data)| {
+            if data.starts_with(&MyEvent::DISCRIMINATOR) {
+                MyEvent::try_from_event_data(data).unwrap().label
+            } else {
+                MyOtherEvent::try_from_event_data(data).unwrap().label
+            }
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(labels, ["first", "second", "third"]);
+
+    let data = decode_events_from_logs::<MyEvent>(&logs)
+        .into_iter()
+        .map(|event| event.data)
+        .collect::<Vec<_>>();
+    assert_eq!(data, [1, 3]);
+}
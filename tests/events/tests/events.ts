@@ -66,6 +66,26 @@ describe("Events", () => {
       assert.strictEqual((event.data.data as anchor.BN).toNumber(), 7);
     });
 
+    it("Works from handlers generic over their accounts", async () => {
+      const tx = await program.methods.testEventCpiTrait().transaction();
+      const config = { commitment: "confirmed" } as const;
+      const txHash = await program.provider.sendAndConfirm(tx, [], config);
+      const txResult = await program.provider.connection.getTransaction(
+        txHash,
+        config
+      );
+
+      const ixData = anchor.utils.bytes.bs58.decode(
+        txResult.meta.innerInstructions[0].instructions[0].data
+      );
+      const eventData = anchor.utils.bytes.base64.encode(ixData.slice(8));
+      const event = program.coder.events.decode(eventData);
+
+      assert.strictEqual(event.name, "MyOtherEvent");
+      assert.strictEqual(event.data.label, "cpi trait");
+      assert.strictEqual((event.data.data as anchor.BN).toNumber(), 8);
+    });
+
     it("Throws on unauthorized invocation", async () => {
       const tx = new anchor.web3.Transaction();
       tx.add(
@@ -322,11 +322,22 @@ mod some_other_module {
 
 #[event]
 pub struct SomeEvent {
+    /// Doc comments of event fields are included in the IDL,
+    /// one entry per line.
     bool_field: bool,
     external_baz: external::Baz,
     other_module_baz: some_other_module::Baz,
 }
 
+// Fields of tuple struct events are named by their position
+#[event]
+pub struct TupleEvent(pub u64, pub Pubkey);
+
+// Events declared in file modules of inline modules are also included in the IDL
+pub mod nested {
+    pub mod events;
+}
+
 #[zero_copy]
 pub struct ZcStruct {
     pub some_field: u16,
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct NestedEvent {
+    #[decimals(6)]
+    pub data: u64,
+    pub label: String,
+    #[index(name = "owner_key")]
+    pub owner_account: Pubkey,
+    #[index]
+    pub owner: Option<Pubkey>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EventHeader {
+    pub slot: u64,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct FlattenedEvent {
+    #[event_flatten]
+    pub header: EventHeader,
+    pub amount: u64,
+}
+
+// Not part of the IDL
+#[event(hidden)]
+pub struct DebugEvent {
+    pub step: u64,
+}
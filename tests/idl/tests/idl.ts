@@ -34,4 +34,44 @@ describe("IDL", () => {
     // @ts-expect-error
     assert.isUndefined(program.idl.constants.find((c) => c.name === "NO_IDL"));
   });
+
+  it("Includes events declared in other modules", () => {
+    const eventNames = program.idl.events.map((e) => e.name);
+    assert.include(eventNames, "SomeEvent");
+    assert.include(eventNames, "NestedEvent");
+  });
+
+  it("Does not include hidden events", () => {
+    const eventNames = program.idl.events.map((e) => e.name);
+    assert.notInclude(eventNames, "DebugEvent");
+    assert.isUndefined(program.idl.types.find((t) => t.name === "DebugEvent"));
+  });
+
+  it("Includes the Rust names of event fields", () => {
+    const event = program.idl.events.find((e) => e.name === "NestedEvent");
+    const field = event.fields.find((f) => f.name === "ownerAccount");
+    assert.strictEqual(field.rustName, "owner_account");
+    // Index names are not converted to camelCase
+    assert.strictEqual(field.indexName, "owner_key");
+
+    const label = event.fields.find((f) => f.name === "label");
+    assert.isUndefined(label.rustName);
+  });
+
+  it("Includes the decimals of event fields", () => {
+    const event = program.idl.events.find((e) => e.name === "NestedEvent");
+    const data = event.fields.find((f) => f.name === "data");
+    assert.strictEqual(data.decimals, 6);
+
+    const label = event.fields.find((f) => f.name === "label");
+    assert.isUndefined(label.decimals);
+  });
+
+  it("Flattens `#[event_flatten]` event fields", () => {
+    const event = program.idl.events.find((e) => e.name === "FlattenedEvent");
+    assert.deepEqual(
+      event.fields.map((f) => f.name),
+      ["slot", "authority", "amount"]
+    );
+  });
 });
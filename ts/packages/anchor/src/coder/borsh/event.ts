@@ -18,9 +18,15 @@ export class BorshEventCoder implements EventCoder {
    */
   private discriminators: Map<string, string>;
 
+  /**
+   * Names of the events with a correlation id slot after their discriminator.
+   */
+  private correlated: Set<string>;
+
   public constructor(idl: Idl) {
     if (idl.events === undefined) {
       this.layouts = new Map();
+      this.correlated = new Set();
       return;
     }
     const layouts: [string, Layout<any>][] = idl.events.map((event) => {
@@ -36,12 +42,19 @@ export class BorshEventCoder implements EventCoder {
       return [event.name, IdlCoder.typeDefLayout(eventTypeDef, idl.types)];
     });
     this.layouts = new Map(layouts);
+    this.correlated = new Set(
+      idl.events.filter((e) => e.correlated).map((e) => e.name)
+    );
 
     this.discriminators = new Map<string, string>(
       idl.events === undefined
         ? []
         : idl.events.map((e) => [
-            base64.encode(eventDiscriminator(e.name)),
+            base64.encode(
+              e.discriminator
+                ? Buffer.from(e.discriminator.slice(0, 8))
+                : eventDiscriminator(e.name)
+            ),
             e.name,
           ])
     );
@@ -69,14 +82,29 @@ export class BorshEventCoder implements EventCoder {
     if (!layout) {
       throw new Error(`Unknown event: ${eventName}`);
     }
-    const data = layout.decode(logArr.slice(8)) as EventData<
-      E["fields"][number],
-      T
-    >;
-    return { data, name: eventName };
+    let payload = logArr.slice(8);
+    let correlationId: Buffer | undefined;
+    if (this.correlated.has(eventName)) {
+      // `Option<[u8; 16]>` slot of `#[event(correlated)]` events
+      if (payload[0] === 1 && payload.length >= 1 + CORRELATION_ID_LEN) {
+        correlationId = payload.slice(1, 1 + CORRELATION_ID_LEN);
+        payload = payload.slice(1 + CORRELATION_ID_LEN);
+      } else if (payload[0] === 0) {
+        payload = payload.slice(1);
+      } else {
+        throw new Error(`Invalid correlation id of event: ${eventName}`);
+      }
+    }
+
+    const data = layout.decode(payload) as EventData<E["fields"][number], T>;
+    return correlationId === undefined
+      ? { data, name: eventName }
+      : { data, name: eventName, correlationId };
   }
 }
 
+const CORRELATION_ID_LEN = 16;
+
 export function eventDiscriminator(name: string): Buffer {
   return discriminator(`event:${name}`);
 }
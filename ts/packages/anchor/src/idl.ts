@@ -25,13 +25,37 @@ export type IdlConstant = {
 
 export type IdlEvent = {
   name: string;
+  discriminator?: number[];
+  /**
+   * Schema version serialized after the discriminator, only set for events
+   * declared with `#[event(version = <N>)]`.
+   */
+  version?: number;
+  /**
+   * Whether an `Option<[u8; 16]>` correlation id slot follows the
+   * discriminator, only set for events declared with `#[event(correlated)]`.
+   */
+  correlated?: boolean;
   fields: IdlEventField[];
+  via?: IdlEventVia;
 };
 
+/**
+ * How an event is emitted, i.e. whether it's found in the `Program data:` logs
+ * of `emit!` or in the self-CPI inner instructions of `emit_cpi!`. Events
+ * without `via` are logged.
+ */
+export type IdlEventVia = "log" | "cpi";
+
 export type IdlEventField = {
   name: string;
+  rustName?: string;
+  docs?: string[];
   type: IdlType;
   index: boolean;
+  indexName?: string;
+  optional?: boolean;
+  decimals?: number;
 };
 
 export type IdlInstruction = {
@@ -261,7 +261,10 @@ export class AccountsResolver<IDL extends Idl> {
         const currentPath = [...path, currentName];
         const nextPath = [...path, nextName];
 
-        if (!this.get(currentPath)) {
+        // Event authorities with a seed suffix are resolved from their PDA seeds
+        const hasSeedSuffix =
+          ((accounts[i] as IdlAccount).pda?.seeds.length ?? 0) > 1;
+        if (!this.get(currentPath) && !hasSeedSuffix) {
           this.set(
             currentPath,
             PublicKey.findProgramAddressSync(
@@ -1,3 +1,4 @@
+import { Buffer } from "buffer";
 import { PublicKey } from "@solana/web3.js";
 import { IdlEvent, IdlEventField } from "../idl.js";
 import { Coder } from "../coder/index.js";
@@ -16,6 +17,11 @@ export type Event<
 > = {
   name: E["name"];
   data: EventData<E["fields"][number], Defined>;
+  /**
+   * Correlation id of `#[event(correlated)]` events emitted through a
+   * `Context` with a correlation id.
+   */
+  correlationId?: Buffer;
 };
 
 export type EventData<T extends IdlEventField, Defined> = {
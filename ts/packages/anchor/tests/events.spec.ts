@@ -1,6 +1,8 @@
+import { Buffer } from "buffer";
 import { PublicKey } from "@solana/web3.js";
 import { EventParser } from "../src/program/event";
 import { BorshCoder } from "../src";
+import { eventDiscriminator } from "../src/coder/borsh/event";
 
 describe("Events", () => {
   it("Parses multiple instructions", () => {
@@ -277,4 +279,61 @@ describe("Events", () => {
       throw new Error("Should never find logs");
     }
   });
+  it("Decodes the correlation id of correlated events", () => {
+    const idl = {
+      version: "0.0.0",
+      name: "basic_3",
+      instructions: [],
+      events: [
+        {
+          name: "Swapped",
+          correlated: true,
+          fields: [{ name: "amount", type: "u8" as "u8", index: false }],
+        },
+        {
+          name: "Deposited",
+          fields: [
+            { name: "flag", type: "u8" as "u8", index: false },
+            { name: "amount", type: "u8" as "u8", index: false },
+          ],
+        },
+      ],
+    };
+    const coder = new BorshCoder(idl);
+    const swapped = eventDiscriminator("Swapped");
+    const correlationId = Buffer.alloc(16, 7);
+
+    const correlated = coder.events.decode(
+      Buffer.concat([
+        swapped,
+        Buffer.from([1]),
+        correlationId,
+        Buffer.from([5]),
+      ]).toString("base64")
+    );
+    expect(correlated?.data.amount).toEqual(5);
+    expect(correlated?.correlationId).toEqual(correlationId);
+
+    const uncorrelated = coder.events.decode(
+      Buffer.concat([swapped, Buffer.from([0, 5])]).toString("base64")
+    );
+    expect(uncorrelated?.data.amount).toEqual(5);
+    expect(uncorrelated?.correlationId).toBeUndefined();
+
+    expect(() =>
+      coder.events.decode(
+        Buffer.concat([swapped, Buffer.from([2, 5])]).toString("base64")
+      )
+    ).toThrow();
+
+    // Events without the slot are never sniffed for an id
+    const deposited = eventDiscriminator("Deposited");
+    const uncorrelatedType = coder.events.decode(
+      Buffer.concat([deposited, Buffer.from([1, 5])]).toString("base64")
+    );
+    expect(uncorrelatedType?.data.flag).toEqual(1);
+    expect(uncorrelatedType?.data.amount).toEqual(5);
+    expect(uncorrelatedType?.correlationId).toBeUndefined();
+  });
+
 });